@@ -0,0 +1,87 @@
+//! Generates the `corpus_tests!` macro, which expands to one `#[test]` per
+//! `.kxi` file in the corpus.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directories of the repository that hold test programs, each named after
+/// its last component in `Expect::from_dir`.
+const DIRS: &[&str] = &[
+    "dons_tests/valid_kxi",
+    "dons_tests/invalid_kxi",
+    "cascade_kxi",
+];
+
+fn main() {
+    let manifest = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let repo = manifest.join("..");
+
+    let mut tests = String::new();
+    for dir in DIRS {
+        let path = repo.join(dir);
+        println!("cargo:rerun-if-changed={}", path.display());
+        let mut files: Vec<PathBuf> = match fs::read_dir(&path) {
+            Ok(entries) => entries
+                .map(|e| e.unwrap().path())
+                .filter(|p| p.extension().is_some_and(|e| e == "kxi"))
+                .collect(),
+            Err(_) => continue,
+        };
+        files.sort();
+        let module = dir.rsplit('/').next().unwrap();
+        writeln!(tests, "        mod {} {{", module).unwrap();
+        writeln!(tests, "            #[allow(unused_imports)]").unwrap();
+        writeln!(tests, "            use super::*;").unwrap();
+        for file in &files {
+            write_test(&mut tests, file);
+        }
+        writeln!(tests, "        }}").unwrap();
+    }
+
+    let generated = format!(
+        "/// Expands to one `#[test]` per `.kxi` file in `dons_tests` and
+/// `cascade_kxi`, each running the file through the compiler built by
+/// `$compiler`.
+///
+/// Tests are grouped in one module per corpus directory, so a single file
+/// can be run with e.g. `cargo test invalid_kxi::scope_out`.
+#[macro_export]
+macro_rules! corpus_tests {{
+    ($compiler:expr) => {{
+{}    }};
+}}
+",
+        tests
+    );
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("corpus_tests.rs");
+    fs::write(out, generated).unwrap();
+}
+
+fn write_test(out: &mut String, file: &Path) {
+    let stem = file.file_stem().unwrap().to_string_lossy();
+    let mut name: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert_str(0, "t_");
+    }
+    let path = file.canonicalize().unwrap();
+    writeln!(out, "            #[test]").unwrap();
+    writeln!(out, "            fn {}() {{", name).unwrap();
+    writeln!(
+        out,
+        "                $crate::assert_case(&mut $compiler, {:?});",
+        path.to_string_lossy()
+    )
+    .unwrap();
+    writeln!(out, "            }}").unwrap();
+}
@@ -0,0 +1,25 @@
+//! Prints a `sem_tests.rs` generated from the `.kxi` corpus.
+//!
+//! Usage: `gen-sem-tests [CORPUS_DIR]`, defaulting to `dons_tests`.
+
+use std::env;
+use std::process::ExitCode;
+
+use kxi_harness::Corpus;
+
+fn main() -> ExitCode {
+    let corpus = match env::args_os().nth(1) {
+        Some(dir) => Corpus::discover(dir),
+        None => Corpus::dons_tests(),
+    };
+    match corpus {
+        Ok(corpus) => {
+            print!("{}", kxi_harness::sem_tests::render(&corpus));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("gen-sem-tests: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Runs a compiler over the corpus and prints the report.
+//!
+//! Usage: `kxi-conform [--format text|junit|tap|html] [CORPUS_DIR]`,
+//! defaulting to text and `dons_tests`. The compiler is the one configured
+//! through `KXI_COMPILER` (see `External::from_env`), or the reference front
+//! end if that is not set. Exits with status 1 if any case fails, and 2 on
+//! bad arguments or a corpus that cannot be loaded.
+//!
+//! `kxi-conform --compare COMPILERS.json [CORPUS_DIR]` runs every compiler
+//! listed in the file instead (see `kxi_harness::compare::Entry`) and prints
+//! a matrix of their results, flagging the files they disagree on. It exits
+//! with status 1 if there are any.
+//!
+//! Either way the files can be narrowed down with `--phase PHASE`,
+//! `--dir DIR`, `--tag TAG` and `--name GLOB`, each of which may be given
+//! several times; see `kxi_harness::Filter`.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::process::ExitCode;
+
+use kxi_harness::compare::{self, load_compilers};
+use kxi_harness::reporter::{self, Format};
+use kxi_harness::{reference, Compiler, Corpus, External, Filter};
+
+const USAGE: &str = "usage: kxi-conform [--format text|junit|tap|html] [--compare COMPILERS.json] \
+                     [--phase PHASE] [--dir DIR] [--tag TAG] [--name GLOB] [CORPUS_DIR]";
+
+struct Options {
+    format: Format,
+    compare: Option<OsString>,
+    filter: Filter,
+    dir: Option<OsString>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = OsString>) -> Result<Options, String> {
+    let mut options = Options {
+        format: Format::Text,
+        compare: None,
+        filter: Filter::new(),
+        dir: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || -> Result<String, String> {
+            let value = args.next().ok_or(USAGE)?;
+            Ok(value.to_str().ok_or(USAGE)?.to_string())
+        };
+        match arg.to_str() {
+            Some("--format") => options.format = value()?.parse()?,
+            Some(s) if s.starts_with("--format=") => {
+                options.format = s["--format=".len()..].parse()?;
+            }
+            Some("--compare") => options.compare = Some(value()?.into()),
+            Some("--phase") => options.filter = options.filter.phase(value()?.parse()?),
+            Some("--dir") => options.filter = options.filter.dir(value()?),
+            Some("--tag") => options.filter = options.filter.tag(value()?),
+            Some("--name") => options.filter = options.filter.name(&value()?),
+            Some(s) if s.starts_with('-') => return Err(USAGE.to_string()),
+            _ if options.dir.is_none() => options.dir = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    if options.compare.is_some() && options.format != Format::Text {
+        return Err("--compare only prints text".to_string());
+    }
+    Ok(options)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args_os().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("kxi-conform: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let corpus = match options.dir {
+        Some(dir) => Corpus::discover(dir),
+        None => Corpus::dons_tests(),
+    };
+    let corpus = match corpus {
+        Ok(corpus) => corpus.filter(&options.filter),
+        Err(e) => {
+            eprintln!("kxi-conform: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    if let Some(path) = options.compare {
+        let compilers = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| load_compilers(&json));
+        let mut compilers = match compilers {
+            Ok(compilers) => compilers,
+            Err(e) => {
+                eprintln!("kxi-conform: {}: {}", path.to_string_lossy(), e);
+                return ExitCode::from(2);
+            }
+        };
+        let matrix = compare::compare(&corpus, &mut compilers);
+        print!("{}", matrix);
+        return if matrix.disagreements().is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        };
+    }
+
+    let mut compiler: Box<dyn Compiler> = match External::from_env() {
+        Some(Ok(external)) => Box::new(external),
+        Some(Err(e)) => {
+            eprintln!("kxi-conform: KXI_DIAGNOSTIC_PATTERN: {}", e);
+            return ExitCode::from(2);
+        }
+        None => Box::new(reference()),
+    };
+    let report = kxi_harness::run(&corpus, compiler.as_mut());
+    print!("{}", reporter::render(&report, options.format));
+    if report.failed() == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,25 @@
+//! Prints how many tests expect each kind of error.
+//!
+//! Usage: `kxi-coverage [CORPUS_DIR]`, defaulting to `dons_tests`.
+
+use std::env;
+use std::process::ExitCode;
+
+use kxi_harness::{Corpus, Coverage};
+
+fn main() -> ExitCode {
+    let corpus = match env::args_os().nth(1) {
+        Some(dir) => Corpus::discover(dir),
+        None => Corpus::dons_tests(),
+    };
+    match corpus {
+        Ok(corpus) => {
+            print!("{}", Coverage::of(&corpus));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("kxi-coverage: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Compares what programs do when built by the compiler under test with what
+//! they do in the reference interpreter, see `kxi_harness::differential`.
+//!
+//! Usage: `kxi-diff [--reduce] [--generate N [--seed S]] [--out DIR]
+//! [DIR...]`. The programs are the `.kxi` files in each `DIR`, and with
+//! `--generate` `N` programs from seeds `S` onwards (0 by default). With
+//! neither, they are `valid_kxi/` and `run_kxi/`. The compiler is the one
+//! configured through `KXI_COMPILER` (see `External::from_env`), or the
+//! reference itself if that is not set.
+//!
+//! `--reduce` shrinks every divergence to a small program, and `--out`
+//! writes the program of each divergence to `DIR` with `.stdin`, `.stdout`
+//! and `.exitcode` files, as in `run_kxi/`, or with the header naming its
+//! runtime error instead of `.exitcode`, as in `runtime_error_kxi/`. Exits with status 1 if any
+//! program diverges, and 2 on bad arguments.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use kxi_harness::corpus::dons_tests_dir;
+use kxi_harness::differential::{generated, load_programs};
+use kxi_harness::{golden, Backend, Differential, Divergence, External, Generator, Reference};
+
+const USAGE: &str = "usage: kxi-diff [--reduce] [--generate N [--seed S]] [--out DIR] [DIR...]";
+
+struct Options {
+    reduce: bool,
+    generate: u64,
+    seed: u64,
+    out: Option<PathBuf>,
+    dirs: Vec<PathBuf>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut reduce = false;
+    let mut generate = 0;
+    let mut seed = 0;
+    let mut out = None;
+    let mut dirs = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut number = || -> Result<u64, String> {
+            let value = args.next().ok_or(USAGE)?;
+            value
+                .parse()
+                .map_err(|_| format!("`{}` is not a number", value))
+        };
+        match arg.as_str() {
+            "--reduce" => reduce = true,
+            "--generate" => generate = number()?,
+            "--seed" => seed = number()?,
+            "--out" => out = Some(args.next().ok_or(USAGE)?.into()),
+            s if s.starts_with('-') => return Err(USAGE.to_string()),
+            _ => dirs.push(arg.into()),
+        }
+    }
+    if dirs.is_empty() && generate == 0 {
+        dirs = vec![dons_tests_dir().join("valid_kxi"), golden::dir("run_kxi")];
+    }
+    Ok(Options {
+        reduce,
+        generate,
+        seed,
+        out,
+        dirs,
+    })
+}
+
+/// Writes the reproducer of `divergence` to `dir`, as a program of
+/// `run_kxi/` or, if it stops with a runtime error, `runtime_error_kxi/`.
+fn write(dir: &Path, divergence: &Divergence) -> Result<(), String> {
+    let case = &divergence.reproducer().case;
+    let path = dir.join(format!("{}.kxi", case.name));
+    let mut files = vec![(path.with_extension("stdout"), case.stdout.clone())];
+    match case.runtime_error {
+        Some(error) => files.push((
+            path.clone(),
+            format!("// expect: runtime-error={}\n{}", error, case.source),
+        )),
+        None => files.extend([
+            (path.clone(), case.source.clone()),
+            (
+                path.with_extension("exitcode"),
+                format!("{}\n", case.exit_code),
+            ),
+        ]),
+    }
+    if !case.stdin.is_empty() {
+        files.push((path.with_extension("stdin"), case.stdin.clone()));
+    }
+    for (file, contents) in files {
+        fs::write(&file, contents).map_err(|e| format!("{}: {}", file.display(), e))?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("kxi-diff: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let mut backend: Box<dyn Backend> = match External::from_env() {
+        Some(Ok(external)) => Box::new(external),
+        Some(Err(e)) => {
+            eprintln!("kxi-diff: KXI_DIAGNOSTIC_PATTERN: {}", e);
+            return ExitCode::from(2);
+        }
+        None => Box::new(Reference),
+    };
+
+    let mut cases = Vec::new();
+    for dir in &options.dirs {
+        match load_programs(dir) {
+            Ok(programs) => cases.extend(programs),
+            Err(e) => {
+                eprintln!("kxi-diff: {}: {}", dir.display(), e);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let seeds = options.seed..options.seed + options.generate;
+    cases.extend(seeds.map(|seed| generated(&Generator::new(seed))));
+
+    // Crashes of the backend become divergences, and their messages are
+    // left on stderr. A crash of the reference ends the run.
+    let report = Differential::new()
+        .reduce(options.reduce)
+        .run(&cases, backend.as_mut());
+    print!("{}", report);
+
+    let divergences = report.divergences();
+    if let Some(out) = &options.out {
+        let written = fs::create_dir_all(out)
+            .map_err(|e| format!("{}: {}", out.display(), e))
+            .and_then(|()| divergences.iter().try_for_each(|d| write(out, d)));
+        if let Err(e) = written {
+            eprintln!("kxi-diff: {}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+    if divergences.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,88 @@
+//! Writes random well-typed KXI programs, see `kxi_harness::Generator`.
+//!
+//! Usage: `kxi-gen [--classes N] [--members N] [--statements N] [--depth N]
+//! [--count N --out DIR] SEED`. Prints the program generated from `SEED`,
+//! or with `--out` writes `count` programs, from seeds `SEED` onwards, to
+//! `DIR/gen_SEED.kxi`. Exits with status 2 on bad arguments.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use kxi_harness::Generator;
+
+const USAGE: &str = "usage: kxi-gen [--classes N] [--members N] [--statements N] [--depth N] \
+                     [--count N --out DIR] SEED";
+
+struct Options {
+    generator: Generator,
+    count: u64,
+    out: Option<PathBuf>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut generator = Generator::new(0);
+    let mut seed = None;
+    let mut count = 1;
+    let mut out = None;
+    while let Some(arg) = args.next() {
+        let mut number = || -> Result<usize, String> {
+            let value = args.next().ok_or(USAGE)?;
+            value
+                .parse()
+                .map_err(|_| format!("`{}` is not a number", value))
+        };
+        match arg.as_str() {
+            "--classes" => generator = generator.classes(number()?),
+            "--members" => generator = generator.members(number()?),
+            "--statements" => generator = generator.statements(number()?),
+            "--depth" => generator = generator.depth(number()?),
+            "--count" => count = number()? as u64,
+            "--out" => out = Some(args.next().ok_or(USAGE)?.into()),
+            s if s.starts_with('-') => return Err(USAGE.to_string()),
+            s if seed.is_none() => {
+                seed = Some(s.parse().map_err(|_| format!("`{}` is not a seed", s))?)
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let Some(seed) = seed else {
+        return Err(USAGE.to_string());
+    };
+    if count != 1 && out.is_none() {
+        return Err("--count needs --out".to_string());
+    }
+    Ok(Options {
+        generator: generator.with_seed(seed),
+        count,
+        out,
+    })
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("kxi-gen: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let Some(dir) = options.out else {
+        print!("{}", options.generator.source());
+        return ExitCode::SUCCESS;
+    };
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("kxi-gen: {}: {}", dir.display(), e);
+        return ExitCode::FAILURE;
+    }
+    let first = options.generator.seed();
+    for seed in first..first + options.count {
+        let path = dir.join(format!("gen_{}.kxi", seed));
+        if let Err(e) = fs::write(&path, options.generator.with_seed(seed).source()) {
+            eprintln!("kxi-gen: {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,156 @@
+//! Writes invalid programs made from valid ones, see `kxi_harness::mutate`.
+//!
+//! Usage: `kxi-mutate [--mutation NAME] [--max N] --out DIR PATH...`. Each
+//! `PATH` is a `.kxi` file or a directory of them, such as `valid_kxi/`.
+//! Every mutant is written to `DIR/NAME_MUTATION_N.kxi` with the tags of
+//! the file it comes from. `--mutation`, which may be given several times,
+//! keeps to the named mutations, and `--max` to at most `N` mutants of each
+//! file, spread over all of its mutants.
+//!
+//! Files the reference rejects are skipped, and so are mutants it does not
+//! report as predicted, with a note on stderr either way. Exits with status 2 on bad arguments.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use kxi_harness::{golden, mutants, reference, run_case, Header, Mutation};
+
+const USAGE: &str = "usage: kxi-mutate [--mutation NAME] [--max N] --out DIR PATH...";
+
+struct Options {
+    mutations: Vec<Mutation>,
+    max: Option<usize>,
+    out: PathBuf,
+    paths: Vec<PathBuf>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut mutations = Vec::new();
+    let mut max = None;
+    let mut out = None;
+    let mut paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mutation" => mutations.push(args.next().ok_or(USAGE)?.parse()?),
+            "--max" => {
+                let value = args.next().ok_or(USAGE)?;
+                let n = value
+                    .parse()
+                    .map_err(|_| format!("`{}` is not a number", value))?;
+                max = Some(n);
+            }
+            "--out" => out = Some(args.next().ok_or(USAGE)?.into()),
+            s if s.starts_with('-') => return Err(USAGE.to_string()),
+            _ => paths.push(arg.into()),
+        }
+    }
+    let Some(out) = out else {
+        return Err(USAGE.to_string());
+    };
+    if paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    if mutations.is_empty() {
+        mutations = Mutation::ALL.to_vec();
+    }
+    Ok(Options {
+        mutations,
+        max,
+        out,
+        paths,
+    })
+}
+
+/// The `.kxi` files named by `path`.
+fn files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    if path.is_dir() {
+        golden::programs(path).map_err(|e| format!("{}: {}", path.display(), e))
+    } else {
+        Ok(vec![path.to_path_buf()])
+    }
+}
+
+/// Writes the mutants of one file, returning how many.
+fn mutate(path: &Path, options: &Options) -> Result<usize, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let program = match kxi_ref::parse(&source) {
+        Ok(program) if kxi_ref::sema::analyze(&program).is_ok() => program,
+        _ => {
+            eprintln!(
+                "kxi-mutate: {}: rejected by the reference, skipped",
+                path.display()
+            );
+            return Ok(0);
+        }
+    };
+    let tags = Header::parse(&source).map(|h| h.tags).unwrap_or_default();
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("mutant");
+    let mut all = mutants(&program);
+    all.retain(|m| options.mutations.contains(&m.mutation));
+    if let Some(max) = options.max.filter(|&max| max < all.len()) {
+        let len = all.len();
+        all = (0..max).map(|k| all[k * len / max].clone()).collect();
+    }
+
+    let mut written = 0;
+    let mut numbers = Vec::new();
+    for mutant in all {
+        let n = numbers.iter().filter(|&&m| m == mutant.mutation).count();
+        numbers.push(mutant.mutation);
+        let name = format!(
+            "{}_{}_{}",
+            stem,
+            mutant.mutation.as_str().replace('-', "_"),
+            n
+        );
+        let case = mutant.test_case(&name, &tags);
+        if !run_case(&case, &mut reference()).passed() {
+            eprintln!(
+                "kxi-mutate: {}: {}: the reference disagrees, skipped",
+                path.display(),
+                mutant.description
+            );
+            continue;
+        }
+        let target = options.out.join(format!("{}.kxi", name));
+        fs::write(&target, &case.source).map_err(|e| format!("{}: {}", target.display(), e))?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("kxi-mutate: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    if let Err(e) = fs::create_dir_all(&options.out) {
+        eprintln!("kxi-mutate: {}: {}", options.out.display(), e);
+        return ExitCode::FAILURE;
+    }
+    let mut written = 0;
+    for path in &options.paths {
+        let result = files(path).and_then(|files| {
+            files
+                .iter()
+                .try_fold(0, |total, file| Ok(total + mutate(file, &options)?))
+        });
+        match result {
+            Ok(n) => written += n,
+            Err(e) => {
+                eprintln!("kxi-mutate: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    println!("wrote {} mutants to {}", written, options.out.display());
+    ExitCode::SUCCESS
+}
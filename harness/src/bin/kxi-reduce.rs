@@ -0,0 +1,138 @@
+//! Shrinks a program the compiler under test fails on, see
+//! `kxi_harness::reduce`.
+//!
+//! Usage: `kxi-reduce [--panics] [--out FILE] FILE`. The compiler is the one
+//! configured through `KXI_COMPILER` (see `External::from_env`), or the
+//! reference front end if that is not set. A version of the program is kept
+//! as long as the compiler fails on it the way it fails on `FILE`, judged
+//! against what the reference reports for that version (see
+//! `kxi_harness::Signature`), or with `--panics` as long as it crashes.
+//!
+//! The reduced program is printed, or written to `--out`, with the header
+//! and annotations of the corpus directory it belongs in, which is named on
+//! stderr. Exits with status 1 if the compiler does not fail on `FILE` in
+//! the first place, and 2 on bad arguments or a file that does not parse.
+
+use std::env;
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use kxi_harness::{
+    check, reduce, reference, reference_case, Compiler, External, Failure, Signature,
+};
+
+const USAGE: &str = "usage: kxi-reduce [--panics] [--out FILE] FILE";
+
+struct Options {
+    panics: bool,
+    out: Option<PathBuf>,
+    file: PathBuf,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut panics = false;
+    let mut out = None;
+    let mut file = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--panics" => panics = true,
+            "--out" => out = Some(args.next().ok_or(USAGE)?.into()),
+            s if s.starts_with('-') => return Err(USAGE.to_string()),
+            _ if file.is_none() => file = Some(arg.into()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let file = file.ok_or(USAGE)?;
+    Ok(Options { panics, out, file })
+}
+
+/// How `compiler` fails on `source`, or `None` if it does not. If only
+/// `panics` count, every crash is the same failure.
+fn failure(
+    compiler: &mut dyn Compiler,
+    name: &str,
+    source: &str,
+    panics: bool,
+) -> Option<Option<Signature>> {
+    let result = check(compiler, name, source);
+    if panics {
+        let panicked = |f: &Failure| matches!(f, Failure::Panicked(_));
+        result.failures.iter().any(panicked).then_some(None)
+    } else {
+        (!result.passed()).then(|| Some(Signature::of(&result)))
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("kxi-reduce: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let path = &options.file;
+    let program = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|source| kxi_ref::parse(&source).map_err(|e| e.to_string()));
+    let program = match program {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("kxi-reduce: {}: {}", path.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+    let mut compiler: Box<dyn Compiler> = match External::from_env() {
+        Some(Ok(external)) => Box::new(external),
+        Some(Err(e)) => {
+            eprintln!("kxi-reduce: KXI_DIAGNOSTIC_PATTERN: {}", e);
+            return ExitCode::from(2);
+        }
+        None => Box::new(reference()),
+    };
+
+    let name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("reduced")
+        .to_string();
+    let original = kxi_ref::print::program(&program);
+    // Crashes are what is being looked for, so do not print each one.
+    panic::set_hook(Box::new(|_| {}));
+    let Some(first) = failure(compiler.as_mut(), &name, &original, options.panics) else {
+        eprintln!(
+            "kxi-reduce: {}: {} does not fail on it",
+            path.display(),
+            compiler.name()
+        );
+        return ExitCode::FAILURE;
+    };
+    let mut tests = 0;
+    let reduced = reduce(&program, |source| {
+        tests += 1;
+        failure(compiler.as_mut(), &name, source, options.panics).as_ref() == Some(&first)
+    });
+    let _ = panic::take_hook();
+    let reduced = kxi_ref::print::program(&reduced);
+
+    let case = reference_case(&name, &reduced);
+    eprintln!(
+        "kxi-reduce: {} lines down to {} after {} tests, belongs in {}/",
+        original.lines().count(),
+        reduced.lines().count(),
+        tests,
+        case.dir
+    );
+    match &options.out {
+        Some(out) => {
+            if let Err(e) = fs::write(out, &case.source) {
+                eprintln!("kxi-reduce: {}: {}", out.display(), e);
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{}", case.source),
+    }
+    ExitCode::SUCCESS
+}
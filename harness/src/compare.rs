@@ -0,0 +1,228 @@
+//! Running several compilers over the same corpus and comparing them.
+//!
+//! A [`Matrix`] has a row per file and a column per compiler. Each cell says
+//! what the compiler made of the file and whether that is what the corpus
+//! expects. Files the compilers disagree on are flagged, since those are
+//! usually the places where the language is ambiguous:
+//!
+//! ```text
+//!    file                            alice       bob
+//!    valid_kxi/class_scope.kxi       ok          ok
+//! !  valid_kxi/nested_dot.kxi        ok          FAIL 1
+//! ```
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::compiler::Compiler;
+use crate::corpus::Corpus;
+use crate::external::External;
+use crate::reference::reference;
+use crate::runner::{self, CaseResult, Report};
+
+/// What a compiler did with one file, ignoring what it should have done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Accepted,
+    /// Rejected with this many errors.
+    Rejected(usize),
+    Panicked,
+}
+
+impl Verdict {
+    fn of(result: &CaseResult) -> Verdict {
+        match &result.outcome {
+            None => Verdict::Panicked,
+            Some(outcome) if outcome.is_accepted() => Verdict::Accepted,
+            Some(outcome) => Verdict::Rejected(outcome.diagnostics.len()),
+        }
+    }
+}
+
+/// The results of several compilers on the same corpus.
+#[derive(Debug, Clone)]
+pub struct Matrix {
+    pub reports: Vec<Report>,
+}
+
+impl Matrix {
+    /// Puts reports side by side. Panics unless every report covers the
+    /// same files in the same order, which reports from [`compare`] or from
+    /// [`runner::run`] on one corpus always do.
+    pub fn new(reports: Vec<Report>) -> Matrix {
+        if let Some((first, rest)) = reports.split_first() {
+            let ids = |r: &Report| r.results.iter().map(|c| c.case.id()).collect::<Vec<_>>();
+            for report in rest {
+                assert!(
+                    ids(report) == ids(first),
+                    "{} and {} were run over different files",
+                    first.compiler,
+                    report.compiler
+                );
+            }
+        }
+        Matrix { reports }
+    }
+
+    /// Number of files, i.e. of rows.
+    pub fn len(&self) -> usize {
+        self.reports.first().map_or(0, |r| r.results.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The results of every compiler on the file in row `row`.
+    pub fn row(&self, row: usize) -> impl Iterator<Item = &CaseResult> {
+        self.reports.iter().map(move |r| &r.results[row])
+    }
+
+    /// Whether the compilers disagree on the file in row `row`: they do not
+    /// all accept it, all reject it with the same number of errors, or all
+    /// panic.
+    pub fn disagree(&self, row: usize) -> bool {
+        let mut verdicts = self.row(row).map(Verdict::of);
+        let first = verdicts.next();
+        verdicts.any(|v| Some(v) != first)
+    }
+
+    /// Ids of the files the compilers disagree on.
+    pub fn disagreements(&self) -> Vec<String> {
+        (0..self.len())
+            .filter(|&row| self.disagree(row))
+            .map(|row| self.reports[0].results[row].case.id())
+            .collect()
+    }
+
+    /// Compiler names with the number of files they pass, best first.
+    /// Compilers passing as many files keep their order.
+    pub fn leaderboard(&self) -> Vec<(&str, usize)> {
+        let mut board: Vec<_> = self
+            .reports
+            .iter()
+            .map(|r| (r.compiler.as_str(), r.passed()))
+            .collect();
+        board.sort_by_key(|&(_, passed)| Reverse(passed));
+        board
+    }
+}
+
+/// Runs every compiler over `corpus`.
+pub fn compare(corpus: &Corpus, compilers: &mut [Box<dyn Compiler>]) -> Matrix {
+    let reports = compilers
+        .iter_mut()
+        .map(|c| runner::run(corpus, c.as_mut()))
+        .collect();
+    Matrix::new(reports)
+}
+
+/// One compiler in a comparison, as written in the JSON file read by
+/// [`load_compilers`]. The fields mirror the [`External`] builder:
+///
+/// ```json
+/// [
+///   {"name": "reference"},
+///   {"name": "alice", "command": ["./alice/kxic", "{file}"]},
+///   {"name": "bob", "command": ["java", "-jar", "bob.jar", "{file}"], "json": true}
+/// ]
+/// ```
+///
+/// An entry named `reference` without a command is the reference front end.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    pub name: String,
+    /// The executable followed by its arguments.
+    #[serde(default)]
+    pub command: Vec<String>,
+    /// See [`External::runs_with`].
+    #[serde(default)]
+    pub run: Vec<String>,
+    /// See [`External::diagnostic_pattern`].
+    pub pattern: Option<String>,
+    /// See [`External::json_diagnostics`].
+    #[serde(default)]
+    pub json: bool,
+}
+
+impl Entry {
+    pub fn build(&self) -> Result<Box<dyn Compiler>, String> {
+        let Some((program, args)) = self.command.split_first() else {
+            if self.name == "reference" {
+                return Ok(Box::new(reference()));
+            }
+            return Err(format!("{}: no command", self.name));
+        };
+        let mut external = External::new(program)
+            .named(&self.name)
+            .args(args)
+            .runs_with(&self.run);
+        if self.json {
+            external = external.json_diagnostics();
+        }
+        if let Some(pattern) = &self.pattern {
+            external = external
+                .diagnostic_pattern(pattern)
+                .map_err(|e| format!("{}: {}", self.name, e))?;
+        }
+        Ok(Box::new(external))
+    }
+}
+
+/// Builds the compilers listed in a JSON array of [`Entry`].
+pub fn load_compilers(json: &str) -> Result<Vec<Box<dyn Compiler>>, String> {
+    let entries: Vec<Entry> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    entries.iter().map(Entry::build).collect()
+}
+
+/// The text of a cell: `ok` or `FAIL`, followed by the number of errors
+/// reported if any, or `panic`.
+fn cell(result: &CaseResult) -> String {
+    let status = if result.passed() { "ok" } else { "FAIL" };
+    match Verdict::of(result) {
+        Verdict::Accepted => status.to_string(),
+        Verdict::Rejected(n) => format!("{} {}", status, n),
+        Verdict::Panicked => "panic".to_string(),
+    }
+}
+
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(first) = self.reports.first() else {
+            return writeln!(f, "no compilers");
+        };
+        let ids: Vec<String> = first.results.iter().map(|r| r.case.id()).collect();
+        let file_width = ids.iter().map(String::len).max().unwrap_or(0).max(4);
+        let widths: Vec<usize> = self
+            .reports
+            .iter()
+            .map(|r| r.compiler.len().max(6))
+            .collect();
+        let mut line = format!("   {:<w$}", "file", w = file_width);
+        for (report, width) in self.reports.iter().zip(&widths) {
+            line.push_str(&format!("  {:<w$}", report.compiler, w = *width));
+        }
+        writeln!(f, "{}", line.trim_end())?;
+        for (row, id) in ids.iter().enumerate() {
+            let flag = if self.disagree(row) { '!' } else { ' ' };
+            let mut line = format!("{}  {:<w$}", flag, id, w = file_width);
+            for (result, width) in self.row(row).zip(&widths) {
+                line.push_str(&format!("  {:<w$}", cell(result), w = *width));
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        writeln!(
+            f,
+            "\n{} of {} files with disagreements",
+            self.disagreements().len(),
+            self.len()
+        )?;
+        for (rank, (name, passed)) in self.leaderboard().into_iter().enumerate() {
+            writeln!(f, "{}. {}: {} passed", rank + 1, name, passed)?;
+        }
+        Ok(())
+    }
+}
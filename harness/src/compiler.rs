@@ -0,0 +1,187 @@
+//! The interface a compiler under test implements to be run over the corpus.
+//!
+//! A compiler run as a separate process can report its diagnostics as JSON,
+//! one object per line of stderr, with every field but `message` optional:
+//!
+//! ```text
+//! {"line":7,"column":13,"phase":"typecheck","kind":"type-mismatch","message":"cannot add int and bool"}
+//! ```
+//!
+//! `phase` is spelled as in expectation headers and `kind` as in error
+//! annotations, see [`Phase`] and [`Kind`].
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::corpus::TestCase;
+use crate::kind::Kind;
+use crate::phase::Phase;
+
+/// A single error reported by the compiler under test.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// 1-based source line, if the compiler reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 1-based column, if the compiler reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// The pass that reported the error, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<Phase>,
+    /// What is wrong, if the compiler says. A diagnostic with a kind only
+    /// satisfies annotations of the same kind.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<Kind>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            line: None,
+            column: None,
+            phase: None,
+            kind: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn at(line: usize, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            line: Some(line),
+            column: None,
+            phase: None,
+            kind: None,
+            message: message.into(),
+        }
+    }
+
+    /// Records the pass that reported this error.
+    pub fn in_phase(mut self, phase: Phase) -> Diagnostic {
+        self.phase = Some(phase);
+        self
+    }
+
+    pub fn with_kind(mut self, kind: Kind) -> Diagnostic {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn with_column(mut self, column: usize) -> Diagnostic {
+        self.column = Some(column);
+        self
+    }
+
+    /// The diagnostic as one line of JSON, leaving out unknown fields.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a diagnostic is always valid JSON")
+    }
+
+    pub fn from_json(json: &str) -> Result<Diagnostic, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Reads the diagnostics in a compiler's output: every line that starts
+/// with `{` must be one JSON diagnostic, other lines are ignored.
+pub fn parse_json_diagnostics(text: &str) -> Result<Vec<Diagnostic>, serde_json::Error> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('{'))
+        .map(Diagnostic::from_json)
+        .collect()
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{}:{}: ", line, column)?,
+            (Some(line), None) => write!(f, "{}: ", line)?,
+            _ => {}
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(kind) = self.kind {
+            write!(f, " [{}]", kind)?;
+        }
+        if let Some(phase) = self.phase {
+            write!(f, " ({})", phase)?;
+        }
+        Ok(())
+    }
+}
+
+/// What the compiler did with one program.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outcome {
+    /// Every error reported. An empty list means the program was accepted.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Outcome {
+    pub fn accepted() -> Outcome {
+        Outcome::default()
+    }
+
+    pub fn rejected(diagnostics: Vec<Diagnostic>) -> Outcome {
+        Outcome { diagnostics }
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+/// A compiler front end that can be run over the corpus.
+///
+/// Implement this once for your compiler and hand it to [`crate::run`].
+/// Compilers built from the passes used in `sem_tests.rs` are easier to hook
+/// up through [`crate::FrontEnd`].
+pub trait Compiler {
+    /// Short name used in reports.
+    fn name(&self) -> &str;
+
+    /// Compiles one test program and reports every error found.
+    fn compile(&mut self, case: &TestCase) -> Outcome;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_round_trips() {
+        let diagnostic = Diagnostic::at(7, "cannot add int and bool")
+            .with_column(13)
+            .in_phase(Phase::TypeCheck)
+            .with_kind(Kind::TypeMismatch);
+        let json = diagnostic.to_json();
+        assert_eq!(
+            json,
+            r#"{"line":7,"column":13,"phase":"typecheck","kind":"type-mismatch","message":"cannot add int and bool"}"#
+        );
+        assert_eq!(Diagnostic::from_json(&json).unwrap(), diagnostic);
+        assert_eq!(
+            diagnostic.to_string(),
+            "7:13: cannot add int and bool [type-mismatch] (typecheck)"
+        );
+    }
+
+    #[test]
+    fn json_fields_are_optional_but_message() {
+        let diagnostic = Diagnostic::from_json(r#"{"message":"oops"}"#).unwrap();
+        assert_eq!(diagnostic, Diagnostic::new("oops"));
+        assert_eq!(diagnostic.to_json(), r#"{"message":"oops"}"#);
+        assert!(Diagnostic::from_json(r#"{"line":3}"#).is_err());
+        assert!(Diagnostic::from_json(r#"{"kind":"TypeMismatch","message":"x"}"#).is_err());
+    }
+
+    #[test]
+    fn json_lines_skip_other_output() {
+        let text = "compiling p.kxi\n{\"line\":2,\"message\":\"a\"}\n  {\"message\":\"b\"}\n";
+        let diagnostics = parse_json_diagnostics(text).unwrap();
+        assert_eq!(diagnostics, [Diagnostic::at(2, "a"), Diagnostic::new("b")]);
+        assert!(parse_json_diagnostics("{not json\n").is_err());
+    }
+}
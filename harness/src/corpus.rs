@@ -0,0 +1,286 @@
+//! Discovery of `.kxi` test programs on disk.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::expect::{parse_annotations, Annotation, Header};
+use crate::phase::Phase;
+
+/// Directory of programs with a single root error, which must be reported
+/// exactly once however many times the faulty name or type is used after it.
+pub const CASCADE_DIR: &str = "cascade_kxi";
+
+/// What the compiler under test is expected to do with a program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expect {
+    /// The program is well formed and must compile without diagnostics.
+    Accept,
+    /// The program is wrong and must be rejected with at least one diagnostic.
+    Reject,
+}
+
+impl Expect {
+    /// Maps a corpus directory name to the expectation of the files inside it.
+    pub fn from_dir(dir: &str) -> Option<Expect> {
+        match dir {
+            "valid_kxi" => Some(Expect::Accept),
+            "invalid_kxi" | CASCADE_DIR => Some(Expect::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// A single `.kxi` program from the corpus.
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    /// File stem, e.g. `bad_dec_type_check`.
+    pub name: String,
+    /// Name of the directory the file was found in, e.g. `invalid_kxi`.
+    pub dir: String,
+    /// Full path to the file.
+    pub path: PathBuf,
+    /// Contents of the file.
+    pub source: String,
+    pub expect: Expect,
+    /// Expectations declared in the file itself.
+    pub header: Header,
+    /// Errors pinned to particular lines, in source order.
+    pub annotations: Vec<Annotation>,
+}
+
+impl TestCase {
+    /// Loads a single file, deriving its expectation from the parent directory.
+    pub fn load(path: &Path) -> io::Result<TestCase> {
+        let dir = path
+            .parent()
+            .and_then(Path::file_name)
+            .and_then(|d| d.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let expect = Expect::from_dir(&dir).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not in a known corpus directory", path.display()),
+            )
+        })?;
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let source = fs::read_to_string(path)?;
+        let invalid = |message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), message),
+            )
+        };
+        let header = Header::parse(&source).map_err(|e| invalid(e.to_string()))?;
+        let annotations = parse_annotations(&source).map_err(|e| invalid(e.to_string()))?;
+        if !annotations.is_empty() {
+            if expect == Expect::Accept {
+                return Err(invalid("valid program has error annotations".to_string()));
+            }
+            if header.errors.is_some_and(|n| n != annotations.len()) {
+                return Err(invalid(format!(
+                    "header expects {} errors but {} are annotated",
+                    header.errors.unwrap_or_default(),
+                    annotations.len()
+                )));
+            }
+        }
+        if dir == CASCADE_DIR && annotations.len() != 1 {
+            return Err(invalid(format!(
+                "{} programs must annotate exactly one error, found {}",
+                CASCADE_DIR,
+                annotations.len()
+            )));
+        }
+        Ok(TestCase {
+            name,
+            dir,
+            path: path.to_path_buf(),
+            source,
+            expect,
+            header,
+            annotations,
+        })
+    }
+
+    /// `dir/name.kxi`, the identifier used in reports.
+    pub fn id(&self) -> String {
+        format!("{}/{}.kxi", self.dir, self.name)
+    }
+}
+
+/// Every test program found under a root directory.
+#[derive(Debug, Clone)]
+pub struct Corpus {
+    pub root: PathBuf,
+    pub cases: Vec<TestCase>,
+}
+
+impl Corpus {
+    /// Recursively collects `.kxi` files below `root`.
+    ///
+    /// Only files whose parent directory is a known corpus directory
+    /// (see [`Expect::from_dir`]) are collected. Cases are sorted by id so
+    /// reports are stable between runs.
+    pub fn discover(root: impl AsRef<Path>) -> io::Result<Corpus> {
+        let root = root.as_ref().to_path_buf();
+        let mut paths = Vec::new();
+        collect(&root, &mut paths)?;
+        let mut cases = paths
+            .iter()
+            .map(|p| TestCase::load(p))
+            .collect::<io::Result<Vec<_>>>()?;
+        cases.sort_by_key(TestCase::id);
+        Ok(Corpus { root, cases })
+    }
+
+    /// The `dons_tests` corpus shipped alongside this crate.
+    pub fn dons_tests() -> io::Result<Corpus> {
+        Corpus::discover(dons_tests_dir())
+    }
+
+    /// The `cascade_kxi` corpus of programs with a single root error.
+    pub fn cascade() -> io::Result<Corpus> {
+        Corpus::discover(cascade_dir())
+    }
+
+    /// The cases selected by `filter`.
+    pub fn filter(&self, filter: &Filter) -> Corpus {
+        Corpus {
+            root: self.root.clone(),
+            cases: self
+                .cases
+                .iter()
+                .filter(|c| filter.matches(c))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cases.is_empty()
+    }
+}
+
+/// Selects part of a corpus, e.g. to work on one pass of a compiler.
+///
+/// A case is selected if it matches every kind of criterion given, and any
+/// one of the values given for each. An empty filter selects everything.
+///
+/// ```
+/// use kxi_harness::{Filter, Phase};
+///
+/// // typecheck tests about arrays or calls
+/// let filter = Filter::new()
+///     .phase(Phase::TypeCheck)
+///     .tag("arrays")
+///     .tag("calls");
+/// // the double declaration tests
+/// let filter = Filter::new().name("double_dec_*");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    phases: Vec<Phase>,
+    dirs: Vec<String>,
+    tags: Vec<String>,
+    names: Vec<Regex>,
+}
+
+impl Filter {
+    pub fn new() -> Filter {
+        Filter::default()
+    }
+
+    /// Cases whose header names `phase`.
+    pub fn phase(mut self, phase: Phase) -> Filter {
+        self.phases.push(phase);
+        self
+    }
+
+    /// Cases in a directory named `dir`, e.g. `invalid_kxi`.
+    pub fn dir(mut self, dir: impl Into<String>) -> Filter {
+        self.dirs.push(dir.into());
+        self
+    }
+
+    /// Cases whose header lists `tag`.
+    pub fn tag(mut self, tag: impl Into<String>) -> Filter {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Cases whose file stem, or `dir/name.kxi` id, matches `glob`. `*`
+    /// matches any run of characters and `?` any single one.
+    pub fn name(mut self, glob: &str) -> Filter {
+        let mut pattern = String::from("^");
+        for c in glob.chars() {
+            match c {
+                '*' => pattern.push_str(".*"),
+                '?' => pattern.push('.'),
+                c => pattern.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        pattern.push('$');
+        self.names
+            .push(Regex::new(&pattern).expect("escaped globs are valid regexes"));
+        self
+    }
+
+    pub fn matches(&self, case: &TestCase) -> bool {
+        let phase =
+            self.phases.is_empty() || case.header.phase.is_some_and(|p| self.phases.contains(&p));
+        let dir = self.dirs.is_empty() || self.dirs.contains(&case.dir);
+        let tag = self.tags.is_empty() || case.header.tags.iter().any(|t| self.tags.contains(t));
+        let name = self.names.is_empty()
+            || self
+                .names
+                .iter()
+                .any(|n| n.is_match(&case.name) || n.is_match(&case.id()));
+        phase && dir && tag && name
+    }
+}
+
+/// Location of the `dons_tests` directory in this repository.
+pub fn dons_tests_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("dons_tests")
+}
+
+/// Location of the `cascade_kxi` directory in this repository.
+pub fn cascade_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join(CASCADE_DIR)
+}
+
+fn collect(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect(&path, out)?;
+        } else if path.extension().is_some_and(|e| e == "kxi") {
+            let known = path
+                .parent()
+                .and_then(Path::file_name)
+                .and_then(|d| d.to_str())
+                .and_then(Expect::from_dir)
+                .is_some();
+            if known {
+                out.push(path);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+//! Which kinds of error a corpus exercises.
+//!
+//! Every annotation in an invalid test names a [`Kind`]. Counting them per
+//! kind shows where the corpus is thin, and which kinds no test asks for at
+//! all:
+//!
+//! ```text
+//! kind                    tests  errors
+//! type-mismatch              13      36
+//! ...
+//! unreachable-code            0       0
+//!
+//! no test exercises: syntax-error, unknown-type, private-access, misplaced-break, unreachable-code
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::corpus::{Corpus, Expect};
+use crate::kind::Kind;
+
+/// How often each kind of error is annotated in a corpus.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    tests: BTreeMap<Kind, Vec<String>>,
+    errors: BTreeMap<Kind, usize>,
+    unannotated: Vec<String>,
+}
+
+impl Coverage {
+    pub fn of(corpus: &Corpus) -> Coverage {
+        let mut coverage = Coverage::default();
+        for case in &corpus.cases {
+            if case.expect != Expect::Reject {
+                continue;
+            }
+            if case.annotations.is_empty() {
+                coverage.unannotated.push(case.id());
+            }
+            for annotation in &case.annotations {
+                *coverage.errors.entry(annotation.kind).or_default() += 1;
+                let tests = coverage.tests.entry(annotation.kind).or_default();
+                if tests.last() != Some(&case.id()) {
+                    tests.push(case.id());
+                }
+            }
+        }
+        coverage
+    }
+
+    /// Ids of the tests expecting at least one error of `kind`.
+    pub fn tests(&self, kind: Kind) -> &[String] {
+        self.tests.get(&kind).map_or(&[], Vec::as_slice)
+    }
+
+    /// Number of errors of `kind` expected over the whole corpus.
+    pub fn errors(&self, kind: Kind) -> usize {
+        self.errors.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Kinds no test expects, in the order of [`Kind::ALL`].
+    pub fn gaps(&self) -> Vec<Kind> {
+        Kind::ALL
+            .into_iter()
+            .filter(|k| !self.tests.contains_key(k))
+            .collect()
+    }
+
+    /// Ids of the invalid tests without a single annotation, whose errors
+    /// are not counted towards any kind.
+    pub fn unannotated(&self) -> &[String] {
+        &self.unannotated
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<22} {:>6} {:>7}", "kind", "tests", "errors")?;
+        for kind in Kind::ALL {
+            writeln!(
+                f,
+                "{:<22} {:>6} {:>7}",
+                kind.as_str(),
+                self.tests(kind).len(),
+                self.errors(kind)
+            )?;
+        }
+        let gaps = self.gaps();
+        if !gaps.is_empty() {
+            let names: Vec<_> = gaps.iter().map(|k| k.as_str()).collect();
+            writeln!(f, "\nno test exercises: {}", names.join(", "))?;
+        }
+        for id in &self.unannotated {
+            writeln!(f, "not annotated: {}", id)?;
+        }
+        Ok(())
+    }
+}
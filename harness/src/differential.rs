@@ -0,0 +1,306 @@
+//! Differential testing against the reference interpreter.
+//!
+//! Programs in `valid_kxi/` only say that a program type checks, and the
+//! ones from a [`crate::Generator`] say nothing at all about what they
+//! print. Running them in `kxi_ref`'s interpreter gives that: [`Differential`]
+//! runs each one there and through a [`Backend`], and reports the programs
+//! whose output or exit status differ:
+//!
+//! ```text
+//! ok        valid_kxi/binary_type_check.kxi
+//! DIVERGED  generated/gen_5.kxi
+//!           stdout line 1: expected `3`, got `0`
+//!           reduced to:
+//!           | void main() {
+//!           |     cout << 7 % 4;
+//!           | }
+//! kxic: 1 agreed, 1 diverged, 0 skipped
+//! ```
+//!
+//! The reference decides what each program must do: print what the
+//! interpreter prints, then exit with status 0, or, if it stops with a
+//! runtime error, fail with that error as the programs of
+//! `runtime_error_kxi/` do. Programs it rejects, and programs it does not
+//! finish within [`Differential::max_steps`], are skipped. With
+//! [`Differential::reduce`], every divergence is shrunk by
+//! [`crate::reduce()`] to a program that still fails the same way, ready for
+//! `run_kxi/`.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use kxi_ref::{print, sema, Interpreter, RuntimeErrorKind};
+
+use crate::execute::{run_program, ArtifactDir, Backend, RunCase, RunResult, DEFAULT_TIMEOUT};
+use crate::generate::Generator;
+use crate::golden::programs;
+use crate::kind::RuntimeError;
+use crate::reduce::reduce;
+
+/// Default for [`Differential::max_steps`].
+pub const DEFAULT_MAX_STEPS: u64 = 1_000_000;
+
+/// Runs programs through a backend and the reference interpreter.
+#[derive(Debug, Clone)]
+pub struct Differential {
+    max_steps: u64,
+    reduce: bool,
+}
+
+/// What became of one program.
+#[derive(Debug, Clone)]
+pub enum Comparison {
+    Agreed,
+    /// The reference cannot say what the program does, for this reason.
+    Skipped(String),
+    Diverged(Box<Divergence>),
+}
+
+/// A program on which the backend and the reference differ.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// The backend's run, judged against the reference's.
+    pub result: RunResult,
+    /// The same failure on the smallest program found, if reducing.
+    pub reduced: Option<RunResult>,
+}
+
+impl Divergence {
+    /// The smallest program showing the divergence.
+    pub fn reproducer(&self) -> &RunResult {
+        self.reduced.as_ref().unwrap_or(&self.result)
+    }
+}
+
+/// The comparisons of one backend with the reference.
+#[derive(Debug, Clone)]
+pub struct DiffReport {
+    pub backend: String,
+    /// Each program's `dir/name.kxi` and what became of it, in the order
+    /// they were given.
+    pub programs: Vec<(String, Comparison)>,
+}
+
+impl DiffReport {
+    pub fn agreed(&self) -> usize {
+        self.count(|c| matches!(c, Comparison::Agreed))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(|c| matches!(c, Comparison::Skipped(_)))
+    }
+
+    pub fn divergences(&self) -> Vec<&Divergence> {
+        self.programs
+            .iter()
+            .filter_map(|(_, c)| match c {
+                Comparison::Diverged(d) => Some(&**d),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn count(&self, pred: impl Fn(&Comparison) -> bool) -> usize {
+        self.programs.iter().filter(|(_, c)| pred(c)).count()
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (id, comparison) in &self.programs {
+            match comparison {
+                Comparison::Agreed => writeln!(f, "ok        {}", id)?,
+                Comparison::Skipped(why) => writeln!(f, "skipped   {}: {}", id, why)?,
+                Comparison::Diverged(divergence) => {
+                    writeln!(f, "DIVERGED  {}", id)?;
+                    for failure in &divergence.result.failures {
+                        writeln!(f, "          {}", failure)?;
+                    }
+                    if let Some(reduced) = &divergence.reduced {
+                        writeln!(f, "          reduced to:")?;
+                        for line in reduced.case.source.lines() {
+                            writeln!(f, "          | {}", line)?;
+                        }
+                        for failure in &reduced.failures {
+                            writeln!(f, "          {}", failure)?;
+                        }
+                    }
+                }
+            }
+        }
+        writeln!(
+            f,
+            "{}: {} agreed, {} diverged, {} skipped",
+            self.backend,
+            self.agreed(),
+            self.divergences().len(),
+            self.skipped()
+        )
+    }
+}
+
+impl Default for Differential {
+    fn default() -> Differential {
+        Differential::new()
+    }
+}
+
+impl Differential {
+    pub fn new() -> Differential {
+        Differential {
+            max_steps: DEFAULT_MAX_STEPS,
+            reduce: false,
+        }
+    }
+
+    /// How many statements, loop iterations and calls the reference runs a
+    /// program for before skipping it.
+    pub fn max_steps(mut self, steps: u64) -> Differential {
+        self.max_steps = steps;
+        self
+    }
+
+    /// Whether to shrink every divergence to a small reproducer.
+    pub fn reduce(mut self, reduce: bool) -> Differential {
+        self.reduce = reduce;
+        self
+    }
+
+    /// Compares every program through `backend`.
+    pub fn run(&self, cases: &[RunCase], backend: &mut dyn Backend) -> DiffReport {
+        let programs = cases
+            .iter()
+            .map(|case| (case.id(), self.compare(case, backend)))
+            .collect();
+        DiffReport {
+            backend: backend.name().to_string(),
+            programs,
+        }
+    }
+
+    /// Compares one program. Only its source, input and timeout are used;
+    /// what it must print comes from the reference.
+    pub fn compare(&self, case: &RunCase, backend: &mut dyn Backend) -> Comparison {
+        let result = match self.judge(case, backend) {
+            Ok(result) => result,
+            Err(why) => return Comparison::Skipped(why),
+        };
+        if result.passed() {
+            return Comparison::Agreed;
+        }
+        let reduced = if self.reduce {
+            self.reduced(&result, backend)
+        } else {
+            None
+        };
+        Comparison::Diverged(Box::new(Divergence { result, reduced }))
+    }
+
+    /// Runs `case` through `backend`, with the reference's output as what
+    /// it must print. `Err` says why the reference cannot tell.
+    fn judge(&self, case: &RunCase, backend: &mut dyn Backend) -> Result<RunResult, String> {
+        let program = kxi_ref::parse(&case.source).map_err(|e| format!("does not parse: {}", e))?;
+        if let Err(errors) = sema::analyze(&program) {
+            return Err(format!("rejected by the reference: {}", errors[0]));
+        }
+        let run = Interpreter::new(&program)
+            .stdin(case.stdin.as_str())
+            .max_steps(self.max_steps)
+            .time_limit(case.timeout)
+            .run();
+        if let Some(e) = &run.error {
+            use RuntimeErrorKind::*;
+            if matches!(e.kind, StepLimit | TimeLimit) {
+                return Err(format!("the reference does not finish it: {}", e));
+            }
+        }
+        let case = RunCase {
+            stdout: run.stdout.clone(),
+            exit_code: run.exit_code(),
+            runtime_error: run.error.as_ref().and_then(|e| runtime_error(e.kind)),
+            ..case.clone()
+        };
+
+        // Compilers run as executables read the program from disk, and it
+        // may only exist in memory.
+        let dir = ArtifactDir::new().expect("cannot create a scratch directory");
+        let parent = case
+            .path
+            .parent()
+            .and_then(Path::file_name)
+            .unwrap_or("programs".as_ref());
+        let path = dir.path().join(parent).join(format!("{}.kxi", case.name));
+        let written = fs::create_dir_all(dir.path().join(parent))
+            .and_then(|()| fs::write(&path, &case.source));
+        if let Err(e) = written {
+            panic!("cannot write {}: {}", path.display(), e);
+        }
+        let result = run_program(
+            &RunCase {
+                path,
+                ..case.clone()
+            },
+            backend,
+        );
+        Ok(RunResult { case, ..result })
+    }
+
+    /// Shrinks the program of `result` as long as `backend` fails on it the
+    /// way it did first.
+    fn reduced(&self, result: &RunResult, backend: &mut dyn Backend) -> Option<RunResult> {
+        let program = kxi_ref::parse(&result.case.source).ok()?;
+        let first = mem::discriminant(result.failures.first()?);
+        let variant = |case: &RunCase, backend: &mut dyn Backend| {
+            self.judge(case, backend)
+                .ok()
+                .filter(|r| r.failures.first().map(mem::discriminant) == Some(first))
+        };
+        let with = |source: String| RunCase {
+            source,
+            ..result.case.clone()
+        };
+        let reduced = reduce(&program, |source| {
+            variant(&with(source.to_string()), backend).is_some()
+        });
+        variant(&with(print::program(&reduced)), backend)
+    }
+}
+
+/// The error of `runtime_error_kxi/` the reference stopped with, if it is
+/// one. A backend may then exit with any status other than 0, as long as it
+/// names the error.
+fn runtime_error(kind: RuntimeErrorKind) -> Option<RuntimeError> {
+    match kind {
+        RuntimeErrorKind::NullDereference => Some(RuntimeError::NullDereference),
+        RuntimeErrorKind::IndexOutOfBounds => Some(RuntimeError::IndexOutOfBounds),
+        RuntimeErrorKind::DivisionByZero => Some(RuntimeError::DivisionByZero),
+        RuntimeErrorKind::StackOverflow => Some(RuntimeError::StackOverflow),
+        _ => None,
+    }
+}
+
+/// The `.kxi` files in `dir`, with their `.stdin` where there is one.
+pub fn load_programs(dir: &Path) -> io::Result<Vec<RunCase>> {
+    programs(dir)?
+        .iter()
+        .map(|path| RunCase::load_program(path))
+        .collect()
+}
+
+/// The program of `generator` as `generated/gen_SEED.kxi`, with no input.
+pub fn generated(generator: &Generator) -> RunCase {
+    let name = format!("gen_{}", generator.seed());
+    RunCase {
+        path: PathBuf::from("generated").join(format!("{}.kxi", name)),
+        name,
+        source: generator.source(),
+        stdin: String::new(),
+        stdout: String::new(),
+        exit_code: 0,
+        runtime_error: None,
+        timeout: DEFAULT_TIMEOUT,
+    }
+}
@@ -0,0 +1,552 @@
+//! Execution tests: compile a program, run what the compiler produced and
+//! compare what it prints.
+//!
+//! Every `.kxi` file in `run_kxi/` sits next to
+//!
+//! * `name.stdin`, fed to the program, empty if the file is missing;
+//! * `name.stdout`, everything the program must print;
+//! * `name.exitcode`, the status the program must exit with.
+//!
+//! A program that runs longer than [`DEFAULT_TIMEOUT`], or than the
+//! `timeout=` in its expectation header, is stopped and fails.
+//!
+//! Programs in `runtime_error_kxi/` must instead stop with the runtime
+//! error their header names, e.g. `// expect: runtime-error=null-dereference`.
+//! They have no `.exitcode`: any status other than 0 will do, as long as
+//! the last [`RuntimeError`] named on stderr is that one. Their `.stdout` holds what they
+//! print before the error.
+//!
+//! Compilers take part by implementing [`Backend`]. [`External`] does so
+//! for compilers that write an executable, or anything a command can run.
+//!
+//! [`External`]: crate::External
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::compiler::Diagnostic;
+use crate::expect::Header;
+use crate::golden::{first_difference_by, programs};
+use crate::kind::RuntimeError;
+use crate::runner::panic_message;
+
+/// How long a program may run when its header does not say.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A program from `run_kxi/` and what running it must produce.
+#[derive(Debug, Clone)]
+pub struct RunCase {
+    /// File stem, e.g. `switch_fallthrough`.
+    pub name: String,
+    /// Full path to the `.kxi` file.
+    pub path: PathBuf,
+    pub source: String,
+    /// Standard input of the program.
+    pub stdin: String,
+    /// Expected standard output.
+    pub stdout: String,
+    /// Expected exit status. Unused if `runtime_error` is set.
+    pub exit_code: i32,
+    /// Runtime error the program must stop with.
+    pub runtime_error: Option<RuntimeError>,
+    pub timeout: Duration,
+}
+
+impl RunCase {
+    /// Loads a program and its sibling files. `.stdout` is required, and so
+    /// is `.exitcode` unless the program must stop with a runtime error.
+    pub fn load(path: &Path) -> io::Result<RunCase> {
+        let mut case = RunCase::load_program(path)?;
+        let sibling = |ext: &str| {
+            let file = path.with_extension(ext);
+            fs::read_to_string(&file)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", file.display(), e)))
+        };
+        case.stdout = sibling("stdout")?;
+        if case.runtime_error.is_some() {
+            return Ok(case);
+        }
+        let code = sibling("exitcode")?;
+        case.exit_code = code.trim().parse().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{}: `{}` is not an exit status",
+                    path.with_extension("exitcode").display(),
+                    code.trim()
+                ),
+            )
+        })?;
+        Ok(case)
+    }
+
+    /// Loads a program and its input, expecting no output and status 0.
+    pub(crate) fn load_program(path: &Path) -> io::Result<RunCase> {
+        let source = fs::read_to_string(path)?;
+        let header = Header::parse(&source).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })?;
+        let stdin = match fs::read_to_string(path.with_extension("stdin")) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(RunCase {
+            name: path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string(),
+            path: path.to_path_buf(),
+            source,
+            stdin,
+            stdout: String::new(),
+            exit_code: 0,
+            runtime_error: header.runtime_error,
+            timeout: header.timeout.unwrap_or(DEFAULT_TIMEOUT),
+        })
+    }
+
+    /// `dir/name.kxi`, the identifier used in reports.
+    pub fn id(&self) -> String {
+        let dir = self
+            .path
+            .parent()
+            .and_then(Path::file_name)
+            .map(|d| d.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        format!("{}/{}.kxi", dir, self.name)
+    }
+}
+
+/// How a run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Exited(i32),
+    /// Ended by a signal or the like, described by the operating system.
+    Killed(String),
+    /// Stopped by the harness after the case's timeout.
+    TimedOut,
+}
+
+/// What a compiled program did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub status: Status,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// What became of one program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Execution {
+    /// The compiler rejected the program.
+    Rejected(Vec<Diagnostic>),
+    Ran(Run),
+}
+
+/// A compiler whose output can be run.
+pub trait Backend {
+    /// Short name used in reports.
+    fn name(&self) -> &str;
+
+    /// Compiles `case.source`, runs the result with `case.stdin` as its
+    /// standard input and reports what happened. A program still running
+    /// after `case.timeout` must be stopped and reported as
+    /// [`Status::TimedOut`].
+    fn execute(&mut self, case: &RunCase) -> Execution;
+}
+
+/// Why a single run failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunFailure {
+    /// The compiler rejected a valid program.
+    Rejected,
+    /// The first line of output that differs, 1-based.
+    Stdout {
+        line: usize,
+        expected: String,
+        actual: String,
+    },
+    ExitCode {
+        expected: i32,
+        actual: i32,
+    },
+    /// The program ended normally instead of with a runtime error.
+    NoRuntimeError {
+        expected: RuntimeError,
+    },
+    /// The program failed without naming the expected runtime error last
+    /// on stderr, but this one or none.
+    WrongRuntimeError {
+        expected: RuntimeError,
+        actual: Option<RuntimeError>,
+        status: i32,
+    },
+    Killed(String),
+    TimedOut(Duration),
+    /// The backend panicked.
+    Panicked(String),
+}
+
+impl fmt::Display for RunFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunFailure::Rejected => write!(f, "valid program was rejected"),
+            RunFailure::Stdout {
+                line,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "stdout line {}: expected `{}`, got `{}`",
+                line, expected, actual
+            ),
+            RunFailure::ExitCode { expected, actual } => {
+                write!(f, "expected exit status {}, got {}", expected, actual)
+            }
+            RunFailure::NoRuntimeError { expected } => {
+                write!(f, "expected a {} error, program exited normally", expected)
+            }
+            RunFailure::WrongRuntimeError {
+                expected,
+                actual: Some(actual),
+                status,
+            } => write!(
+                f,
+                "expected a {} error, program exited with status {} after a {} error",
+                expected, status, actual
+            ),
+            RunFailure::WrongRuntimeError {
+                expected,
+                actual: None,
+                status,
+            } => write!(
+                f,
+                "expected a {} error, program exited with status {} without naming it on stderr",
+                expected, status
+            ),
+            RunFailure::Killed(how) => write!(f, "program was killed: {}", how),
+            RunFailure::TimedOut(limit) => write!(f, "program still running after {:?}", limit),
+            RunFailure::Panicked(msg) => write!(f, "backend panicked: {}", msg),
+        }
+    }
+}
+
+/// The result of running one program.
+#[derive(Debug, Clone)]
+pub struct RunResult {
+    pub case: RunCase,
+    /// `None` if the backend panicked.
+    pub execution: Option<Execution>,
+    /// Empty when the case passed.
+    pub failures: Vec<RunFailure>,
+}
+
+impl RunResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// The results of running a backend over a directory of programs.
+#[derive(Debug, Clone)]
+pub struct RunReport {
+    pub backend: String,
+    pub results: Vec<RunResult>,
+}
+
+impl RunReport {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    /// Panics with the full report if any program failed.
+    #[track_caller]
+    pub fn assert_all_passed(&self) {
+        if self.failed() > 0 {
+            panic!("{}", self);
+        }
+    }
+}
+
+impl fmt::Display for RunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            if result.passed() {
+                writeln!(f, "ok      {}", result.case.id())?;
+                continue;
+            }
+            writeln!(f, "FAILED  {}", result.case.id())?;
+            for failure in &result.failures {
+                writeln!(f, "        {}", failure)?;
+            }
+            match &result.execution {
+                Some(Execution::Rejected(diagnostics)) => {
+                    for diagnostic in diagnostics {
+                        writeln!(f, "        reported {}", diagnostic)?;
+                    }
+                }
+                Some(Execution::Ran(run)) => {
+                    for line in run.stderr.lines() {
+                        writeln!(f, "        stderr: {}", line)?;
+                    }
+                }
+                None => {}
+            }
+        }
+        writeln!(
+            f,
+            "{}: {} passed, {} failed",
+            self.backend,
+            self.passed(),
+            self.failed()
+        )
+    }
+}
+
+/// Runs every program in `dir` through `backend`.
+pub fn run_programs(dir: &Path, backend: &mut dyn Backend) -> io::Result<RunReport> {
+    let results = programs(dir)?
+        .iter()
+        .map(|path| RunCase::load(path).map(|case| run_program(&case, backend)))
+        .collect::<io::Result<_>>()?;
+    Ok(RunReport {
+        backend: backend.name().to_string(),
+        results,
+    })
+}
+
+/// Runs and judges a single program.
+pub fn run_program(case: &RunCase, backend: &mut dyn Backend) -> RunResult {
+    match panic::catch_unwind(AssertUnwindSafe(|| backend.execute(case))) {
+        Ok(execution) => RunResult {
+            case: case.clone(),
+            failures: judge(case, &execution),
+            execution: Some(execution),
+        },
+        Err(payload) => RunResult {
+            case: case.clone(),
+            execution: None,
+            failures: vec![RunFailure::Panicked(panic_message(payload))],
+        },
+    }
+}
+
+/// Rewrites the `.stdout` and `.exitcode` files in `dir` with what
+/// `backend`'s programs do, leaving out `.exitcode` for programs that must
+/// stop with a runtime error. Programs it rejects or that do not finish are
+/// left alone and returned.
+pub fn bless_runs(dir: &Path, backend: &mut dyn Backend) -> io::Result<Vec<PathBuf>> {
+    let mut skipped = Vec::new();
+    for path in programs(dir)? {
+        let case = RunCase::load_program(&path)?;
+        match backend.execute(&case) {
+            Execution::Ran(Run {
+                status: Status::Exited(code),
+                stdout,
+                ..
+            }) => {
+                fs::write(path.with_extension("stdout"), stdout)?;
+                if case.runtime_error.is_none() {
+                    fs::write(path.with_extension("exitcode"), format!("{}\n", code))?;
+                }
+            }
+            _ => skipped.push(path),
+        }
+    }
+    Ok(skipped)
+}
+
+/// Panics with a report if any program in `dir` fails. With `KXI_BLESS`
+/// set, [`bless_runs`] instead.
+#[track_caller]
+pub fn assert_runs(dir: impl AsRef<Path>, backend: &mut dyn Backend) {
+    let dir = dir.as_ref();
+    if env::var_os("KXI_BLESS").is_some_and(|v| v != "0") {
+        let skipped = bless_runs(dir, backend)
+            .unwrap_or_else(|e| panic!("could not bless {}: {}", dir.display(), e));
+        for path in skipped {
+            eprintln!("not blessed, did not run to the end: {}", path.display());
+        }
+        return;
+    }
+    run_programs(dir, backend)
+        .unwrap_or_else(|e| panic!("could not read {}: {}", dir.display(), e))
+        .assert_all_passed();
+}
+
+fn judge(case: &RunCase, execution: &Execution) -> Vec<RunFailure> {
+    let run = match execution {
+        Execution::Rejected(_) => return vec![RunFailure::Rejected],
+        Execution::Ran(run) => run,
+    };
+    // The output of a program stopped half way says nothing.
+    if run.status == Status::TimedOut {
+        return vec![RunFailure::TimedOut(case.timeout)];
+    }
+    let mut failures = Vec::new();
+    if let Some((line, expected, actual)) =
+        first_difference_by(&case.stdout, &run.stdout, |e, a| {
+            e == a.trim_end_matches('\r')
+        })
+    {
+        failures.push(RunFailure::Stdout {
+            line,
+            expected,
+            actual,
+        });
+    } else if case.stdout.ends_with('\n') != run.stdout.ends_with('\n') {
+        // Only the newline after the last line differs.
+        let last = |text: &str| {
+            let line = text.lines().last().unwrap_or("").trim_end_matches('\r');
+            let newline = if text.ends_with('\n') { "\\n" } else { "" };
+            format!("{}{}", line, newline)
+        };
+        failures.push(RunFailure::Stdout {
+            line: case.stdout.lines().count().max(1),
+            expected: last(&case.stdout),
+            actual: last(&run.stdout),
+        });
+    }
+    let named = RuntimeError::named_in(&run.stderr);
+    match (&run.status, case.runtime_error) {
+        (Status::Exited(0), Some(expected)) => {
+            failures.push(RunFailure::NoRuntimeError { expected })
+        }
+        (Status::Exited(code), Some(expected)) if named != Some(expected) => {
+            failures.push(RunFailure::WrongRuntimeError {
+                expected,
+                actual: named,
+                status: *code,
+            })
+        }
+        (Status::Exited(code), None) if *code != case.exit_code => {
+            failures.push(RunFailure::ExitCode {
+                expected: case.exit_code,
+                actual: *code,
+            })
+        }
+        (Status::Exited(_), _) => {}
+        (Status::Killed(how), _) => failures.push(RunFailure::Killed(how.clone())),
+        (Status::TimedOut, _) => unreachable!("judged above"),
+    }
+    failures
+}
+
+/// A fresh directory for one compiler's output, removed again on drop.
+pub(crate) struct ArtifactDir(PathBuf);
+
+impl ArtifactDir {
+    pub(crate) fn new() -> io::Result<ArtifactDir> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let dir = env::temp_dir().join(format!("kxi_harness_out_{}_{}", std::process::id(), n));
+        fs::create_dir_all(&dir)?;
+        Ok(ArtifactDir(dir))
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ArtifactDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Runs `command` with `stdin`, killing it once `timeout` has passed.
+///
+/// Whatever the program started may outlive it and hold its pipes open, so
+/// its output is only waited for until the timeout too. A program whose
+/// output is not complete by then has timed out.
+pub(crate) fn run_with_timeout(
+    mut command: Command,
+    stdin: &str,
+    timeout: Duration,
+) -> io::Result<Run> {
+    let deadline = Instant::now() + timeout;
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let input = stdin.to_string();
+    let mut pipe = child.stdin.take().expect("stdin is piped");
+    // A program that never reads must not block the harness on a full pipe,
+    // so the writer is left to finish or fail on its own.
+    thread::spawn(move || {
+        let _ = pipe.write_all(input.as_bytes());
+    });
+    let stdout = drain(child.stdout.take().expect("stdout is piped"));
+    let stderr = drain(child.stderr.take().expect("stderr is piped"));
+    let timed_out = Run {
+        status: Status::TimedOut,
+        stdout: String::new(),
+        stderr: String::new(),
+    };
+    let Some(status) = wait(&mut child, deadline)? else {
+        return Ok(timed_out);
+    };
+    while !(stdout.is_finished() && stderr.is_finished()) {
+        if Instant::now() >= deadline {
+            return Ok(timed_out);
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    let text = |reader: thread::JoinHandle<Vec<u8>>| {
+        String::from_utf8_lossy(&reader.join().unwrap_or_default()).into_owned()
+    };
+    Ok(Run {
+        status: exit_status(status),
+        stdout: text(stdout),
+        stderr: text(stderr),
+    })
+}
+
+fn drain(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+/// Waits for `child` to exit, or kills it and returns `None` at `deadline`.
+fn wait(child: &mut Child, deadline: Instant) -> io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            child.wait()?;
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn exit_status(status: ExitStatus) -> Status {
+    match status.code() {
+        Some(code) => Status::Exited(code),
+        None => Status::Killed(status.to_string()),
+    }
+}
@@ -0,0 +1,443 @@
+//! Expectation headers embedded in `.kxi` files.
+//!
+//! A test program can state what the compiler is expected to report in a
+//! line comment at the top of the file:
+//!
+//! ```text
+//! // expect: phase=typecheck errors=4
+//! void main(){
+//!     ...
+//! ```
+//!
+//! Programs may bound how long compiling them, and running them if they
+//! are run as those under `run_kxi/` are, takes with `timeout=`, in seconds
+//! or milliseconds:
+//!
+//! ```text
+//! // expect: timeout=500ms
+//! ```
+//!
+//! Programs that must stop with a runtime error, such as those under
+//! `runtime_error_kxi/`, name the kind of error:
+//!
+//! ```text
+//! // expect: runtime-error=division-by-zero
+//! ```
+//!
+//! Any file may list tags, for running a part of the corpus at a time (see
+//! [`crate::Filter`]):
+//!
+//! ```text
+//! // expect: phase=typecheck errors=2 tags=arrays,calls
+//! ```
+//!
+//! Only the leading block of comments is searched, so the header has to come
+//! before the first line of code. Several `// expect:` lines are merged.
+//!
+//! Individual errors are pinned to a line with a trailing comment naming the
+//! [`Kind`] of error, in the spirit of rustc's `//~ ERROR` UI-test
+//! annotations. Only a comment counts, not a string or char literal that
+//! happens to contain `// error:`. A line that must produce several errors
+//! lists every kind:
+//!
+//! ```text
+//!     int y = x + 2; // error: type-mismatch, type-mismatch
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::kind::{Kind, RuntimeError};
+use crate::phase::Phase;
+
+const MARKER: &str = "expect:";
+const ERROR_MARKER: &str = "// error:";
+
+/// The expectations declared in a file's header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Header {
+    /// For an invalid program, the pass that must report the errors. For a
+    /// valid program, the last pass it has to get through cleanly.
+    pub phase: Option<Phase>,
+    /// Exact number of errors that must be reported.
+    pub errors: Option<usize>,
+    /// How long the compiler, and then the compiled program, may run.
+    pub timeout: Option<Duration>,
+    /// Runtime error the compiled program must stop with.
+    pub runtime_error: Option<RuntimeError>,
+    /// What the program is about, e.g. `arrays`.
+    pub tags: Vec<String>,
+}
+
+/// An error the compiler must report on a particular line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// 1-based source line.
+    pub line: usize,
+    pub kind: Kind,
+}
+
+/// A malformed expectation header or error annotation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectError {
+    /// 1-based line of the offending comment.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExpectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ExpectError {}
+
+impl Header {
+    /// Parses the header of `source`. A file without one yields an empty
+    /// header.
+    pub fn parse(source: &str) -> Result<Header, ExpectError> {
+        let mut header = Header::default();
+        for (idx, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some(comment) = line.strip_prefix("//") else {
+                break;
+            };
+            let Some(body) = comment.trim().strip_prefix(MARKER) else {
+                continue;
+            };
+            header.apply(body).map_err(|message| ExpectError {
+                line: idx + 1,
+                message,
+            })?;
+        }
+        Ok(header)
+    }
+
+    fn apply(&mut self, body: &str) -> Result<(), String> {
+        for item in body.split_whitespace() {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("expected `key=value`, found `{}`", item))?;
+            match key {
+                "phase" => set(&mut self.phase, key, value.parse()?)?,
+                "errors" => {
+                    let count = value
+                        .parse()
+                        .map_err(|_| format!("`errors` must be a number, found `{}`", value))?;
+                    set(&mut self.errors, key, count)?
+                }
+                "timeout" => set(&mut self.timeout, key, parse_duration(value)?)?,
+                "runtime-error" => set(&mut self.runtime_error, key, value.parse()?)?,
+                "tags" => {
+                    if !self.tags.is_empty() {
+                        return Err(format!("`{}` is given more than once", key));
+                    }
+                    for tag in value.split(',') {
+                        if !is_tag(tag) {
+                            return Err(format!("`{}` is not a tag", tag));
+                        }
+                        self.tags.push(tag.to_string());
+                    }
+                }
+                _ => return Err(format!("unknown key `{}`", key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the header back as a single `// expect:` line.
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "// {}", MARKER)?;
+        if let Some(phase) = self.phase {
+            write!(f, " phase={}", phase)?;
+        }
+        if let Some(errors) = self.errors {
+            write!(f, " errors={}", errors)?;
+        }
+        if let Some(timeout) = self.timeout {
+            match timeout.subsec_millis() {
+                0 => write!(f, " timeout={}s", timeout.as_secs())?,
+                _ => write!(f, " timeout={}ms", timeout.as_millis())?,
+            }
+        }
+        if let Some(kind) = self.runtime_error {
+            write!(f, " runtime-error={}", kind)?;
+        }
+        if !self.tags.is_empty() {
+            write!(f, " tags={}", self.tags.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects every `// error:` annotation in `source`, one per expected
+/// error, in source order.
+pub fn parse_annotations(source: &str) -> Result<Vec<Annotation>, ExpectError> {
+    let mut annotations = Vec::new();
+    for (idx, line) in source.lines().enumerate() {
+        let Some(pos) = find_marker(line) else {
+            continue;
+        };
+        for kind in line[pos + ERROR_MARKER.len()..].split(',') {
+            let kind = kind.trim().parse().map_err(|message| ExpectError {
+                line: idx + 1,
+                message,
+            })?;
+            annotations.push(Annotation {
+                line: idx + 1,
+                kind,
+            });
+        }
+    }
+    Ok(annotations)
+}
+
+/// Appends an `// error:` comment for `annotations` to the lines of `source`
+/// they point at, the inverse of [`parse_annotations`].
+pub fn annotate(source: &str, annotations: &[Annotation]) -> String {
+    let mut out = String::with_capacity(source.len());
+    for (idx, line) in source.lines().enumerate() {
+        out.push_str(line);
+        let kinds: Vec<&str> = annotations
+            .iter()
+            .filter(|a| a.line == idx + 1)
+            .map(|a| a.kind.as_str())
+            .collect();
+        if !kinds.is_empty() {
+            out.push_str(&format!(" {} {}", ERROR_MARKER, kinds.join(", ")));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Removes the expectation header and every error annotation, leaving the
+/// program as the compiler sees it minus the harness's comments.
+pub fn strip_expectations(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut in_header = true;
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if in_header && trimmed.starts_with("//") && trimmed[2..].trim().starts_with(MARKER) {
+            continue;
+        }
+        if !trimmed.is_empty() && !trimmed.starts_with("//") {
+            in_header = false;
+        }
+        match find_marker(line) {
+            Some(pos) => out.push_str(line[..pos].trim_end()),
+            None => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The position of the `// error:` marker in the comment ending `line`,
+/// skipping over string and char literals, which may contain `//`.
+fn find_marker(line: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut chars = line.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '/') if line[pos..].starts_with("//") => {
+                return line[pos..].find(ERROR_MARKER).map(|i| pos + i);
+            }
+            (None, _) => {}
+        }
+    }
+    None
+}
+
+/// Tags are lowercase words joined by `-`.
+fn is_tag(kind: &str) -> bool {
+    !kind.is_empty() && kind.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+}
+
+/// `2s` or `500ms`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "`timeout` must look like `2s` or `500ms`, found `{}`",
+            value
+        )
+    };
+    let (number, unit) = match value.strip_suffix("ms") {
+        Some(number) => (number, Duration::from_millis(1)),
+        None => (
+            value.strip_suffix('s').ok_or_else(invalid)?,
+            Duration::from_secs(1),
+        ),
+    };
+    let count: u32 = number.parse().map_err(|_| invalid())?;
+    Ok(unit * count)
+}
+
+fn set<T>(slot: &mut Option<T>, key: &str, value: T) -> Result<(), String> {
+    if slot.is_some() {
+        return Err(format!("`{}` is given more than once", key));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_phase_and_count() {
+        let header = Header::parse("// expect: phase=typecheck errors=4\nvoid main(){}\n").unwrap();
+        assert_eq!(header.phase, Some(Phase::TypeCheck));
+        assert_eq!(header.errors, Some(4));
+    }
+
+    #[test]
+    fn missing_header_is_empty() {
+        assert_eq!(Header::parse("void main(){}\n").unwrap(), Header::default());
+    }
+
+    #[test]
+    fn stops_at_first_line_of_code() {
+        let header = Header::parse("void main(){}\n// expect: errors=1\n").unwrap();
+        assert_eq!(header.errors, None);
+    }
+
+    #[test]
+    fn merges_several_lines() {
+        let header =
+            Header::parse("// a comment\n// expect: phase=resolve\n\n// expect: errors=2\n")
+                .unwrap();
+        assert_eq!(header.phase, Some(Phase::Resolve));
+        assert_eq!(header.errors, Some(2));
+    }
+
+    #[test]
+    fn parses_timeouts() {
+        let header = Header::parse("// expect: timeout=2s\n").unwrap();
+        assert_eq!(header.timeout, Some(Duration::from_secs(2)));
+        let header = Header::parse("// expect: timeout=250ms\n").unwrap();
+        assert_eq!(header.timeout, Some(Duration::from_millis(250)));
+        assert!(Header::parse("// expect: timeout=2\n").is_err());
+        assert!(Header::parse("// expect: timeout=fast\n").is_err());
+    }
+
+    #[test]
+    fn parses_runtime_errors() {
+        let header = Header::parse("// expect: runtime-error=null-dereference\n").unwrap();
+        assert_eq!(header.runtime_error, Some(RuntimeError::NullDereference));
+        assert!(Header::parse("// expect: runtime-error=NullPointer\n").is_err());
+    }
+
+    #[test]
+    fn parses_tags() {
+        let header = Header::parse("// expect: phase=typecheck tags=arrays,calls\n").unwrap();
+        assert_eq!(header.tags, ["arrays", "calls"]);
+        assert!(Header::parse("// expect: tags=arrays,\n").is_err());
+        assert!(Header::parse("// expect: tags=Arrays\n").is_err());
+        assert!(Header::parse("// expect: tags=a\n// expect: tags=b\n").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        let err = Header::parse("// expect: errors=lots\n").unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(Header::parse("// expect: phase=codegen\n").is_err());
+        assert!(Header::parse("// expect: warnings=1\n").is_err());
+        assert!(Header::parse("// expect: errors=1 errors=2\n").is_err());
+        assert!(Header::parse("// expect: errors\n").is_err());
+    }
+
+    #[test]
+    fn parses_annotations() {
+        let source = "void main(){\n    int y = x + 2; // error: type-mismatch, type-mismatch\n    y = 4; // error: undeclared-identifier\n}\n";
+        let kinds: Vec<_> = parse_annotations(source)
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.line, a.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (2, Kind::TypeMismatch),
+                (2, Kind::TypeMismatch),
+                (3, Kind::UndeclaredIdentifier),
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_header_and_annotations() {
+        let source = "// expect: phase=resolve errors=1\nvoid main(){\n    y = 4; // error: undeclared-identifier\n}\n";
+        assert_eq!(strip_expectations(source), "void main(){\n    y = 4;\n}\n");
+    }
+
+    #[test]
+    fn writes_headers_that_parse_back() {
+        for text in [
+            "// expect: phase=typecheck errors=2 tags=arrays,calls",
+            "// expect: timeout=2s runtime-error=null-dereference",
+            "// expect: timeout=250ms",
+        ] {
+            let header = Header::parse(text).unwrap();
+            assert_eq!(header.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn annotates_lines() {
+        let source = "void main(){\n    int y = x + 2;\n}\n";
+        let annotations = [
+            Annotation {
+                line: 2,
+                kind: Kind::UndeclaredIdentifier,
+            },
+            Annotation {
+                line: 2,
+                kind: Kind::TypeMismatch,
+            },
+        ];
+        let annotated = annotate(source, &annotations);
+        assert_eq!(
+            annotated,
+            "void main(){\n    int y = x + 2; // error: undeclared-identifier, type-mismatch\n}\n"
+        );
+        assert_eq!(parse_annotations(&annotated).unwrap(), annotations);
+    }
+
+    #[test]
+    fn ignores_markers_in_literals() {
+        let source = "void main(){\n    cout << \"// error: x\";\n    cout << '\"' + \"\\\" // error: y\"; // error: type-mismatch\n}\n";
+        assert_eq!(
+            parse_annotations(source).unwrap(),
+            [Annotation {
+                line: 3,
+                kind: Kind::TypeMismatch
+            }]
+        );
+        assert_eq!(
+            strip_expectations(source),
+            "void main(){\n    cout << \"// error: x\";\n    cout << '\"' + \"\\\" // error: y\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_annotations() {
+        assert_eq!(parse_annotations("x; // error:\n").unwrap_err().line, 1);
+        assert!(parse_annotations("x; // error: a,\n").is_err());
+        assert!(parse_annotations("x; // error: Type Mismatch\n").is_err());
+        assert!(parse_annotations("x; // error: type-mismatched\n").is_err());
+    }
+}
@@ -0,0 +1,330 @@
+//! Adapter for compilers that ship as a standalone executable.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::compiler::{parse_json_diagnostics, Compiler, Diagnostic, Outcome};
+use crate::corpus::TestCase;
+use crate::execute::{
+    run_with_timeout, ArtifactDir, Backend, Execution, Run, RunCase, Status, DEFAULT_TIMEOUT,
+};
+
+/// Matches `file:line:col: message`, the format used by gcc, clang and rustc.
+///
+/// Custom patterns may also have `phase` and `kind` groups, holding a
+/// [`Phase`](crate::Phase) or [`Kind`](crate::Kind) as spelled in
+/// expectation headers and annotations.
+pub const DEFAULT_PATTERN: &str =
+    r"^(?P<file>[^:]+):(?P<line>\d+):(?P<column>\d+):\s*(?:error:\s*)?(?P<message>.*)$";
+
+/// Placeholder in an argument that is replaced by the path of the test file.
+/// If no argument contains it, the path is appended after all arguments.
+pub const FILE_PLACEHOLDER: &str = "{file}";
+
+/// Placeholder for where the compiler should write its output, a path in a
+/// fresh directory of its own.
+pub const OUT_PLACEHOLDER: &str = "{out}";
+
+/// A compiler run as a separate process, one invocation per test file.
+///
+/// A zero exit status means the program was accepted. On any other status
+/// every line of stderr matching the diagnostic pattern becomes one
+/// [`Diagnostic`]. The pattern must have a `message` group and may have
+/// `file`, `line`, `column`, `phase` and `kind` groups. Lines whose `file`
+/// names another file than the test's, such as one it includes, are left
+/// out. Compilers that print their
+/// diagnostics as JSON instead are read with [`External::json_diagnostics`].
+/// If the compiler fails without printing a single diagnostic, its whole
+/// stderr is reported as one diagnostic without a location.
+///
+/// A compiler killed by a signal, or one that cannot be started, is treated
+/// like a panic and reported as a crash of the case that triggered it.
+///
+/// As a [`Backend`], the compiler is expected to write a program to
+/// [`OUT_PLACEHOLDER`], which is then run directly or through the command
+/// given to [`External::runs_with`]:
+///
+/// ```
+/// use kxi_harness::External;
+///
+/// let kxic = External::new("kxic").args(["{file}", "-o", "{out}"]);
+/// let on_a_vm = External::new("kxic")
+///     .args(["{file}", "-o", "{out}.asm"])
+///     .runs_with(["kxi-vm", "{out}.asm"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct External {
+    name: String,
+    program: OsString,
+    args: Vec<OsString>,
+    run: Vec<OsString>,
+    pattern: Regex,
+    json: bool,
+}
+
+impl External {
+    pub fn new(program: impl Into<OsString>) -> External {
+        let program = program.into();
+        let name = Path::new(&program)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        External {
+            name,
+            program,
+            args: Vec::new(),
+            run: Vec::new(),
+            pattern: Regex::new(DEFAULT_PATTERN).unwrap(),
+            json: false,
+        }
+    }
+
+    /// Builds an adapter from the environment, if `KXI_COMPILER` is set.
+    ///
+    /// * `KXI_COMPILER`: the executable to run.
+    /// * `KXI_COMPILER_ARGS`: whitespace separated arguments, may contain
+    ///   [`FILE_PLACEHOLDER`].
+    /// * `KXI_DIAGNOSTIC_PATTERN`: a regex replacing [`DEFAULT_PATTERN`].
+    /// * `KXI_DIAGNOSTIC_FORMAT`: `json` to read diagnostics with
+    ///   [`External::json_diagnostics`].
+    /// * `KXI_RUN`: whitespace separated command running the compiled
+    ///   program, see [`External::runs_with`].
+    pub fn from_env() -> Option<Result<External, regex::Error>> {
+        let program = env::var_os("KXI_COMPILER")?;
+        let mut external = External::new(program);
+        if let Ok(args) = env::var("KXI_COMPILER_ARGS") {
+            external = external.args(args.split_whitespace());
+        }
+        if let Ok(run) = env::var("KXI_RUN") {
+            external = external.runs_with(run.split_whitespace());
+        }
+        if env::var("KXI_DIAGNOSTIC_FORMAT").is_ok_and(|f| f == "json") {
+            external = external.json_diagnostics();
+        }
+        Some(match env::var("KXI_DIAGNOSTIC_PATTERN") {
+            Ok(pattern) => external.diagnostic_pattern(&pattern),
+            Err(_) => Ok(external),
+        })
+    }
+
+    /// Overrides the name used in reports, which defaults to the file name of
+    /// the executable.
+    pub fn named(mut self, name: impl Into<String>) -> External {
+        self.name = name.into();
+        self
+    }
+
+    pub fn arg(mut self, arg: impl Into<OsString>) -> External {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I>(mut self, args: I) -> External
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// The command running a compiled program, for compilers whose output
+    /// is not itself executable. Arguments may contain [`OUT_PLACEHOLDER`].
+    /// Without one, the file the compiler wrote is run directly.
+    pub fn runs_with<I>(mut self, command: I) -> External
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        self.run = command.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Reads diagnostics from stderr as JSON, one object per line, in the
+    /// format described in [`crate::compiler`]. Lines not starting with `{`
+    /// are ignored. Malformed JSON is treated as a crash of the compiler,
+    /// which fails the case.
+    pub fn json_diagnostics(mut self) -> External {
+        self.json = true;
+        self
+    }
+
+    /// Replaces the regex used to pick diagnostics out of stderr.
+    pub fn diagnostic_pattern(mut self, pattern: &str) -> Result<External, regex::Error> {
+        self.pattern = Regex::new(pattern)?;
+        Ok(self)
+    }
+
+    fn command(&self, file: &Path, out: &Path) -> Command {
+        let mut command = Command::new(&self.program);
+        let mut substituted = false;
+        for arg in &self.args {
+            match arg.to_str() {
+                Some(s) if s.contains(FILE_PLACEHOLDER) => {
+                    command
+                        .arg(substitute(s, out).replace(FILE_PLACEHOLDER, &file.to_string_lossy()));
+                    substituted = true;
+                }
+                Some(s) if s.contains(OUT_PLACEHOLDER) => {
+                    command.arg(substitute(s, out));
+                }
+                _ => {
+                    command.arg(arg);
+                }
+            }
+        }
+        if !substituted {
+            command.arg(file);
+        }
+        command
+    }
+
+    /// Compiles `file`, writing any output below `out`, and kills the
+    /// compiler after `timeout`. Returns the diagnostics if the compiler
+    /// rejected it.
+    fn build(&self, file: &Path, out: &Path, timeout: Duration) -> Result<(), Vec<Diagnostic>> {
+        let run = match run_with_timeout(self.command(file, out), "", timeout) {
+            Ok(run) => run,
+            Err(e) => panic!("could not run {}: {}", self.program.to_string_lossy(), e),
+        };
+        let code = match run.status {
+            Status::Exited(code) => code,
+            Status::Killed(how) => panic!("compiler was killed: {}", how),
+            Status::TimedOut => panic!("compiler still running after {:?}", timeout),
+        };
+        if code == 0 {
+            return Ok(());
+        }
+        let stderr = run.stderr;
+        let mut diagnostics = match self.parse_diagnostics(&stderr, file) {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => panic!("malformed JSON diagnostic: {}", e),
+        };
+        if diagnostics.is_empty() {
+            let message = match stderr.trim() {
+                "" => format!("exited with status {}", code),
+                text => text.to_string(),
+            };
+            diagnostics.push(Diagnostic::new(message));
+        }
+        Err(diagnostics)
+    }
+
+    /// Picks the diagnostics about `file` out of a compiler's stderr. Fails
+    /// only on malformed JSON.
+    pub fn parse_diagnostics(
+        &self,
+        stderr: &str,
+        file: &Path,
+    ) -> Result<Vec<Diagnostic>, serde_json::Error> {
+        if self.json {
+            return parse_json_diagnostics(stderr);
+        }
+        let file = canonical(file);
+        let diagnostics = stderr
+            .lines()
+            .filter_map(|line| self.pattern.captures(line.trim_end()))
+            .filter(|caps| caps.name("file").is_none_or(|m| names(&file, m.as_str())))
+            .map(|caps| {
+                let number = |group| caps.name(group).and_then(|m| m.as_str().parse().ok());
+                Diagnostic {
+                    line: number("line"),
+                    column: number("column"),
+                    phase: caps.name("phase").and_then(|m| m.as_str().parse().ok()),
+                    kind: caps.name("kind").and_then(|m| m.as_str().parse().ok()),
+                    message: caps
+                        .name("message")
+                        .map_or("", |m| m.as_str())
+                        .trim()
+                        .to_string(),
+                }
+            })
+            .collect();
+        Ok(diagnostics)
+    }
+}
+
+impl Compiler for External {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn compile(&mut self, case: &TestCase) -> Outcome {
+        let dir = ArtifactDir::new().expect("cannot create an output directory");
+        let timeout = case.header.timeout.unwrap_or(DEFAULT_TIMEOUT);
+        match self.build(&case.path, &dir.path().join("program"), timeout) {
+            Ok(()) => Outcome::accepted(),
+            Err(diagnostics) => Outcome::rejected(diagnostics),
+        }
+    }
+}
+
+impl Backend for External {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn execute(&mut self, case: &RunCase) -> Execution {
+        let dir = ArtifactDir::new().expect("cannot create an output directory");
+        let out = dir.path().join("program");
+        if let Err(diagnostics) = self.build(&case.path, &out, case.timeout) {
+            return Execution::Rejected(diagnostics);
+        }
+        let command = match self.run.split_first() {
+            None => Command::new(&out),
+            Some((program, args)) => {
+                let mut command = Command::new(substitute_os(program, &out));
+                command.args(args.iter().map(|a| substitute_os(a, &out)));
+                command
+            }
+        };
+        match run_with_timeout(command, &case.stdin, case.timeout) {
+            Ok(run) => Execution::Ran(run),
+            Err(e) => Execution::Ran(Run {
+                status: Status::Killed(format!("could not start: {}", e)),
+                stdout: String::new(),
+                stderr: String::new(),
+            }),
+        }
+    }
+}
+
+/// `path` with symlinks and `..` resolved, or as it is if it does not exist.
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Whether `reported`, a path as a compiler printed it, is `file`, itself
+/// [`canonical`]: the same path, or its last components, as in
+/// `invalid_kxi/scope_out.kxi`.
+fn names(file: &Path, reported: &str) -> bool {
+    let reported = Path::new(reported);
+    let reported = if reported.is_absolute() {
+        canonical(reported)
+    } else {
+        reported.to_path_buf()
+    };
+    let reported: Vec<Component> = reported
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    let file: Vec<Component> = file.components().collect();
+    !reported.is_empty() && file.ends_with(&reported)
+}
+
+fn substitute(arg: &str, out: &Path) -> String {
+    arg.replace(OUT_PLACEHOLDER, &out.to_string_lossy())
+}
+
+fn substitute_os(arg: &OsString, out: &Path) -> OsString {
+    match arg.to_str() {
+        Some(s) => substitute(s, out).into(),
+        None => arg.clone(),
+    }
+}
@@ -0,0 +1,141 @@
+//! Adapter for compilers structured like the pipeline in `sem_tests.rs`.
+
+use crate::compiler::{Compiler, Diagnostic, Outcome};
+use crate::corpus::{TestCase, CASCADE_DIR};
+use crate::phase::Phase;
+
+/// A compiler front end split into the passes exercised by `sem_tests.rs`.
+///
+/// Every test there repeats the same pipeline: parse the token stream, run
+/// `SymbolTable`, hand its table to `SymbolInner`, hand that table to
+/// `TypeCheck`, and count the errors of the last pass. Implementing this
+/// trait once lets [`Phased`] drive that pipeline over the whole corpus.
+///
+/// State that flows from one pass to the next (the AST, symbol tables,
+/// scopes) lives in [`FrontEnd::Unit`], which the passes are free to update.
+///
+/// ```ignore
+/// struct Mine;
+///
+/// struct Unit {
+///     ast: CompUnit,
+///     table: Table,
+///     scopes: Scopes,
+/// }
+///
+/// impl FrontEnd for Mine {
+///     type Unit = Unit;
+///
+///     fn name(&self) -> &str {
+///         "mine"
+///     }
+///
+///     fn parse(&mut self, source: &str) -> Result<Unit, Vec<Diagnostic>> {
+///         let ast = kxi::CompUnitParser::new()
+///             .parse(tok_gen(source))
+///             .map_err(|e| vec![Diagnostic::new(format!("{:?}", e))])?;
+///         Ok(Unit { ast, table: Table::default(), scopes: Scopes::default() })
+///     }
+///
+///     fn declare(&mut self, unit: &mut Unit) -> Vec<Diagnostic> {
+///         let mut st = SymbolTable::default();
+///         unit.ast.accept(&mut st);
+///         unit.table = st.table;
+///         unit.scopes = st.c_scopes;
+///         st.errs.iter().map(to_diagnostic).collect()
+///     }
+///
+///     // resolve() runs SymbolInner::new(table, scopes), typecheck() runs
+///     // TypeCheck::new(table) in the same way.
+/// }
+/// ```
+pub trait FrontEnd {
+    /// The program as it moves through the passes.
+    type Unit;
+
+    /// Short name used in reports.
+    fn name(&self) -> &str;
+
+    /// Parses the source text. A syntax error ends compilation.
+    fn parse(&mut self, source: &str) -> Result<Self::Unit, Vec<Diagnostic>>;
+
+    /// Declares classes, members and `main`.
+    fn declare(&mut self, unit: &mut Self::Unit) -> Vec<Diagnostic>;
+
+    /// Resolves names inside method bodies and initializers.
+    fn resolve(&mut self, unit: &mut Self::Unit) -> Vec<Diagnostic>;
+
+    /// Checks the types of every expression and statement.
+    fn typecheck(&mut self, unit: &mut Self::Unit) -> Vec<Diagnostic>;
+}
+
+/// Runs a [`FrontEnd`] as a [`Compiler`].
+///
+/// The passes run in order. Compilation stops after the first pass that
+/// reports errors, or after the pass named in the case's expectation header,
+/// whichever comes first. Programs of `cascade_kxi` instead go through every
+/// pass, so that errors a later pass reports about the root error show.
+/// Every diagnostic is stamped with the pass that produced it.
+pub struct Phased<F>(pub F);
+
+impl<F: FrontEnd> Phased<F> {
+    /// Runs the passes over `source`, stopping after `last`.
+    pub fn run_through(&mut self, source: &str, last: Phase) -> Outcome {
+        self.run(source, last, false)
+    }
+
+    /// Runs every pass over `source`, whether or not the ones before it
+    /// report errors. Only a syntax error ends compilation early.
+    pub fn run_all(&mut self, source: &str) -> Outcome {
+        self.run(source, Phase::TypeCheck, true)
+    }
+
+    fn run(&mut self, source: &str, last: Phase, keep_going: bool) -> Outcome {
+        let front = &mut self.0;
+        let mut unit = match front.parse(source) {
+            Ok(unit) => unit,
+            Err(errs) if errs.is_empty() => {
+                return stamped(vec![Diagnostic::new("syntax error")], Phase::Parse)
+            }
+            Err(errs) => return stamped(errs, Phase::Parse),
+        };
+        let mut diagnostics = Vec::new();
+        for phase in [Phase::Declare, Phase::Resolve, Phase::TypeCheck] {
+            if phase > last {
+                break;
+            }
+            let errs = match phase {
+                Phase::Declare => front.declare(&mut unit),
+                Phase::Resolve => front.resolve(&mut unit),
+                _ => front.typecheck(&mut unit),
+            };
+            diagnostics.extend(errs.into_iter().map(|d| d.in_phase(phase)));
+            if !diagnostics.is_empty() && !keep_going {
+                break;
+            }
+        }
+        if diagnostics.is_empty() {
+            Outcome::accepted()
+        } else {
+            Outcome::rejected(diagnostics)
+        }
+    }
+}
+
+impl<F: FrontEnd> Compiler for Phased<F> {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn compile(&mut self, case: &TestCase) -> Outcome {
+        if case.dir == CASCADE_DIR {
+            return self.run_all(&case.source);
+        }
+        let last = case.header.phase.unwrap_or(Phase::TypeCheck);
+        self.run_through(&case.source, last)
+    }
+}
+
+fn stamped(errs: Vec<Diagnostic>, phase: Phase) -> Outcome {
+    Outcome::rejected(errs.into_iter().map(|d| d.in_phase(phase)).collect())
+}
@@ -0,0 +1,1245 @@
+//! Random KXI programs that every compiler must accept.
+//!
+//! `valid_kxi/` shows each rule of the language on a few lines. A
+//! [`Generator`] combines all of them into programs of any size: classes
+//! with static and instance fields and methods, constructors, nested
+//! blocks, loops, `switch` on ints and chars, arrays of any rank and chains
+//! such as `C0.m3().f1[2]`. The same seed always gives the same program.
+//!
+//! ```
+//! let source = kxi_harness::Generator::new(7).source();
+//! let program = kxi_ref::parse(&source).unwrap();
+//! assert!(kxi_ref::sema::analyze(&program).is_ok());
+//! ```
+//!
+//! Programs are well scoped and well typed by construction, and they also
+//! run to the end without a runtime error, so what they print can be
+//! compared between compilers:
+//!
+//! * every loop counts up to a constant of at most 3, with a counter the
+//!   body does not assign;
+//! * a method only calls methods declared before it, so nothing recurses;
+//! * the steps a body takes, as `kxi_ref`'s interpreter counts them, are
+//!   added up as it is written, and a statement, call or `new` that would
+//!   take `main` past [`MAIN_BUDGET`], or a method past its own budget, is
+//!   not written. Only a method that has to return an object it cannot
+//!   afford goes over, and its callers are charged for that;
+//! * variables of class type always hold an object, and arrays are filled
+//!   in as soon as they are declared;
+//! * indexes are constants within bounds, and only constants from 1 to 9
+//!   are divided by.
+//!
+//! Nothing reads `cin`. Names are unique in the whole program: classes are
+//! `C0`, `C1`... and fields, methods, parameters and variables `f`, `m`,
+//! `p` and `v` (`a` for arrays and `i` for loop counters) followed by a
+//! number.
+
+use kxi_ref::ast::*;
+use kxi_ref::print;
+
+/// SplitMix64. Small, and the same on every platform, which is all a seed
+/// needs.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, for `n > 0`.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// A number in `lo..=hi`.
+    pub(crate) fn range(&mut self, lo: usize, hi: usize) -> usize {
+        lo + self.below(hi.max(lo) - lo + 1)
+    }
+
+    pub(crate) fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    pub(crate) fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+/// Steps a method or constructor may take, counting calls.
+const METHOD_BUDGET: u64 = 300;
+/// Steps a whole program takes at most, whatever the settings of its
+/// [`Generator`], as counted by `kxi_ref::Interpreter::max_steps`.
+pub const MAIN_BUDGET: u64 = 5_000;
+/// Largest number of times a loop runs.
+const LOOP_BOUND: usize = 3;
+
+const STRINGS: &[&str] = &["kxi", "hello", "x = ", " ", "\n", ""];
+
+/// Builds random well-typed programs from a seed.
+///
+/// ```
+/// use kxi_harness::Generator;
+///
+/// let small = Generator::new(42).classes(1).statements(3).depth(2).source();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Generator {
+    seed: u64,
+    classes: usize,
+    members: usize,
+    statements: usize,
+    depth: usize,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Generator {
+        Generator {
+            seed,
+            classes: 3,
+            members: 4,
+            statements: 5,
+            depth: 3,
+        }
+    }
+
+    /// Largest number of classes, 3 by default.
+    pub fn classes(mut self, n: usize) -> Generator {
+        self.classes = n;
+        self
+    }
+
+    /// Largest number of fields, and of methods, per class. 4 by default.
+    pub fn members(mut self, n: usize) -> Generator {
+        self.members = n;
+        self
+    }
+
+    /// Largest number of statements per block, 5 by default. `main` gets
+    /// twice as many.
+    pub fn statements(mut self, n: usize) -> Generator {
+        self.statements = n.max(1);
+        self
+    }
+
+    /// How deeply statements, and expressions, nest. 3 by default.
+    pub fn depth(mut self, n: usize) -> Generator {
+        self.depth = n;
+        self
+    }
+
+    /// The same settings with another seed.
+    pub fn with_seed(mut self, seed: u64) -> Generator {
+        self.seed = seed;
+        self
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The program, parsed back from [`Generator::source`] so that its line
+    /// numbers are those of the source.
+    pub fn program(&self) -> Program {
+        let source = self.source();
+        kxi_ref::parse(&source)
+            .unwrap_or_else(|e| panic!("seed {} does not parse: {}\n{}", self.seed, e, source))
+    }
+
+    /// The program as source text, starting with a comment naming the seed.
+    pub fn source(&self) -> String {
+        let program = Builder::new(*self).program();
+        format!(
+            "// generated by kxi-gen from seed {}\n{}",
+            self.seed,
+            print::program(&program)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ClassPlan {
+    name: String,
+    /// Position of the constructor among everything that runs code, see
+    /// `MethodPlan::order`. Set even for classes without a constructor,
+    /// since their field initializers run on `new`.
+    built: usize,
+    ctor: Option<Vec<Param>>,
+    ctor_cost: u64,
+    fields: Vec<FieldPlan>,
+    methods: Vec<MethodPlan>,
+}
+
+#[derive(Debug, Clone)]
+struct FieldPlan {
+    name: String,
+    ty: Type,
+    is_static: bool,
+    public: bool,
+}
+
+#[derive(Debug, Clone)]
+struct MethodPlan {
+    name: String,
+    params: Vec<Param>,
+    ret: Type,
+    is_static: bool,
+    public: bool,
+    /// Code may only call methods and constructors with a lower order.
+    order: usize,
+    cost: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Local {
+    name: String,
+    ty: Type,
+    assignable: bool,
+    /// The size of each dimension, for arrays.
+    sizes: Vec<usize>,
+}
+
+/// The method, constructor or `main` being written.
+#[derive(Debug, Clone, Default)]
+struct Body {
+    class: Option<usize>,
+    is_static: bool,
+    order: usize,
+    /// The return type, if the body may return early.
+    ret: Option<Type>,
+    scopes: Vec<Vec<Local>>,
+    breakable: usize,
+    /// How many times the statement being written runs per call at most.
+    multiplier: u64,
+    cost: u64,
+    budget: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Receiver {
+    /// `m()`
+    Bare,
+    /// `this.m()`
+    This,
+    /// `C0.m()`
+    Class,
+    /// `object.m()`
+    Object,
+}
+
+/// Where a value comes from.
+#[derive(Debug, Clone)]
+enum Source {
+    Literal,
+    Local(String),
+    /// A fully indexed array, with the size of each dimension.
+    Element(String, Vec<usize>),
+    This,
+    /// A field of the current class by its bare name.
+    OwnField(String),
+    ThisField(String),
+    /// `C0.f1`
+    StaticField(usize, String),
+    /// `object.f1`, where `object` is of the given class.
+    Field(usize, String),
+    Call(usize, usize, Receiver),
+    New(usize),
+    Arithmetic,
+    Negate,
+    Compare,
+    Logic,
+    Not,
+}
+
+struct Builder {
+    rng: Rng,
+    config: Generator,
+    classes: Vec<ClassPlan>,
+    names: usize,
+    body: Body,
+}
+
+fn expr(kind: ExprKind) -> Expr {
+    Expr {
+        line: 0,
+        column: 0,
+        kind,
+    }
+}
+
+fn stmt(kind: StmtKind) -> Stmt {
+    Stmt { line: 0, kind }
+}
+
+fn ident(name: &str) -> Expr {
+    expr(ExprKind::Ident(name.to_string()))
+}
+
+fn int(n: usize) -> Expr {
+    expr(ExprKind::Literal(Literal::Int(n as i32)))
+}
+
+fn member(object: Expr, name: &str) -> Expr {
+    expr(ExprKind::Member(Box::new(object), name.to_string()))
+}
+
+fn binary(op: BinaryOp, lhs: Expr, rhs: Expr) -> Expr {
+    expr(ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)))
+}
+
+fn assign(target: Expr, value: Expr) -> Stmt {
+    stmt(StmtKind::Expr(expr(ExprKind::Assign(
+        Box::new(target),
+        Box::new(value),
+    ))))
+}
+
+fn index(array: &str, indexes: impl IntoIterator<Item = Expr>) -> Expr {
+    indexes.into_iter().fold(ident(array), |array, index| {
+        expr(ExprKind::Index(Box::new(array), Box::new(index)))
+    })
+}
+
+/// `ty` with `rank` pairs of brackets.
+fn array_of(ty: &Type, rank: usize) -> Type {
+    (0..rank).fold(ty.clone(), |ty, _| Type::Array(Box::new(ty)))
+}
+
+fn is_primitive(ty: &Type) -> bool {
+    matches!(ty, Type::Int | Type::Char | Type::Bool | Type::String)
+}
+
+/// `int i = 0;`, `i < bound` and `i = i + 1` for a loop counting to
+/// `bound`.
+fn counting(counter: &str, bound: usize) -> (Expr, Expr, Expr) {
+    (
+        expr(ExprKind::Assign(Box::new(ident(counter)), Box::new(int(0)))),
+        binary(BinaryOp::Less, ident(counter), int(bound)),
+        expr(ExprKind::Assign(
+            Box::new(ident(counter)),
+            Box::new(binary(BinaryOp::Add, ident(counter), int(1))),
+        )),
+    )
+}
+
+impl Builder {
+    fn new(config: Generator) -> Builder {
+        Builder {
+            rng: Rng::new(config.seed),
+            config,
+            classes: Vec::new(),
+            names: 0,
+            body: Body::default(),
+        }
+    }
+
+    fn fresh(&mut self, prefix: &str) -> String {
+        self.names += 1;
+        format!("{}{}", prefix, self.names - 1)
+    }
+
+    fn program(mut self) -> Program {
+        self.plan();
+        let classes = (0..self.classes.len()).map(|i| self.class(i)).collect();
+        self.body = Body {
+            is_static: true,
+            order: usize::MAX,
+            scopes: vec![Vec::new()],
+            multiplier: 1,
+            // The call of `main` itself.
+            cost: 1,
+            budget: MAIN_BUDGET,
+            ..Body::default()
+        };
+        let statements = self.config.statements * 2;
+        let body = self.block_of(statements, self.config.depth);
+        Program {
+            classes,
+            main: Main {
+                name: "main".to_string(),
+                line: 0,
+                body,
+            },
+        }
+    }
+
+    /// Decides the classes and the signatures of their members, so bodies
+    /// can refer to members of classes that come later.
+    fn plan(&mut self) {
+        let mut order = 0;
+        for i in 0..self.rng.range(1, self.config.classes) {
+            order += 1;
+            let built = order;
+            self.classes.push(ClassPlan {
+                name: format!("C{}", i),
+                built,
+                ctor: None,
+                ctor_cost: 0,
+                fields: Vec::new(),
+                methods: Vec::new(),
+            });
+            if self.rng.chance(60) {
+                let params = self.params(built);
+                self.classes[i].ctor = Some(params);
+            }
+            for _ in 0..self.rng.range(0, self.config.members) {
+                let is_static = self.rng.chance(30);
+                let ty = if is_static {
+                    self.primitive()
+                } else {
+                    self.value_type(built)
+                };
+                let field = FieldPlan {
+                    name: self.fresh("f"),
+                    ty,
+                    is_static,
+                    public: self.rng.chance(70),
+                };
+                self.classes[i].fields.push(field);
+            }
+            for _ in 0..self.rng.range(1, self.config.members) {
+                order += 1;
+                let ret = if self.rng.chance(25) {
+                    Type::Void
+                } else {
+                    self.value_type(order)
+                };
+                let method = MethodPlan {
+                    name: self.fresh("m"),
+                    params: self.params(order),
+                    ret,
+                    is_static: self.rng.chance(40),
+                    public: self.rng.chance(70),
+                    order,
+                    cost: 0,
+                };
+                self.classes[i].methods.push(method);
+            }
+        }
+    }
+
+    fn params(&mut self, order: usize) -> Vec<Param> {
+        (0..self.rng.range(0, 2))
+            .map(|_| Param {
+                ty: self.value_type(order),
+                name: self.fresh("p"),
+                line: 0,
+            })
+            .collect()
+    }
+
+    fn primitive(&mut self) -> Type {
+        self.rng
+            .pick(&[Type::Int, Type::Int, Type::Char, Type::Bool, Type::String])
+            .clone()
+    }
+
+    /// A type whose values code at `order` can create: a primitive, or a
+    /// class constructed by earlier code.
+    fn value_type(&mut self, order: usize) -> Type {
+        let classes: Vec<Type> = self
+            .classes
+            .iter()
+            .filter(|c| c.built < order)
+            .map(|c| Type::Class(c.name.clone()))
+            .collect();
+        if !classes.is_empty() && self.rng.chance(25) {
+            return self.rng.pick(&classes).clone();
+        }
+        self.primitive()
+    }
+
+    fn class(&mut self, i: usize) -> ClassDef {
+        let plan = self.classes[i].clone();
+        let mut members = Vec::new();
+        let mut init_cost = 0;
+        for field in &plan.fields {
+            let init = match &field.ty {
+                Type::Class(_) => {
+                    let (init, cost) = self.constant(&field.ty);
+                    init_cost += cost;
+                    Some(init)
+                }
+                Type::Char => Some(self.constant(&field.ty).0),
+                _ if self.rng.chance(50) => Some(self.constant(&field.ty).0),
+                _ => None,
+            };
+            members.push(Member::Field(Field {
+                modifiers: Modifiers {
+                    is_static: field.is_static,
+                    visibility: visibility(field.public),
+                },
+                ty: field.ty.clone(),
+                name: field.name.clone(),
+                init,
+                line: 0,
+            }));
+        }
+        let mut ctor_cost = init_cost;
+        if let Some(params) = &plan.ctor {
+            self.start(Some(i), false, plan.built, None, params);
+            let body = self.block(self.config.depth);
+            ctor_cost += self.body.cost;
+            members.push(Member::Constructor(Constructor {
+                name: plan.name.clone(),
+                params: params.clone(),
+                body,
+                line: 0,
+            }));
+        }
+        self.classes[i].ctor_cost = ctor_cost;
+        for (k, method) in plan.methods.iter().enumerate() {
+            let ret = Some(method.ret.clone());
+            self.start(Some(i), method.is_static, method.order, ret, &method.params);
+            let returns = method.ret != Type::Void;
+            let reserve = if returns {
+                1 + self.min_cost(&method.ret)
+            } else {
+                0
+            };
+            let mut body = self.reserving(reserve, |b| b.block(b.config.depth));
+            if returns {
+                self.spend(1);
+                let value = self.value(&method.ret, self.config.depth);
+                body.push(stmt(StmtKind::Return(Some(value))));
+            }
+            self.classes[i].methods[k].cost = self.body.cost;
+            members.push(Member::Method(Method {
+                modifiers: Modifiers {
+                    is_static: method.is_static,
+                    visibility: visibility(method.public),
+                },
+                ret: method.ret.clone(),
+                name: method.name.clone(),
+                params: method.params.clone(),
+                body,
+                line: 0,
+            }));
+        }
+        ClassDef {
+            name: plan.name,
+            line: 0,
+            members,
+        }
+    }
+
+    /// Starts writing a method or constructor body.
+    fn start(
+        &mut self,
+        class: Option<usize>,
+        is_static: bool,
+        order: usize,
+        ret: Option<Type>,
+        params: &[Param],
+    ) {
+        let params = params
+            .iter()
+            .map(|p| Local {
+                name: p.name.clone(),
+                ty: p.ty.clone(),
+                assignable: true,
+                sizes: Vec::new(),
+            })
+            .collect();
+        self.body = Body {
+            class,
+            is_static,
+            order,
+            ret,
+            scopes: vec![params],
+            breakable: 0,
+            multiplier: 1,
+            cost: 0,
+            budget: METHOD_BUDGET,
+        };
+    }
+
+    /// A field initializer: a literal, or a new object built from literals.
+    /// Returns it with the number of steps it takes.
+    fn constant(&mut self, ty: &Type) -> (Expr, u64) {
+        let cost = self.constant_cost(ty);
+        let Type::Class(name) = ty else {
+            return (self.literal(ty), cost);
+        };
+        let j = self.class_index(name);
+        let params = self.classes[j].ctor.clone().unwrap_or_default();
+        let args = params.iter().map(|p| self.constant(&p.ty).0).collect();
+        (expr(ExprKind::New(name.clone(), args)), cost)
+    }
+
+    /// The number of steps [`Builder::constant`] of `ty` takes.
+    fn constant_cost(&self, ty: &Type) -> u64 {
+        let Type::Class(name) = ty else {
+            return 0;
+        };
+        let class = &self.classes[self.class_index(name)];
+        let params = class.ctor.as_deref().unwrap_or_default();
+        let args: u64 = params.iter().map(|p| self.constant_cost(&p.ty)).sum();
+        class.ctor_cost + 1 + args
+    }
+
+    fn class_index(&self, name: &str) -> usize {
+        self.classes.iter().position(|c| c.name == name).unwrap()
+    }
+
+    fn literal(&mut self, ty: &Type) -> Expr {
+        let literal = match ty {
+            Type::Int => Literal::Int(self.rng.range(0, 20) as i32),
+            Type::Char => Literal::Char((b'a' + self.rng.below(26) as u8) as char),
+            Type::Bool => Literal::Bool(self.rng.chance(50)),
+            Type::String => Literal::Str(self.rng.pick(STRINGS).to_string()),
+            _ => unreachable!("no literal of type {}", ty),
+        };
+        expr(ExprKind::Literal(literal))
+    }
+
+    /// Whether `steps` more steps each time the code being written runs
+    /// stay within the budget.
+    fn fits(&self, steps: u64) -> bool {
+        self.body.cost + self.body.multiplier * steps <= self.body.budget
+    }
+
+    fn spend(&mut self, steps: u64) {
+        self.body.cost += self.body.multiplier * steps;
+    }
+
+    /// Pays for a call of code taking `cost` steps.
+    fn charge(&mut self, cost: u64) {
+        self.spend(cost + 1);
+    }
+
+    /// Runs `f` with `steps` per run kept out of the budget, for what has
+    /// to be written after it.
+    fn reserving<T>(&mut self, steps: u64, f: impl FnOnce(&mut Builder) -> T) -> T {
+        let reserved = (self.body.multiplier * steps).min(self.body.budget);
+        self.body.budget -= reserved;
+        let result = f(self);
+        self.body.budget += reserved;
+        result
+    }
+
+    /// The steps the cheapest value of `ty` takes here: none for a literal
+    /// or a value at hand, or else those of a new object built from
+    /// constants.
+    fn min_cost(&self, ty: &Type) -> u64 {
+        if is_primitive(ty) || self.at_hand(ty) {
+            0
+        } else {
+            self.constant_cost(ty)
+        }
+    }
+
+    /// Whether some value of `ty` fits in the budget.
+    fn available(&self, ty: &Type) -> bool {
+        self.fits(self.min_cost(ty))
+    }
+
+    /// Whether a value of `ty` can be had without running anything: a
+    /// local, an element of a local array, `this` or a field of its class.
+    fn at_hand(&self, ty: &Type) -> bool {
+        let scopes = &self.body.scopes;
+        if scopes
+            .iter()
+            .flatten()
+            .any(|l| l.ty == array_of(ty, l.sizes.len()))
+        {
+            return true;
+        }
+        let Some(i) = self.body.class else {
+            return false;
+        };
+        let class = &self.classes[i];
+        let instance = !self.body.is_static;
+        (instance && *ty == Type::Class(class.name.clone()))
+            || class
+                .fields
+                .iter()
+                .any(|f| f.ty == *ty && (f.is_static || instance))
+    }
+
+    fn own(&self, class: usize) -> bool {
+        self.body.class == Some(class)
+    }
+
+    /// A block of up to the configured number of statements, in a scope of
+    /// its own.
+    fn block(&mut self, depth: usize) -> Vec<Stmt> {
+        let n = self.rng.range(1, self.config.statements);
+        self.block_of(n, depth)
+    }
+
+    fn block_of(&mut self, n: usize, depth: usize) -> Vec<Stmt> {
+        self.body.scopes.push(Vec::new());
+        let mut out = Vec::new();
+        for _ in 0..n {
+            if !self.fits(1) {
+                break;
+            }
+            self.statement(depth, &mut out);
+        }
+        self.body.scopes.pop();
+        out
+    }
+
+    fn declare(&mut self, local: Local) {
+        self.body.scopes.last_mut().unwrap().push(local);
+    }
+
+    /// Appends a statement to `out`, or a few for arrays and loops, which
+    /// need declarations first.
+    fn statement(&mut self, depth: usize, out: &mut Vec<Stmt>) {
+        self.spend(1);
+        let choices = if depth > 0 { 16 } else { 10 };
+        let choice = self.rng.below(choices);
+        // Steps taken besides those of the statement's parts, which mind the
+        // budget themselves: the blocks of an `if` and its `break` or
+        // `return`, the counter, blocks and steps of a loop and the breaks
+        // of a `switch`.
+        let overhead = match choice {
+            10 | 11 => 2,
+            12 | 13 => 2 + 3 * LOOP_BOUND as u64,
+            14 => 3,
+            _ => 0,
+        };
+        if !self.fits(overhead) {
+            out.push(self.cout());
+            return;
+        }
+        self.spend(overhead);
+        match choice {
+            0 | 1 => out.push(self.variable()),
+            2 => self.array(out),
+            3..=5 => out.push(self.assignment()),
+            6 | 7 => out.push(self.cout()),
+            8 | 9 => out.push(self.call_statement()),
+            10 | 11 => out.push(self.if_statement(depth)),
+            12 => self.while_loop(depth, out),
+            13 => self.for_loop(depth, out),
+            14 => out.push(self.switch(depth)),
+            _ => out.push(stmt(StmtKind::Block(self.block(depth - 1)))),
+        }
+    }
+
+    fn variable(&mut self) -> Stmt {
+        let ty = self.affordable_type();
+        let init = self.value(&ty, self.config.depth);
+        let name = self.fresh("v");
+        self.declare(Local {
+            name: name.clone(),
+            ty: ty.clone(),
+            assignable: true,
+            sizes: Vec::new(),
+        });
+        stmt(StmtKind::Var {
+            ty,
+            name,
+            init: Some(init),
+        })
+    }
+
+    /// Declares an array and fills it in, one nested loop per dimension:
+    ///
+    /// ```text
+    /// int[][] a0 = new int[][2];
+    /// int i1;
+    /// int i2;
+    /// for (i1 = 0; i1 < 2; i1 = i1 + 1) {
+    ///     a0[i1] = new int[3];
+    ///     for (i2 = 0; i2 < 3; i2 = i2 + 1) {
+    ///         a0[i1][i2] = 7;
+    ///     }
+    /// }
+    /// ```
+    fn array(&mut self, out: &mut Vec<Stmt>) {
+        let elem = self.value_type(self.body.order);
+        let rank = self.rng.range(1, 3);
+        let sizes: Vec<usize> = (0..rank).map(|_| self.rng.range(1, 3)).collect();
+        // A counter each and the outer loop, and for every iteration of a
+        // level a loop step, a block, an assignment and the next loop.
+        let mut iterations = 1;
+        let mut steps = rank as u64 + 1;
+        for &n in &sizes {
+            iterations *= n as u64;
+            steps += 4 * iterations;
+        }
+        if !self.fits(steps + iterations * self.min_cost(&elem)) {
+            out.push(self.cout());
+            return;
+        }
+        self.spend(steps);
+        let name = self.fresh("a");
+        let counters: Vec<String> = (0..rank).map(|_| self.fresh("i")).collect();
+        out.push(stmt(StmtKind::Var {
+            ty: array_of(&elem, rank),
+            name: name.clone(),
+            init: Some(expr(ExprKind::NewArray(
+                array_of(&elem, rank - 1),
+                Box::new(int(sizes[0])),
+            ))),
+        }));
+        for counter in &counters {
+            out.push(stmt(StmtKind::Var {
+                ty: Type::Int,
+                name: counter.clone(),
+                init: None,
+            }));
+            self.declare(Local {
+                name: counter.clone(),
+                ty: Type::Int,
+                assignable: false,
+                sizes: Vec::new(),
+            });
+        }
+        let multiplier = self.body.multiplier;
+        self.body.multiplier *= iterations;
+        let mut body = None;
+        for level in (0..rank).rev() {
+            let target = index(&name, counters[..=level].iter().map(|c| ident(c)));
+            let stmts = match body.take() {
+                None => {
+                    let value = self.value(&elem, self.config.depth.saturating_sub(1));
+                    vec![assign(target, value)]
+                }
+                Some(inner) => {
+                    let sub = expr(ExprKind::NewArray(
+                        array_of(&elem, rank - level - 2),
+                        Box::new(int(sizes[level + 1])),
+                    ));
+                    vec![assign(target, sub), inner]
+                }
+            };
+            let (init, cond, step) = counting(&counters[level], sizes[level]);
+            body = Some(stmt(StmtKind::For {
+                init: Some(init),
+                cond: Some(cond),
+                step: Some(step),
+                body: Box::new(stmt(StmtKind::Block(stmts))),
+            }));
+        }
+        out.extend(body);
+        self.body.multiplier = multiplier;
+        self.declare(Local {
+            name,
+            ty: array_of(&elem, rank),
+            assignable: false,
+            sizes,
+        });
+    }
+
+    fn assignment(&mut self) -> Stmt {
+        let ty = self.affordable_type();
+        let value_cost = self.min_cost(&ty);
+        match self.reserving(value_cost, |b| b.place(&ty)) {
+            Some(target) => {
+                let value = self.value(&ty, self.config.depth);
+                assign(target, value)
+            }
+            None => self.variable(),
+        }
+    }
+
+    fn cout(&mut self) -> Stmt {
+        let ty = self
+            .rng
+            .pick(&[Type::Int, Type::Char, Type::String, Type::String])
+            .clone();
+        stmt(StmtKind::Cout(self.value(&ty, self.config.depth)))
+    }
+
+    fn call_statement(&mut self) -> Stmt {
+        let mut calls = Vec::new();
+        for (m, class) in self.classes.iter().enumerate() {
+            for k in 0..class.methods.len() {
+                calls.extend(self.receivers(m, k).into_iter().map(|r| (m, k, r)));
+            }
+        }
+        if calls.is_empty() {
+            return self.cout();
+        }
+        let (m, k, receiver) = *self.rng.pick(&calls);
+        let call = self.call(m, k, receiver, self.config.depth);
+        stmt(StmtKind::Expr(call))
+    }
+
+    fn if_statement(&mut self, depth: usize) -> Stmt {
+        let cond = self.value(&Type::Bool, self.config.depth);
+        let mut then = self.block(depth - 1);
+        if self.body.breakable > 0 && self.rng.chance(20) {
+            then.push(stmt(StmtKind::Break));
+        } else if self.rng.chance(10) {
+            let ret = self.body.ret.clone();
+            if let Some(ret) = ret.filter(|r| *r == Type::Void || self.available(r)) {
+                let value = (ret != Type::Void).then(|| self.value(&ret, self.config.depth));
+                then.push(stmt(StmtKind::Return(value)));
+            }
+        }
+        let otherwise = self
+            .rng
+            .chance(40)
+            .then(|| Box::new(stmt(StmtKind::Block(self.block(depth - 1)))));
+        stmt(StmtKind::If {
+            cond,
+            then: Box::new(stmt(StmtKind::Block(then))),
+            otherwise,
+        })
+    }
+
+    /// Declares a counter that only the loop assigns, and writes the body
+    /// of a loop running `bound` times.
+    fn loop_body(&mut self, depth: usize, out: &mut Vec<Stmt>) -> (String, usize, Vec<Stmt>) {
+        let counter = self.fresh("i");
+        let bound = self.rng.range(1, LOOP_BOUND);
+        out.push(stmt(StmtKind::Var {
+            ty: Type::Int,
+            name: counter.clone(),
+            init: None,
+        }));
+        self.declare(Local {
+            name: counter.clone(),
+            ty: Type::Int,
+            assignable: false,
+            sizes: Vec::new(),
+        });
+        let multiplier = self.body.multiplier;
+        self.body.multiplier *= bound as u64;
+        self.body.breakable += 1;
+        let body = self.block(depth - 1);
+        self.body.breakable -= 1;
+        self.body.multiplier = multiplier;
+        (counter, bound, body)
+    }
+
+    fn while_loop(&mut self, depth: usize, out: &mut Vec<Stmt>) {
+        let (counter, bound, mut body) = self.loop_body(depth, out);
+        let (init, cond, step) = counting(&counter, bound);
+        out.push(stmt(StmtKind::Expr(init)));
+        body.push(stmt(StmtKind::Expr(step)));
+        out.push(stmt(StmtKind::While {
+            cond,
+            body: Box::new(stmt(StmtKind::Block(body))),
+        }));
+    }
+
+    fn for_loop(&mut self, depth: usize, out: &mut Vec<Stmt>) {
+        let (counter, bound, body) = self.loop_body(depth, out);
+        let (init, cond, step) = counting(&counter, bound);
+        out.push(stmt(StmtKind::For {
+            init: Some(init),
+            cond: Some(cond),
+            step: Some(step),
+            body: Box::new(stmt(StmtKind::Block(body))),
+        }));
+    }
+
+    fn switch(&mut self, depth: usize) -> Stmt {
+        let (ty, mut labels): (Type, Vec<Literal>) = if self.rng.chance(50) {
+            (Type::Int, (-1..=5).map(Literal::Int).collect())
+        } else {
+            (Type::Char, ('a'..='f').map(Literal::Char).collect())
+        };
+        let scrutinee = self.value(&ty, self.config.depth);
+        self.body.breakable += 1;
+        let mut cases = Vec::new();
+        for _ in 0..self.rng.range(1, 3) {
+            let label = labels.remove(self.rng.below(labels.len()));
+            let body = self.case_body(depth, true);
+            cases.push(Case {
+                line: 0,
+                label,
+                body,
+            });
+        }
+        let default = self.rng.chance(50).then(|| self.case_body(depth, false));
+        self.body.breakable -= 1;
+        stmt(StmtKind::Switch {
+            scrutinee,
+            cases,
+            default,
+        })
+    }
+
+    /// The statements of a case, which cannot use variables declared by
+    /// another case: jumping to a later case skips their declaration.
+    fn case_body(&mut self, depth: usize, may_break: bool) -> Vec<Stmt> {
+        let n = self.rng.range(0, 2);
+        let mut body = self.block_of(n, depth - 1);
+        if may_break && self.rng.chance(60) {
+            body.push(stmt(StmtKind::Break));
+        }
+        body
+    }
+
+    /// Something of type `ty` that can be assigned to, if there is one.
+    fn place(&mut self, ty: &Type) -> Option<Expr> {
+        let sources: Vec<Source> = self
+            .sources(ty, self.config.depth)
+            .into_iter()
+            .filter(|s| match s {
+                Source::Local(name) => self.local(name).is_some_and(|l| l.assignable),
+                Source::Element(..)
+                | Source::OwnField(_)
+                | Source::ThisField(_)
+                | Source::StaticField(..)
+                | Source::Field(..) => true,
+                _ => false,
+            })
+            .collect();
+        if sources.is_empty() {
+            return None;
+        }
+        let source = self.rng.pick(&sources).clone();
+        Some(self.build(source, ty, self.config.depth))
+    }
+
+    fn local(&self, name: &str) -> Option<&Local> {
+        self.body.scopes.iter().flatten().find(|l| l.name == name)
+    }
+
+    /// A type for a new variable, as [`Builder::value_type`], but a
+    /// primitive if no object of the class it picks fits in the budget.
+    fn affordable_type(&mut self) -> Type {
+        let ty = self.value_type(self.body.order);
+        if self.available(&ty) {
+            ty
+        } else {
+            self.primitive()
+        }
+    }
+
+    /// An expression of type `ty`, nesting at most `depth` deep, within the
+    /// budget if one is [`available`](Builder::available).
+    fn value(&mut self, ty: &Type, depth: usize) -> Expr {
+        let sources = self.sources(ty, depth);
+        if sources.is_empty() {
+            // Only the object a method returns is ever needed when none is
+            // available. Its cost goes into the method's, which callers pay.
+            let (value, cost) = self.constant(ty);
+            self.spend(cost);
+            return value;
+        }
+        let source = self.rng.pick(&sources).clone();
+        self.build(source, ty, depth)
+    }
+
+    /// Values of `types` in order, each leaving enough of the budget for
+    /// the cheapest values of those after it.
+    fn values(&mut self, types: &[Type], depth: usize) -> Vec<Expr> {
+        let costs: Vec<u64> = types.iter().map(|ty| self.min_cost(ty)).collect();
+        types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                let later = costs[i + 1..].iter().sum();
+                self.reserving(later, |b| b.value(ty, depth))
+            })
+            .collect()
+    }
+
+    /// Everything a value of type `ty` can come from here.
+    fn sources(&self, ty: &Type, depth: usize) -> Vec<Source> {
+        let mut sources = Vec::new();
+        if is_primitive(ty) {
+            sources.push(Source::Literal);
+        }
+        for local in self.body.scopes.iter().flatten() {
+            if local.sizes.is_empty() && local.ty == *ty {
+                sources.push(Source::Local(local.name.clone()));
+            } else if !local.sizes.is_empty() && local.ty == array_of(ty, local.sizes.len()) {
+                sources.push(Source::Element(local.name.clone(), local.sizes.clone()));
+            }
+        }
+        if let Some(i) = self.body.class {
+            let class = &self.classes[i];
+            if !self.body.is_static && *ty == Type::Class(class.name.clone()) {
+                sources.push(Source::This);
+            }
+            for field in class.fields.iter().filter(|f| f.ty == *ty) {
+                if field.is_static || !self.body.is_static {
+                    sources.push(Source::OwnField(field.name.clone()));
+                }
+                if !field.is_static && !self.body.is_static {
+                    sources.push(Source::ThisField(field.name.clone()));
+                }
+            }
+        }
+        for (m, class) in self.classes.iter().enumerate() {
+            let visible = |public: bool| public || self.own(m);
+            for field in class
+                .fields
+                .iter()
+                .filter(|f| f.ty == *ty && visible(f.public))
+            {
+                if field.is_static {
+                    sources.push(Source::StaticField(m, field.name.clone()));
+                } else if depth > 0
+                    && class.built < self.body.order
+                    && self.available(&Type::Class(class.name.clone()))
+                {
+                    sources.push(Source::Field(m, field.name.clone()));
+                }
+            }
+            if depth > 0 {
+                for (k, method) in class.methods.iter().enumerate() {
+                    if method.ret == *ty {
+                        let receivers = self.receivers(m, k);
+                        sources.extend(receivers.into_iter().map(|r| Source::Call(m, k, r)));
+                    }
+                }
+            }
+            if *ty == Type::Class(class.name.clone())
+                && class.built < self.body.order
+                && self.fits(self.constant_cost(ty))
+            {
+                sources.push(Source::New(m));
+            }
+        }
+        if depth > 0 {
+            match ty {
+                Type::Int => {
+                    sources.extend([Source::Arithmetic, Source::Arithmetic, Source::Negate])
+                }
+                Type::Bool => {
+                    sources.extend([Source::Compare, Source::Compare, Source::Logic, Source::Not])
+                }
+                _ => {}
+            }
+        }
+        sources
+    }
+
+    /// The ways method `k` of class `m` can be called from here, arguments
+    /// and all, if it can be called at all.
+    fn receivers(&self, m: usize, k: usize) -> Vec<Receiver> {
+        let method = &self.classes[m].methods[k];
+        let args: u64 = method.params.iter().map(|p| self.min_cost(&p.ty)).sum();
+        let steps = method.cost + 1 + args;
+        if method.order >= self.body.order || !(method.public || self.own(m)) || !self.fits(steps) {
+            return Vec::new();
+        }
+        let mut receivers = Vec::new();
+        if method.is_static {
+            receivers.push(Receiver::Class);
+            if self.own(m) {
+                receivers.push(Receiver::Bare);
+            }
+        } else {
+            let class = Type::Class(self.classes[m].name.clone());
+            if self.fits(steps + self.min_cost(&class)) {
+                receivers.push(Receiver::Object);
+            }
+            if self.own(m) && !self.body.is_static {
+                receivers.extend([Receiver::Bare, Receiver::This]);
+            }
+        }
+        receivers
+    }
+
+    fn build(&mut self, source: Source, ty: &Type, depth: usize) -> Expr {
+        let inner = depth.saturating_sub(1);
+        match source {
+            Source::Literal => self.literal(ty),
+            Source::Local(name) | Source::OwnField(name) => ident(&name),
+            Source::Element(name, sizes) => {
+                let indexes: Vec<Expr> = sizes.iter().map(|&n| int(self.rng.below(n))).collect();
+                index(&name, indexes)
+            }
+            Source::This => expr(ExprKind::This),
+            Source::ThisField(name) => member(expr(ExprKind::This), &name),
+            Source::StaticField(m, name) => member(ident(&self.classes[m].name), &name),
+            Source::Field(m, name) => {
+                let class = Type::Class(self.classes[m].name.clone());
+                let object = self.value(&class, inner);
+                member(object, &name)
+            }
+            Source::Call(m, k, receiver) => self.call(m, k, receiver, depth),
+            Source::New(m) => {
+                self.charge(self.classes[m].ctor_cost);
+                let params = self.classes[m].ctor.clone().unwrap_or_default();
+                let types: Vec<Type> = params.into_iter().map(|p| p.ty).collect();
+                let args = self.values(&types, inner);
+                expr(ExprKind::New(self.classes[m].name.clone(), args))
+            }
+            Source::Arithmetic => {
+                let op = *self.rng.pick(&[
+                    BinaryOp::Add,
+                    BinaryOp::Add,
+                    BinaryOp::Sub,
+                    BinaryOp::Mul,
+                    BinaryOp::Div,
+                    BinaryOp::Mod,
+                ]);
+                let lhs = self.value(&Type::Int, inner);
+                let rhs = match op {
+                    BinaryOp::Add | BinaryOp::Sub => self.value(&Type::Int, inner),
+                    BinaryOp::Mul => int(self.rng.range(0, 9)),
+                    _ => int(self.rng.range(1, 9)),
+                };
+                binary(op, lhs, rhs)
+            }
+            Source::Negate => {
+                let operand = self.value(&Type::Int, inner);
+                expr(ExprKind::Unary(UnaryOp::Neg, Box::new(operand)))
+            }
+            Source::Compare => {
+                let ty = self.rng.pick(&[Type::Int, Type::Char]).clone();
+                let op = *self.rng.pick(&[
+                    BinaryOp::Eq,
+                    BinaryOp::NotEq,
+                    BinaryOp::Less,
+                    BinaryOp::LessEq,
+                    BinaryOp::Greater,
+                    BinaryOp::GreaterEq,
+                ]);
+                let lhs = self.value(&ty, inner);
+                let rhs = self.value(&ty, inner);
+                binary(op, lhs, rhs)
+            }
+            Source::Logic => {
+                let op = *self.rng.pick(&[BinaryOp::And, BinaryOp::Or, BinaryOp::Eq]);
+                let lhs = self.value(&Type::Bool, inner);
+                let rhs = self.value(&Type::Bool, inner);
+                binary(op, lhs, rhs)
+            }
+            Source::Not => {
+                let operand = self.value(&Type::Bool, inner);
+                expr(ExprKind::Unary(UnaryOp::Not, Box::new(operand)))
+            }
+        }
+    }
+
+    fn call(&mut self, m: usize, k: usize, receiver: Receiver, depth: usize) -> Expr {
+        let inner = depth.saturating_sub(1);
+        let method = self.classes[m].methods[k].clone();
+        self.charge(method.cost);
+        // The object comes first, so that the arguments are paid for.
+        let mut types = Vec::new();
+        if let Receiver::Object = receiver {
+            types.push(Type::Class(self.classes[m].name.clone()));
+        }
+        types.extend(method.params.iter().map(|p| p.ty.clone()));
+        let mut args = self.values(&types, inner);
+        let callee = match receiver {
+            Receiver::Bare => ident(&method.name),
+            Receiver::This => member(expr(ExprKind::This), &method.name),
+            Receiver::Class => member(ident(&self.classes[m].name), &method.name),
+            Receiver::Object => member(args.remove(0), &method.name),
+        };
+        expr(ExprKind::Call(Box::new(callee), args))
+    }
+}
+
+fn visibility(public: bool) -> Visibility {
+    if public {
+        Visibility::Public
+    } else {
+        Visibility::Private
+    }
+}
@@ -0,0 +1,187 @@
+//! Golden-file tests: every `.kxi` file in a directory sits next to a file
+//! holding the output some tool must produce for it.
+//!
+//! `lexer_kxi/` pairs each program with its token stream in a `.tokens`
+//! file, `valid_parse/` with its syntax tree in an `.ast` file and
+//! `invalid_parse/` with the position of its syntax error, also in an `.ast`
+//! file. Any lexer or parser can be checked against them by rendering its
+//! output in the same format and handing that renderer to [`assert_goldens`].
+//!
+//! Lines are compared exactly, except that two `error` lines at the same
+//! position match whatever their messages say.
+//!
+//! Setting `KXI_BLESS=1` rewrites the golden files with the current output
+//! instead of comparing against them.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A program whose output differs from its golden file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub path: PathBuf,
+    /// 1-based line of the first difference.
+    pub line: usize,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: expected `{}`, got `{}`",
+            self.path.display(),
+            self.line,
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+/// The `.kxi` files directly inside `dir`, sorted by name.
+pub fn programs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "kxi") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Renders every program in `dir` and compares the result with the file of
+/// the same name and extension `golden`. A missing golden file counts as
+/// empty.
+pub fn check_goldens(
+    dir: &Path,
+    golden: &str,
+    mut render: impl FnMut(&str) -> String,
+) -> io::Result<Vec<Mismatch>> {
+    let mut mismatches = Vec::new();
+    for path in programs(dir)? {
+        let actual = render(&fs::read_to_string(&path)?);
+        let golden_path = path.with_extension(golden);
+        let expected = match fs::read_to_string(&golden_path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        if let Some((line, expected, actual)) = first_difference(&expected, &actual) {
+            mismatches.push(Mismatch {
+                path: golden_path,
+                line,
+                expected,
+                actual,
+            });
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Rewrites every golden file in `dir` with the current output.
+pub fn bless(dir: &Path, golden: &str, mut render: impl FnMut(&str) -> String) -> io::Result<()> {
+    for path in programs(dir)? {
+        let actual = render(&fs::read_to_string(&path)?);
+        fs::write(path.with_extension(golden), actual)?;
+    }
+    Ok(())
+}
+
+/// Panics with every mismatch if any program's output differs from its
+/// golden file. With `KXI_BLESS` set, [`bless`]es the files instead.
+pub fn assert_goldens(dir: impl AsRef<Path>, golden: &str, render: impl FnMut(&str) -> String) {
+    let dir = dir.as_ref();
+    if env::var_os("KXI_BLESS").is_some_and(|v| v != "0") {
+        bless(dir, golden, render)
+            .unwrap_or_else(|e| panic!("could not bless {}: {}", dir.display(), e));
+        return;
+    }
+    let mismatches = check_goldens(dir, golden, render)
+        .unwrap_or_else(|e| panic!("could not read {}: {}", dir.display(), e));
+    if !mismatches.is_empty() {
+        let lines: Vec<String> = mismatches.iter().map(ToString::to_string).collect();
+        panic!(
+            "{} golden files differ (rerun with KXI_BLESS=1 to update them)\n{}",
+            mismatches.len(),
+            lines.join("\n")
+        );
+    }
+}
+
+/// Lines are equal if they match exactly, or if both are errors at the same
+/// position: error messages are for people and may differ between tools.
+fn same_line(expected: &str, actual: &str) -> bool {
+    let error_at = |line: &str| {
+        let (position, rest) = line.split_once(' ')?;
+        (rest == "error" || rest.starts_with("error ")).then_some(position.to_string())
+    };
+    expected == actual || error_at(expected).is_some_and(|p| error_at(actual) == Some(p))
+}
+
+/// The first line where `expected` and `actual` differ, ignoring `\r`.
+fn first_difference(expected: &str, actual: &str) -> Option<(usize, String, String)> {
+    first_difference_by(expected, actual, same_line)
+}
+
+/// [`first_difference`] with lines compared by `same`.
+pub(crate) fn first_difference_by(
+    expected: &str,
+    actual: &str,
+    same: impl Fn(&str, &str) -> bool,
+) -> Option<(usize, String, String)> {
+    let mut expected = expected.lines().map(|l| l.trim_end_matches('\r'));
+    let mut actual = actual.lines();
+    for line in 1.. {
+        match (expected.next(), actual.next()) {
+            (None, None) => return None,
+            (Some(e), Some(a)) if same(e, a) => continue,
+            (e, a) => {
+                return Some((
+                    line,
+                    e.unwrap_or("<end of file>").to_string(),
+                    a.unwrap_or("<end of file>").to_string(),
+                ))
+            }
+        }
+    }
+    unreachable!()
+}
+
+/// Location of a golden corpus such as `lexer_kxi` in this repository.
+pub fn dir(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_messages_may_differ() {
+        assert_eq!(
+            first_difference(
+                "1:1 ident x\n2:3 error bad\n",
+                "1:1 ident x\n2:3 error oops\n"
+            ),
+            None
+        );
+        assert_eq!(
+            first_difference("2:3 error bad\n", "2:4 error bad\n"),
+            Some((1, "2:3 error bad".into(), "2:4 error bad".into()))
+        );
+    }
+
+    #[test]
+    fn missing_lines_are_reported() {
+        assert_eq!(
+            first_difference("1:1 eof\r\n", ""),
+            Some((1, "1:1 eof".into(), "<end of file>".into()))
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! The kinds of error a KXI compiler reports, and those a compiled program
+//! stops with.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// What a diagnostic complains about, spelled in kebab case in annotations
+/// (`// error: type-mismatch`) and in JSON diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    /// The parser could not make sense of the program.
+    SyntaxError,
+    /// A class, member, parameter or variable is declared twice in the same
+    /// scope.
+    DuplicateDeclaration,
+    /// A constructor is not named after its class.
+    InvalidConstructor,
+    /// The program does not end with `void main()`.
+    MissingMain,
+    /// A name that is not declared where it is used.
+    UndeclaredIdentifier,
+    /// A type that names no class.
+    UnknownType,
+    /// A member that the class does not declare.
+    UnknownMember,
+    /// An instance member used from a static method or through the class
+    /// name.
+    StaticContext,
+    /// `this` outside an instance method or constructor.
+    InvalidThis,
+    /// A private member used outside its class.
+    PrivateAccess,
+    /// `break` outside a loop or `switch`.
+    MisplacedBreak,
+    /// An expression or statement whose types do not fit together.
+    TypeMismatch,
+    /// A call or `new` with the wrong number of arguments.
+    BadArgCount,
+    /// A non-void method that can end without returning a value.
+    MissingReturn,
+    /// A statement that can never run, such as one after a `return`. The
+    /// reference does not report it, so no test can ask for it yet.
+    UnreachableCode,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 15] = [
+        Kind::SyntaxError,
+        Kind::DuplicateDeclaration,
+        Kind::InvalidConstructor,
+        Kind::MissingMain,
+        Kind::UndeclaredIdentifier,
+        Kind::UnknownType,
+        Kind::UnknownMember,
+        Kind::StaticContext,
+        Kind::InvalidThis,
+        Kind::PrivateAccess,
+        Kind::MisplacedBreak,
+        Kind::TypeMismatch,
+        Kind::BadArgCount,
+        Kind::MissingReturn,
+        Kind::UnreachableCode,
+    ];
+
+    /// The spelling used in annotations, e.g. `type-mismatch`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::SyntaxError => "syntax-error",
+            Kind::DuplicateDeclaration => "duplicate-declaration",
+            Kind::InvalidConstructor => "invalid-constructor",
+            Kind::MissingMain => "missing-main",
+            Kind::UndeclaredIdentifier => "undeclared-identifier",
+            Kind::UnknownType => "unknown-type",
+            Kind::UnknownMember => "unknown-member",
+            Kind::StaticContext => "static-context",
+            Kind::InvalidThis => "invalid-this",
+            Kind::PrivateAccess => "private-access",
+            Kind::MisplacedBreak => "misplaced-break",
+            Kind::TypeMismatch => "type-mismatch",
+            Kind::BadArgCount => "bad-arg-count",
+            Kind::MissingReturn => "missing-return",
+            Kind::UnreachableCode => "unreachable-code",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Kind, String> {
+        Kind::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| format!("unknown error kind `{}`", s))
+    }
+}
+
+/// What a compiled program stops with, spelled in kebab case in headers
+/// (`// expect: runtime-error=null-dereference`) and on the program's stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RuntimeError {
+    /// A member or element of `null` was used.
+    NullDereference,
+    /// An array was indexed outside its bounds.
+    IndexOutOfBounds,
+    /// An integer was divided by zero, or its remainder taken.
+    DivisionByZero,
+    /// Calls were nested too deep, as in unbounded recursion.
+    StackOverflow,
+}
+
+impl RuntimeError {
+    pub const ALL: [RuntimeError; 4] = [
+        RuntimeError::NullDereference,
+        RuntimeError::IndexOutOfBounds,
+        RuntimeError::DivisionByZero,
+        RuntimeError::StackOverflow,
+    ];
+
+    /// The spelling used in headers, e.g. `null-dereference`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RuntimeError::NullDereference => "null-dereference",
+            RuntimeError::IndexOutOfBounds => "index-out-of-bounds",
+            RuntimeError::DivisionByZero => "division-by-zero",
+            RuntimeError::StackOverflow => "stack-overflow",
+        }
+    }
+
+    /// The runtime error named last in `text`, such as a program's stderr.
+    /// Only whole words count: `division-by-zero-ish` names none.
+    pub fn named_in(text: &str) -> Option<RuntimeError> {
+        text.rsplit(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+            .find_map(|word| word.parse().ok())
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RuntimeError {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RuntimeError, String> {
+        RuntimeError::ALL
+            .into_iter()
+            .find(|k| k.as_str() == s)
+            .ok_or_else(|| format!("unknown runtime error `{}`", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spellings_round_trip() {
+        for kind in Kind::ALL {
+            assert_eq!(kind.as_str().parse(), Ok(kind));
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(json, format!("\"{}\"", kind));
+        }
+        assert!("TypeMismatch".parse::<Kind>().is_err());
+        for error in RuntimeError::ALL {
+            assert_eq!(error.as_str().parse(), Ok(error));
+        }
+    }
+
+    #[test]
+    fn runtime_errors_are_named_by_whole_words() {
+        let named = RuntimeError::named_in;
+        assert_eq!(
+            named("prog.kxi:3: division by zero [division-by-zero]\n"),
+            Some(RuntimeError::DivisionByZero)
+        );
+        assert_eq!(
+            named("error: null-dereference, then index-out-of-bounds"),
+            Some(RuntimeError::IndexOutOfBounds)
+        );
+        assert_eq!(named("division-by-zero-ish"), None);
+        assert_eq!(named("no-division-by-zero"), None);
+    }
+}
@@ -0,0 +1,93 @@
+//! Conformance harness for KXI compilers.
+//!
+//! The harness discovers the `.kxi` programs under `dons_tests/`, feeds each
+//! one to a compiler implementing [`Compiler`] and checks that programs in
+//! `valid_kxi/` are accepted and programs in `invalid_kxi/` are rejected.
+//! Invalid programs can pin down the exact number of errors with an
+//! expectation header and the line of each error with an annotation, see
+//! [`expect`].
+//!
+//! Compilers built from the same passes as `sem_tests.rs` (parser,
+//! `SymbolTable`, `SymbolInner`, `TypeCheck`) can implement [`FrontEnd`]
+//! instead and be run through [`Phased`]. Compilers that only exist as an
+//! executable are run through [`External`].
+//!
+//! [`Reference`] runs the reference implementation from `kxi_ref` through
+//! the corpus, which keeps the expectations in the corpus honest.
+//! [`Coverage`] counts the kinds of error the corpus expects, showing
+//! which kinds no test exercises. [`Generator`] writes random programs
+//! that every compiler must accept, and [`mutants`] breaks valid programs
+//! into invalid ones whose errors it predicts. When a compiler fails on one
+//! of them, [`reduce`] shrinks the program to what the failure needs.
+//! [`Differential`] runs valid programs through a backend and the reference
+//! interpreter and reports where what they print differs.
+//!
+//! Single stages such as a lexer are checked against golden files instead,
+//! see [`golden`]. Whole compilers are also checked by running the programs
+//! they produce, see [`execute`].
+//!
+//! ```no_run
+//! use kxi_harness::{Compiler, Corpus, Outcome, TestCase};
+//!
+//! struct MyCompiler;
+//!
+//! impl Compiler for MyCompiler {
+//!     fn name(&self) -> &str {
+//!         "my-compiler"
+//!     }
+//!
+//!     fn compile(&mut self, case: &TestCase) -> Outcome {
+//!         // parse and check `case.source` here
+//!         Outcome::accepted()
+//!     }
+//! }
+//!
+//! let corpus = Corpus::dons_tests().unwrap();
+//! kxi_harness::run(&corpus, &mut MyCompiler).assert_all_passed();
+//! ```
+
+pub mod compare;
+pub mod compiler;
+pub mod corpus;
+pub mod coverage;
+pub mod differential;
+pub mod execute;
+pub mod expect;
+pub mod external;
+pub mod frontend;
+pub mod generate;
+pub mod golden;
+pub mod kind;
+pub mod mutate;
+pub mod phase;
+pub mod reduce;
+pub mod reference;
+pub mod reporter;
+pub mod runner;
+pub mod sem_tests;
+
+pub use compare::{compare, Matrix};
+pub use compiler::{parse_json_diagnostics, Compiler, Diagnostic, Outcome};
+pub use corpus::{Corpus, Expect, Filter, TestCase};
+pub use coverage::Coverage;
+pub use differential::{Comparison, DiffReport, Differential, Divergence};
+pub use execute::{
+    assert_runs, run_program, run_programs, Backend, Execution, Run, RunCase, RunFailure,
+    RunReport, RunResult, Status,
+};
+pub use expect::{
+    annotate, parse_annotations, strip_expectations, Annotation, ExpectError, Header,
+};
+pub use external::External;
+pub use frontend::{FrontEnd, Phased};
+pub use generate::Generator;
+pub use golden::{assert_goldens, check_goldens, Mismatch};
+pub use kind::{Kind, RuntimeError};
+pub use mutate::{mutants, Mutant, Mutation};
+pub use phase::Phase;
+pub use reduce::{check, reduce, reference_case, Signature};
+pub use reference::{reference, Reference};
+pub use reporter::Format;
+pub use runner::{assert_case, run, run_case, CaseResult, Failure, Report};
+
+include!(concat!(env!("OUT_DIR"), "/corpus_tests.rs"));
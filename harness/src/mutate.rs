@@ -0,0 +1,794 @@
+//! Invalid KXI programs made from valid ones.
+//!
+//! [`mutants`] takes a program the compiler must accept and breaks it in
+//! one place at a time, predicting what the compiler must report. Each
+//! [`Mutant`] is written with the expectation header and annotations of an
+//! `invalid_kxi/` file:
+//!
+//! ```text
+//! // expect: phase=resolve errors=1
+//! // instance-through-class: `get` used through `MyClass`
+//! ...
+//!     MyClass.get(1); // error: static-context
+//! ```
+//!
+//! Fed with `valid_kxi/` or with a [`crate::Generator`], this gives as many
+//! invalid programs as are wanted. Every change breaks exactly one rule, and
+//! only in ways where the reference reports a single error per broken use,
+//! so the predictions hold for any compiler that does not cascade.
+//!
+//! The program is printed by `kxi_ref::print` before it is changed, so the
+//! annotated lines are those of the printed mutant and comments in the
+//! original are lost.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use kxi_ref::ast::*;
+use kxi_ref::print;
+
+use crate::corpus::{Expect, TestCase};
+use crate::expect::{annotate, Annotation, Header};
+use crate::kind::Kind;
+use crate::phase::Phase;
+
+/// Lines written above the mutated program: the expectation header and
+/// the description.
+const HEADER_LINES: usize = 2;
+
+/// A way of breaking a valid program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Mutation {
+    /// A literal replaced by one of another type where the type matters,
+    /// e.g. `int x = "4";` or `while (1)`.
+    SwapLiteral,
+    /// A variable, field, method or class declared a second time right
+    /// after the first.
+    DuplicateDeclaration,
+    /// A statement moved out of the block that declares a variable it uses.
+    MoveOutOfBlock,
+    /// An instance member used through the name of its class.
+    InstanceThroughClass,
+    /// A static member used through `this` in a static method.
+    ThisInStatic,
+}
+
+impl Mutation {
+    pub const ALL: [Mutation; 5] = [
+        Mutation::SwapLiteral,
+        Mutation::DuplicateDeclaration,
+        Mutation::MoveOutOfBlock,
+        Mutation::InstanceThroughClass,
+        Mutation::ThisInStatic,
+    ];
+
+    /// The spelling used on the command line and in file names, e.g.
+    /// `swap-literal`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Mutation::SwapLiteral => "swap-literal",
+            Mutation::DuplicateDeclaration => "duplicate-declaration",
+            Mutation::MoveOutOfBlock => "move-out-of-block",
+            Mutation::InstanceThroughClass => "instance-through-class",
+            Mutation::ThisInStatic => "this-in-static",
+        }
+    }
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Mutation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mutation, String> {
+        Mutation::ALL
+            .into_iter()
+            .find(|m| m.as_str() == s)
+            .ok_or_else(|| format!("unknown mutation `{}`", s))
+    }
+}
+
+/// A program broken by one [`Mutation`], with the errors it must produce.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutant {
+    pub mutation: Mutation,
+    /// What was changed, e.g. ``4 became "4"``.
+    pub description: String,
+    /// The pass that must report the errors.
+    pub phase: Phase,
+    /// The errors, on lines of [`Mutant::source`].
+    pub annotations: Vec<Annotation>,
+    /// The mutated program as printed, without expectations.
+    pub program: String,
+}
+
+impl Mutant {
+    /// The file to put in `invalid_kxi/`: the header, listing `tags`, a
+    /// comment saying what was changed and the annotated program.
+    pub fn source(&self, tags: &[String]) -> String {
+        let source = format!(
+            "{}\n// {}: {}\n{}",
+            self.header(tags),
+            self.mutation,
+            self.description,
+            self.program
+        );
+        annotate(&source, &self.annotations)
+    }
+
+    fn header(&self, tags: &[String]) -> Header {
+        Header {
+            phase: Some(self.phase),
+            errors: Some(self.annotations.len()),
+            tags: tags.to_vec(),
+            ..Header::default()
+        }
+    }
+
+    /// The mutant as a case of `invalid_kxi/`, as if [`Mutant::source`] had
+    /// been written to `invalid_kxi/name.kxi` and loaded from there.
+    pub fn test_case(&self, name: &str, tags: &[String]) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            dir: "invalid_kxi".to_string(),
+            path: PathBuf::from("invalid_kxi").join(format!("{}.kxi", name)),
+            source: self.source(tags),
+            expect: Expect::Reject,
+            header: self.header(tags),
+            annotations: self.annotations.clone(),
+        }
+    }
+}
+
+/// Every mutant of `program`, which must be valid, by mutation in the order
+/// of [`Mutation::ALL`] and then by the place changed.
+pub fn mutants(program: &Program) -> Vec<Mutant> {
+    let program = kxi_ref::parse(&print::program(program)).expect("printed programs parse");
+    let mut out = Vec::new();
+    for mutation in Mutation::ALL {
+        let mut counter = Walker::new(&program, mutation, None);
+        counter.program(&mut program.clone());
+        for target in 0..counter.sites {
+            let mut mutated = program.clone();
+            let mut walker = Walker::new(&program, mutation, Some(target));
+            walker.program(&mut mutated);
+            let change = walker.change.expect("every counted site can be changed");
+            out.push(change.into_mutant(mutation, &mutated));
+        }
+    }
+    out
+}
+
+/// What a mutation did and where the errors it causes are.
+struct Change {
+    description: String,
+    phase: Phase,
+    kind: Kind,
+    /// How many errors of `kind` are reported on the line.
+    count: usize,
+    at: At,
+}
+
+/// Where the errors of a [`Change`] are reported.
+enum At {
+    /// A line of the mutated program.
+    Line(usize),
+    /// The second class with this name, or the second member with the
+    /// given name in it.
+    Second(String, Option<String>),
+}
+
+impl Change {
+    fn new(description: String, phase: Phase, kind: Kind, at: At) -> Change {
+        Change {
+            description,
+            phase,
+            kind,
+            count: 1,
+            at,
+        }
+    }
+
+    fn into_mutant(self, mutation: Mutation, mutated: &Program) -> Mutant {
+        let program = print::program(mutated);
+        let line = match &self.at {
+            At::Line(line) => *line,
+            At::Second(class, member) => {
+                // Where the copy starts depends on how long the original
+                // prints, so look it up instead.
+                let printed = kxi_ref::parse(&program).expect("printed programs parse");
+                let mut classes = printed.classes.iter().filter(|c| &c.name == class);
+                match member {
+                    None => classes.nth(1).map(|c| c.line),
+                    Some(member) => classes.next().and_then(|c| {
+                        let mut members = c.members.iter().filter(|m| m.name() == member);
+                        members.nth(1).map(Member::line)
+                    }),
+                }
+                .expect("the copy is in the printed program")
+            }
+        };
+        let annotation = Annotation {
+            line: line + HEADER_LINES,
+            kind: self.kind,
+        };
+        Mutant {
+            mutation,
+            description: self.description,
+            phase: self.phase,
+            annotations: vec![annotation; self.count],
+            program,
+        }
+    }
+}
+
+/// What the walk needs to know about a class.
+struct ClassInfo {
+    name: String,
+    /// Name, type and whether it is static.
+    fields: Vec<(String, Type, bool)>,
+    /// Name and whether it is static.
+    methods: Vec<(String, bool)>,
+}
+
+/// Walks a program in source order, counting the places `mutation` can
+/// change and changing the one numbered `target`.
+struct Walker {
+    mutation: Mutation,
+    target: Option<usize>,
+    sites: usize,
+    change: Option<Change>,
+    classes: Vec<ClassInfo>,
+    /// Index into `classes` of the class being walked.
+    class: Option<usize>,
+    is_static: bool,
+    /// Whether the walk is in a static method, rather than a static field
+    /// initializer or `main`.
+    static_method: bool,
+    scopes: Vec<Vec<(String, Type)>>,
+}
+
+impl Walker {
+    fn new(program: &Program, mutation: Mutation, target: Option<usize>) -> Walker {
+        let classes = program
+            .classes
+            .iter()
+            .map(|c| {
+                let mut info = ClassInfo {
+                    name: c.name.clone(),
+                    fields: Vec::new(),
+                    methods: Vec::new(),
+                };
+                for m in &c.members {
+                    match m {
+                        Member::Field(f) => {
+                            info.fields
+                                .push((f.name.clone(), f.ty.clone(), f.modifiers.is_static));
+                        }
+                        Member::Method(m) => {
+                            info.methods.push((m.name.clone(), m.modifiers.is_static));
+                        }
+                        Member::Constructor(_) => {}
+                    }
+                }
+                info
+            })
+            .collect();
+        Walker {
+            mutation,
+            target,
+            sites: 0,
+            change: None,
+            classes,
+            class: None,
+            is_static: true,
+            static_method: false,
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Counts a place `mutation` can change, and says whether to change it.
+    fn site(&mut self, mutation: Mutation) -> bool {
+        if self.mutation != mutation {
+            return false;
+        }
+        let hit = self.target == Some(self.sites);
+        self.sites += 1;
+        hit
+    }
+
+    fn program(&mut self, p: &mut Program) {
+        let mut i = 0;
+        while i < p.classes.len() {
+            let name = p.classes[i].name.clone();
+            self.class = self.classes.iter().position(|c| c.name == name);
+            self.members(&mut p.classes[i]);
+            if self.site(Mutation::DuplicateDeclaration) {
+                self.change = Some(Change::new(
+                    format!("class `{}` declared twice", name),
+                    Phase::Declare,
+                    Kind::DuplicateDeclaration,
+                    At::Second(name, None),
+                ));
+                let copy = p.classes[i].clone();
+                p.classes.insert(i + 1, copy);
+                i += 1;
+            }
+            i += 1;
+        }
+        self.class = None;
+        self.is_static = true;
+        self.static_method = false;
+        self.scopes.clear();
+        self.stmts(&mut p.main.body);
+    }
+
+    fn members(&mut self, class: &mut ClassDef) {
+        let mut i = 0;
+        while i < class.members.len() {
+            self.scopes.clear();
+            let copyable = match &mut class.members[i] {
+                Member::Field(f) => {
+                    self.is_static = f.modifiers.is_static;
+                    self.static_method = false;
+                    if let Some(init) = &mut f.init {
+                        self.literal(init, false);
+                        self.expr(init);
+                    }
+                    true
+                }
+                Member::Method(m) => {
+                    self.is_static = m.modifiers.is_static;
+                    self.static_method = m.modifiers.is_static;
+                    self.scopes.push(params(&m.params));
+                    self.stmts(&mut m.body);
+                    true
+                }
+                Member::Constructor(c) => {
+                    self.is_static = false;
+                    self.static_method = false;
+                    self.scopes.push(params(&c.params));
+                    self.stmts(&mut c.body);
+                    false
+                }
+            };
+            if copyable && self.site(Mutation::DuplicateDeclaration) {
+                let name = class.members[i].name().to_string();
+                self.change = Some(Change::new(
+                    format!("`{}.{}` declared twice", class.name, name),
+                    Phase::Declare,
+                    Kind::DuplicateDeclaration,
+                    At::Second(class.name.clone(), Some(name)),
+                ));
+                let copy = class.members[i].clone();
+                class.members.insert(i + 1, copy);
+                i += 1;
+            }
+            i += 1;
+        }
+    }
+
+    fn stmts(&mut self, list: &mut Vec<Stmt>) {
+        self.scopes.push(Vec::new());
+        let mut i = 0;
+        while i < list.len() {
+            self.move_out(list, i);
+            self.stmt(&mut list[i]);
+            if let StmtKind::Var { name, .. } = &list[i].kind {
+                if self.site(Mutation::DuplicateDeclaration) {
+                    self.change = Some(Change::new(
+                        format!("variable `{}` declared twice", name),
+                        Phase::Resolve,
+                        Kind::DuplicateDeclaration,
+                        At::Line(list[i].line + 1),
+                    ));
+                    let copy = list[i].clone();
+                    list.insert(i + 1, copy);
+                    i += 1;
+                }
+            }
+            i += 1;
+        }
+        self.scopes.pop();
+    }
+
+    /// The body of an `if` or a loop, which has a scope of its own even
+    /// without braces.
+    fn body(&mut self, s: &mut Stmt) {
+        self.scopes.push(Vec::new());
+        self.stmt(s);
+        self.scopes.pop();
+    }
+
+    fn stmt(&mut self, s: &mut Stmt) {
+        match &mut s.kind {
+            StmtKind::Block(body) => self.stmts(body),
+            StmtKind::Var { ty, name, init } => {
+                if let Some(init) = init {
+                    self.literal(init, false);
+                    self.expr(init);
+                }
+                let scope = self.scopes.last_mut().expect("statements are in a scope");
+                scope.push((name.clone(), ty.clone()));
+            }
+            StmtKind::Expr(e) => {
+                if let ExprKind::Assign(_, value) = &mut e.kind {
+                    self.literal(value, false);
+                }
+                self.expr(e);
+            }
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                self.literal(cond, false);
+                self.expr(cond);
+                self.body(then);
+                if let Some(otherwise) = otherwise {
+                    self.body(otherwise);
+                }
+            }
+            StmtKind::While { cond, body } => {
+                self.literal(cond, false);
+                self.expr(cond);
+                self.body(body);
+            }
+            StmtKind::For {
+                init,
+                cond,
+                step,
+                body,
+            } => {
+                for e in [init, cond, step].into_iter().flatten() {
+                    self.expr(e);
+                }
+                self.body(body);
+            }
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    self.literal(value, false);
+                    self.expr(value);
+                }
+            }
+            StmtKind::Cin(e) => self.expr(e),
+            StmtKind::Cout(e) => {
+                self.literal(e, true);
+                self.expr(e);
+            }
+            StmtKind::Switch {
+                scrutinee,
+                cases,
+                default,
+            } => {
+                self.expr(scrutinee);
+                for case in cases {
+                    self.stmts(&mut case.body);
+                }
+                if let Some(default) = default {
+                    self.stmts(default);
+                }
+            }
+            StmtKind::Break => {}
+        }
+    }
+
+    /// Swaps `e` for a literal of another type if it is a literal whose type
+    /// is checked against something: a `bool` one for what `cout` prints,
+    /// any other type elsewhere.
+    fn literal(&mut self, e: &mut Expr, printed: bool) {
+        let ExprKind::Literal(old) = &e.kind else {
+            return;
+        };
+        let new = match (old, printed) {
+            (Literal::Bool(_) | Literal::Null, true) => return,
+            (_, true) => Literal::Bool(true),
+            (Literal::Int(n), false) => Literal::Str(n.to_string()),
+            (Literal::Char(c), false) => Literal::Int(*c as i32),
+            (Literal::Str(s), false) => Literal::Char(s.chars().next().unwrap_or(' ')),
+            (Literal::Bool(b), false) => Literal::Int(*b as i32),
+            (Literal::Null, false) => Literal::Int(0),
+        };
+        if self.site(Mutation::SwapLiteral) {
+            self.change = Some(Change::new(
+                format!("{} became {}", old, new),
+                Phase::TypeCheck,
+                Kind::TypeMismatch,
+                At::Line(e.line),
+            ));
+            e.kind = ExprKind::Literal(new);
+        }
+    }
+
+    fn expr(&mut self, e: &mut Expr) {
+        match &mut e.kind {
+            ExprKind::Literal(_) | ExprKind::This => {}
+            ExprKind::Ident(name) => {
+                let name = name.clone();
+                if self.own_static(&name, false) && self.site(Mutation::ThisInStatic) {
+                    self.this_in_static(&name, e.line);
+                    e.kind = ExprKind::Member(Box::new(this(e)), name);
+                }
+            }
+            ExprKind::Unary(_, operand) => self.expr(operand),
+            ExprKind::Binary(_, left, right) => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Assign(target, value) => {
+                self.expr(target);
+                self.expr(value);
+            }
+            ExprKind::Member(object, name) => self.member(object, name, e.line, false),
+            ExprKind::Call(callee, args) => {
+                match &mut callee.kind {
+                    ExprKind::Ident(name) => {
+                        let name = name.clone();
+                        if self.own_static(&name, true) && self.site(Mutation::ThisInStatic) {
+                            self.this_in_static(&name, callee.line);
+                            callee.kind = ExprKind::Member(Box::new(this(callee)), name);
+                        }
+                    }
+                    ExprKind::Member(object, name) => self.member(object, name, e.line, true),
+                    _ => self.expr(callee),
+                }
+                self.args(args);
+            }
+            ExprKind::Index(array, index) => {
+                self.expr(array);
+                self.expr(index);
+            }
+            ExprKind::New(_, args) => self.args(args),
+            ExprKind::NewArray(_, size) => self.expr(size),
+        }
+    }
+
+    fn args(&mut self, args: &mut [Expr]) {
+        for arg in args {
+            self.literal(arg, false);
+            self.expr(arg);
+        }
+    }
+
+    /// `object.name`, where `line` is that of the access or the call.
+    fn member(&mut self, object: &mut Expr, name: &str, line: usize, call: bool) {
+        if let Some(class) = self.instance_receiver(object, name, call) {
+            if self.site(Mutation::InstanceThroughClass) {
+                self.change = Some(Change::new(
+                    format!("`{}` used through `{}`", name, class),
+                    Phase::Resolve,
+                    Kind::StaticContext,
+                    At::Line(line),
+                ));
+                object.kind = ExprKind::Ident(class);
+                return;
+            }
+        }
+        let own = self.class.map(|c| self.classes[c].name.clone());
+        if let (ExprKind::Ident(receiver), Some(own)) = (&object.kind, own) {
+            if self.static_method
+                && *receiver == own
+                && self.names_class(&own)
+                && self.site(Mutation::ThisInStatic)
+            {
+                self.this_in_static(name, object.line);
+                object.kind = ExprKind::This;
+                return;
+            }
+        }
+        self.expr(object);
+    }
+
+    fn this_in_static(&mut self, name: &str, line: usize) {
+        self.change = Some(Change::new(
+            format!("`{}` used through `this` in a static method", name),
+            Phase::Resolve,
+            Kind::InvalidThis,
+            At::Line(line),
+        ));
+    }
+
+    /// The class of `object` if `name` is one of its instance members and
+    /// the class's name can stand in for `object`. Only `this`, variables,
+    /// fields and `new` are looked at.
+    fn instance_receiver(&self, object: &Expr, name: &str, call: bool) -> Option<String> {
+        let class = match &object.kind {
+            ExprKind::This if !self.is_static => self.classes[self.class?].name.clone(),
+            ExprKind::Ident(var) => match self.lookup(var).or_else(|| self.field(var)) {
+                Some(Type::Class(class)) => class.clone(),
+                _ => return None,
+            },
+            ExprKind::New(class, _) => class.clone(),
+            _ => return None,
+        };
+        let info = self.classes.iter().find(|c| c.name == class)?;
+        let instance = if call {
+            info.methods.iter().any(|(m, s)| m == name && !s)
+        } else {
+            info.fields.iter().any(|(f, _, s)| f == name && !s)
+        };
+        (instance && self.names_class(&class)).then_some(class)
+    }
+
+    /// Whether `name` written on its own is the class of that name rather
+    /// than a variable or a field.
+    fn names_class(&self, name: &str) -> bool {
+        self.lookup(name).is_none() && self.field(name).is_none()
+    }
+
+    /// Whether the walk is in a static method and `name` on its own is one of
+    /// its class's methods, if `call`, or fields.
+    fn own_static(&self, name: &str, call: bool) -> bool {
+        if !self.static_method {
+            return false;
+        }
+        let Some(class) = self.class.map(|c| &self.classes[c]) else {
+            return false;
+        };
+        if call {
+            class.methods.iter().any(|(m, _)| m == name)
+        } else {
+            self.lookup(name).is_none() && self.field(name).is_some()
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Type> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(n, _)| n == name)
+            .map(|(_, ty)| ty)
+    }
+
+    /// The type of a field of the class being walked.
+    fn field(&self, name: &str) -> Option<&Type> {
+        let class = &self.classes[self.class?];
+        class
+            .fields
+            .iter()
+            .find(|(f, _, _)| f == name)
+            .map(|(_, ty, _)| ty)
+    }
+
+    /// Moves a statement out of a block that is, or is the body of,
+    /// `list[i]`, to just after `list[i]`. The statement must use variables
+    /// declared in the block and nothing else of that name may be visible
+    /// outside it, so that each use is undeclared.
+    fn move_out(&mut self, list: &mut Vec<Stmt>, i: usize) {
+        if self.mutation != Mutation::MoveOutOfBlock {
+            return;
+        }
+        let mut moved = None;
+        'blocks: for block in blocks(&mut list[i]) {
+            for j in 0..block.len() {
+                let Some(uses) = self.escaping_uses(&block[..j], &block[j]) else {
+                    continue;
+                };
+                if self.site(Mutation::MoveOutOfBlock) {
+                    moved = Some((block.remove(j), uses));
+                    break 'blocks;
+                }
+            }
+        }
+        let Some((moved, uses)) = moved else {
+            return;
+        };
+        let after = list[i].line + print::statement(&list[i]).lines().count();
+        let mut change = Change::new(
+            format!(
+                "`{}` moved out of its block",
+                print::statement(&moved).trim()
+            ),
+            Phase::Resolve,
+            Kind::UndeclaredIdentifier,
+            At::Line(after),
+        );
+        change.count = uses;
+        self.change = Some(change);
+        list.insert(i + 1, moved);
+    }
+
+    /// How many times `s`, an expression statement or a `cout`, uses a
+    /// variable declared in `before` that would be undeclared outside the
+    /// block. `None` if there are none or if a name it uses would mean
+    /// something else outside.
+    fn escaping_uses(&self, before: &[Stmt], s: &Stmt) -> Option<usize> {
+        let (StmtKind::Expr(e) | StmtKind::Cout(e)) = &s.kind else {
+            return None;
+        };
+        let mut names = Vec::new();
+        variables(e, &mut names);
+        let mut uses = 0;
+        for name in names {
+            let declared = before
+                .iter()
+                .any(|s| matches!(&s.kind, StmtKind::Var { name: n, .. } if n == name));
+            if !declared {
+                continue;
+            }
+            let visible = self.lookup(name).is_some()
+                || self.field(name).is_some()
+                || self.classes.iter().any(|c| c.name == name);
+            if visible {
+                return None;
+            }
+            uses += 1;
+        }
+        (uses > 0).then_some(uses)
+    }
+}
+
+fn params(params: &[Param]) -> Vec<(String, Type)> {
+    params
+        .iter()
+        .map(|p| (p.name.clone(), p.ty.clone()))
+        .collect()
+}
+
+/// `this` where `e` is.
+fn this(e: &Expr) -> Expr {
+    Expr {
+        line: e.line,
+        column: e.column,
+        kind: ExprKind::This,
+    }
+}
+
+/// The blocks `s` is made of: itself if it is one, or its bodies that are.
+fn blocks(s: &mut Stmt) -> Vec<&mut Vec<Stmt>> {
+    fn block(s: &mut Stmt) -> Option<&mut Vec<Stmt>> {
+        match &mut s.kind {
+            StmtKind::Block(body) => Some(body),
+            _ => None,
+        }
+    }
+    match &mut s.kind {
+        StmtKind::Block(body) => vec![body],
+        StmtKind::If {
+            then, otherwise, ..
+        } => std::iter::once(then)
+            .chain(otherwise)
+            .filter_map(|s| block(s))
+            .collect(),
+        StmtKind::While { body, .. } | StmtKind::For { body, .. } => {
+            block(body).into_iter().collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Every name `e` uses as a variable, once per use.
+fn variables<'e>(e: &'e Expr, out: &mut Vec<&'e str>) {
+    match &e.kind {
+        ExprKind::Literal(_) | ExprKind::This => {}
+        ExprKind::Ident(name) => out.push(name),
+        ExprKind::Unary(_, operand) => variables(operand, out),
+        ExprKind::Binary(_, left, right)
+        | ExprKind::Assign(left, right)
+        | ExprKind::Index(left, right) => {
+            variables(left, out);
+            variables(right, out);
+        }
+        ExprKind::Member(object, _) => variables(object, out),
+        ExprKind::Call(callee, args) => {
+            if let ExprKind::Member(object, _) = &callee.kind {
+                variables(object, out);
+            }
+            for arg in args {
+                variables(arg, out);
+            }
+        }
+        ExprKind::New(_, args) => {
+            for arg in args {
+                variables(arg, out);
+            }
+        }
+        ExprKind::NewArray(_, size) => variables(size, out),
+    }
+}
@@ -0,0 +1,56 @@
+//! The passes of a KXI front end.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// A pass of the front end, in the order they run.
+///
+/// These mirror the stages exercised by `dons_tests/sem_tests.rs`: the
+/// parser, the `SymbolTable` pass (`Declare`), the `SymbolInner` pass
+/// (`Resolve`) and `TypeCheck`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Parse,
+    Declare,
+    Resolve,
+    TypeCheck,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 4] = [
+        Phase::Parse,
+        Phase::Declare,
+        Phase::Resolve,
+        Phase::TypeCheck,
+    ];
+
+    /// The spelling used in expectation headers.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::Parse => "parse",
+            Phase::Declare => "declare",
+            Phase::Resolve => "resolve",
+            Phase::TypeCheck => "typecheck",
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Phase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Phase, String> {
+        Phase::ALL
+            .into_iter()
+            .find(|p| p.as_str() == s)
+            .ok_or_else(|| format!("unknown phase `{}`", s))
+    }
+}
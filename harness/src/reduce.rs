@@ -0,0 +1,430 @@
+//! Shrinking a program a compiler fails on.
+//!
+//! A failure found with a [`crate::Generator`] or a [`crate::mutants`] run
+//! is usually in a program of a few hundred lines, most of which has nothing
+//! to do with it. [`reduce`] takes the program and a test saying whether a
+//! version of it still shows the failure, and removes whatever it can:
+//!
+//! * runs of classes, members, statements, `switch` cases and arguments,
+//!   from long runs down to single ones, in the manner of delta debugging;
+//! * statements around a body, such as an `if` around its branch, as well
+//!   as `else` branches, initializers and `for` clauses;
+//! * expressions around one of their operands, such as `a + f(b)` around
+//!   `f(b)`.
+//!
+//! Every candidate is printed by `kxi_ref::print` and must parse again, so
+//! the test only ever sees syntactically valid programs. The passes are
+//! repeated until none of them removes anything more.
+//!
+//! Expectations in the original file would not match the reduced program,
+//! so [`reference_case`] makes the file to add to the corpus from what the
+//! reference reports instead. That lets a version drift to another bug, such
+//! as a missed type error turning into a missed undeclared identifier once
+//! the declaration is gone; comparing the [`Signature`] of each version with
+//! the original's keeps to the first one.
+
+use std::collections::HashSet;
+use std::fs;
+use std::mem::{self, Discriminant};
+use std::path::PathBuf;
+
+use kxi_ref::ast::*;
+use kxi_ref::print;
+
+use crate::compiler::Compiler;
+use crate::corpus::{Expect, TestCase};
+use crate::execute::ArtifactDir;
+use crate::expect::{annotate, strip_expectations, Annotation, Header};
+use crate::kind::Kind;
+use crate::reference::reference;
+use crate::runner::{run_case, CaseResult, Failure};
+
+/// Shrinks `program` as long as `interesting` holds for it, and returns the
+/// smallest program found. `interesting` is given the program as source.
+///
+/// If the printed `program` is not interesting to begin with, it is returned
+/// as is.
+pub fn reduce(program: &Program, mut interesting: impl FnMut(&str) -> bool) -> Program {
+    let mut best = program.clone();
+    if !interesting(&print::program(&best)) {
+        return best;
+    }
+    loop {
+        let mut changed = false;
+        let lines = print::program(&best).lines().count();
+        let sizes = (0..usize::BITS)
+            .rev()
+            .map(|bit| 1 << bit)
+            .filter(|&size| size <= lines.next_power_of_two());
+        let passes = sizes.map(Pass::Remove).chain([Pass::Unwrap, Pass::Hoist]);
+        for pass in passes {
+            let mut target = 0;
+            loop {
+                let mut candidate = best.clone();
+                let mut editor = Editor::new(pass, target);
+                editor.program(&mut candidate);
+                if !editor.applied {
+                    break;
+                }
+                let source = print::program(&candidate);
+                match kxi_ref::parse(&source) {
+                    Ok(parsed) if interesting(&source) => {
+                        // The next site now has this number.
+                        best = parsed;
+                        changed = true;
+                    }
+                    _ => target += 1,
+                }
+            }
+        }
+        if !changed {
+            return best;
+        }
+    }
+}
+
+/// The corpus file the reference makes of `program`: a case of
+/// `valid_kxi/` if it accepts it, or else one of `invalid_kxi/` whose header
+/// and annotations list the errors it reports. Errors are only annotated if
+/// every one of them has a line and a kind, and otherwise only counted.
+pub fn reference_case(name: &str, program: &str) -> TestCase {
+    let unjudged = TestCase {
+        name: name.to_string(),
+        dir: "valid_kxi".to_string(),
+        path: PathBuf::from(name),
+        source: program.to_string(),
+        expect: Expect::Accept,
+        header: Header::default(),
+        annotations: Vec::new(),
+    };
+    let outcome = reference().compile(&unjudged);
+    if outcome.is_accepted() {
+        return TestCase {
+            path: PathBuf::from("valid_kxi").join(format!("{}.kxi", name)),
+            ..unjudged
+        };
+    }
+    let diagnostics = &outcome.diagnostics;
+    // One line for the header.
+    let annotations: Vec<Annotation> = diagnostics
+        .iter()
+        .map(|d| {
+            Some(Annotation {
+                line: d.line? + 1,
+                kind: d.kind?,
+            })
+        })
+        .collect::<Option<_>>()
+        .unwrap_or_default();
+    let phase = diagnostics[0].phase;
+    let header = Header {
+        phase: phase.filter(|_| diagnostics.iter().all(|d| d.phase == phase)),
+        errors: Some(diagnostics.len()),
+        ..Header::default()
+    };
+    let source = annotate(&format!("{}\n{}", header, program), &annotations);
+    TestCase {
+        name: name.to_string(),
+        dir: "invalid_kxi".to_string(),
+        path: PathBuf::from("invalid_kxi").join(format!("{}.kxi", name)),
+        source,
+        expect: Expect::Reject,
+        header,
+        annotations,
+    }
+}
+
+/// Runs `compiler` on the [`reference_case`] made of `program`.
+///
+/// The case is written to a scratch directory first, as compilers run
+/// through [`crate::External`] read it from disk, and removed afterwards.
+pub fn check(compiler: &mut dyn Compiler, name: &str, program: &str) -> CaseResult {
+    let mut case = reference_case(name, program);
+    let dir = ArtifactDir::new().expect("cannot create a scratch directory");
+    case.path = dir.path().join(&case.path);
+    let written = fs::create_dir_all(dir.path().join(&case.dir))
+        .and_then(|()| fs::write(&case.path, &case.source));
+    if let Err(e) = written {
+        panic!("cannot write {}: {}", case.path.display(), e);
+    }
+    run_case(&case, compiler)
+}
+
+/// How a [`check`] failed: which kinds of [`Failure`] there were, and which
+/// errors the reference expects on which lines. Lines are told by their text,
+/// which stays the same as the lines around them are removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    failures: HashSet<Discriminant<Failure>>,
+    errors: Option<usize>,
+    expected: Vec<(Kind, String)>,
+}
+
+impl Signature {
+    pub fn of(result: &CaseResult) -> Signature {
+        let lines: Vec<&str> = result.case.source.lines().collect();
+        let mut expected: Vec<(Kind, String)> = result
+            .case
+            .annotations
+            .iter()
+            .map(|a| {
+                let line = lines.get(a.line - 1).copied().unwrap_or_default();
+                (a.kind, strip_expectations(line).trim().to_string())
+            })
+            .collect();
+        expected.sort();
+        Signature {
+            failures: result.failures.iter().map(mem::discriminant).collect(),
+            errors: result.case.header.errors,
+            expected,
+        }
+    }
+}
+
+/// A kind of edit tried on every place it applies to.
+#[derive(Debug, Clone, Copy)]
+enum Pass {
+    /// Remove this many consecutive items of a list.
+    Remove(usize),
+    /// Replace a statement by one of its parts, or drop an optional part.
+    Unwrap,
+    /// Replace an expression by one of its operands.
+    Hoist,
+}
+
+/// Walks a program making the edit of `pass` numbered `target`.
+struct Editor {
+    pass: Pass,
+    target: usize,
+    sites: usize,
+    applied: bool,
+}
+
+impl Editor {
+    fn new(pass: Pass, target: usize) -> Editor {
+        Editor {
+            pass,
+            target,
+            sites: 0,
+            applied: false,
+        }
+    }
+
+    /// Counts a place the edit applies to, and says whether to edit it.
+    fn site(&mut self) -> bool {
+        let hit = !self.applied && self.sites == self.target;
+        self.sites += 1;
+        self.applied |= hit;
+        hit
+    }
+
+    fn program(&mut self, p: &mut Program) {
+        self.list(&mut p.classes);
+        for class in &mut p.classes {
+            self.list(&mut class.members);
+            for member in &mut class.members {
+                match member {
+                    Member::Field(f) => {
+                        if f.init.is_some() && self.unwrap() {
+                            f.init = None;
+                        }
+                        if let Some(init) = &mut f.init {
+                            self.expr(init);
+                        }
+                    }
+                    Member::Method(m) => self.stmts(&mut m.body),
+                    Member::Constructor(c) => self.stmts(&mut c.body),
+                }
+            }
+        }
+        self.stmts(&mut p.main.body);
+    }
+
+    /// Removes a run of `items` in a [`Pass::Remove`].
+    fn list<T>(&mut self, items: &mut Vec<T>) {
+        let Pass::Remove(size) = self.pass else {
+            return;
+        };
+        if size > items.len() {
+            return;
+        }
+        for start in (0..items.len()).step_by(size) {
+            if self.site() {
+                items.drain(start..items.len().min(start + size));
+                return;
+            }
+        }
+    }
+
+    /// A site of a [`Pass::Unwrap`].
+    fn unwrap(&mut self) -> bool {
+        matches!(self.pass, Pass::Unwrap) && self.site()
+    }
+
+    fn stmts(&mut self, list: &mut Vec<Stmt>) {
+        self.list(list);
+        let mut i = 0;
+        while i < list.len() {
+            if let StmtKind::Block(body) = &mut list[i].kind {
+                if self.unwrap() {
+                    let body = std::mem::take(body);
+                    list.splice(i..=i, body);
+                    continue;
+                }
+            }
+            self.stmt(&mut list[i]);
+            i += 1;
+        }
+    }
+
+    fn stmt(&mut self, s: &mut Stmt) {
+        let replacement = match &mut s.kind {
+            StmtKind::If {
+                then, otherwise, ..
+            } => {
+                if self.unwrap() {
+                    Some((**then).clone())
+                } else if otherwise.is_some() && self.unwrap() {
+                    otherwise.as_deref().cloned()
+                } else {
+                    if otherwise.is_some() && self.unwrap() {
+                        *otherwise = None;
+                    }
+                    None
+                }
+            }
+            StmtKind::While { body, .. } | StmtKind::For { body, .. } => {
+                self.unwrap().then(|| (**body).clone())
+            }
+            _ => None,
+        };
+        if let Some(replacement) = replacement {
+            *s = replacement;
+            return;
+        }
+        match &mut s.kind {
+            StmtKind::Block(body) => self.stmts(body),
+            StmtKind::Var { init, .. } => {
+                if init.is_some() && self.unwrap() {
+                    *init = None;
+                }
+                if let Some(init) = init {
+                    self.expr(init);
+                }
+            }
+            StmtKind::Expr(e) | StmtKind::Cin(e) | StmtKind::Cout(e) => self.expr(e),
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                self.expr(cond);
+                self.stmt(then);
+                if let Some(otherwise) = otherwise {
+                    self.stmt(otherwise);
+                }
+            }
+            StmtKind::While { cond, body } => {
+                self.expr(cond);
+                self.stmt(body);
+            }
+            StmtKind::For {
+                init,
+                cond,
+                step,
+                body,
+            } => {
+                for clause in [&mut *init, &mut *cond, &mut *step] {
+                    if clause.is_some() && self.unwrap() {
+                        *clause = None;
+                    }
+                    if let Some(e) = clause {
+                        self.expr(e);
+                    }
+                }
+                self.stmt(body);
+            }
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            StmtKind::Switch {
+                scrutinee,
+                cases,
+                default,
+            } => {
+                self.expr(scrutinee);
+                self.list(cases);
+                for case in cases {
+                    self.stmts(&mut case.body);
+                }
+                if default.is_some() && self.unwrap() {
+                    *default = None;
+                }
+                if let Some(default) = default {
+                    self.stmts(default);
+                }
+            }
+            StmtKind::Break => {}
+        }
+    }
+
+    fn expr(&mut self, e: &mut Expr) {
+        if matches!(self.pass, Pass::Hoist) {
+            for operand in operands(e) {
+                if self.site() {
+                    *e = operand.clone();
+                    return;
+                }
+            }
+        }
+        match &mut e.kind {
+            ExprKind::Literal(_) | ExprKind::Ident(_) | ExprKind::This => {}
+            ExprKind::Unary(_, operand) | ExprKind::Member(operand, _) => self.expr(operand),
+            ExprKind::NewArray(_, operand) => self.expr(operand),
+            ExprKind::Binary(_, left, right)
+            | ExprKind::Assign(left, right)
+            | ExprKind::Index(left, right) => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Call(callee, args) => {
+                if let ExprKind::Member(object, _) = &mut callee.kind {
+                    self.expr(object);
+                }
+                self.list(args);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            ExprKind::New(_, args) => {
+                self.list(args);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+        }
+    }
+}
+
+/// The expressions `e` can be replaced by: its operands, the object of a
+/// member and the arguments of a call.
+fn operands(e: &Expr) -> Vec<&Expr> {
+    match &e.kind {
+        ExprKind::Literal(_) | ExprKind::Ident(_) | ExprKind::This => Vec::new(),
+        ExprKind::Unary(_, operand) | ExprKind::Member(operand, _) => vec![operand],
+        ExprKind::NewArray(_, size) => vec![size],
+        ExprKind::Binary(_, left, right)
+        | ExprKind::Assign(left, right)
+        | ExprKind::Index(left, right) => vec![left, right],
+        ExprKind::Call(callee, args) => {
+            let object = match &callee.kind {
+                ExprKind::Member(object, _) => Some(&**object),
+                _ => None,
+            };
+            object.into_iter().chain(args).collect()
+        }
+        ExprKind::New(_, args) => args.iter().collect(),
+    }
+}
@@ -0,0 +1,120 @@
+//! The reference front end from `kxi_ref`, as a [`FrontEnd`].
+//!
+//! Running it over the corpus checks the corpus itself: every header count
+//! and annotation has to agree with what the reference passes report.
+
+use kxi_ref::ast::Program;
+use kxi_ref::sema::{self, SemanticError, Table};
+use kxi_ref::{Interpreter, RuntimeErrorKind, SyntaxError};
+
+use crate::compiler::Diagnostic;
+use crate::execute::{Backend, Execution, Run, RunCase, Status};
+use crate::frontend::{FrontEnd, Phased};
+use crate::kind::Kind;
+
+/// The reference front end. Run it with [`reference`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reference;
+
+/// A parsed program and, once declared, its symbol table.
+#[derive(Debug, Clone)]
+pub struct Unit {
+    pub program: Program,
+    pub table: Table,
+}
+
+/// The reference front end as a [`Compiler`](crate::Compiler).
+pub fn reference() -> Phased<Reference> {
+    Phased(Reference)
+}
+
+fn diagnostics(errors: Vec<SemanticError>) -> Vec<Diagnostic> {
+    errors
+        .into_iter()
+        .map(|e| {
+            let kind = e
+                .kind
+                .as_str()
+                .parse()
+                .expect("the harness knows every kind");
+            Diagnostic::at(e.line, e.message).with_kind(kind)
+        })
+        .collect()
+}
+
+fn syntax_error(e: SyntaxError) -> Diagnostic {
+    Diagnostic {
+        line: Some(e.line),
+        column: Some(e.column),
+        phase: None,
+        kind: Some(Kind::SyntaxError),
+        message: e.message,
+    }
+}
+
+impl FrontEnd for Reference {
+    type Unit = Unit;
+
+    fn name(&self) -> &str {
+        "reference"
+    }
+
+    fn parse(&mut self, source: &str) -> Result<Unit, Vec<Diagnostic>> {
+        match kxi_ref::parse(source) {
+            Ok(program) => Ok(Unit {
+                program,
+                table: Table::default(),
+            }),
+            Err(e) => Err(vec![syntax_error(e)]),
+        }
+    }
+
+    fn declare(&mut self, unit: &mut Unit) -> Vec<Diagnostic> {
+        let (table, errors) = sema::declare(&unit.program);
+        unit.table = table;
+        diagnostics(errors)
+    }
+
+    fn resolve(&mut self, unit: &mut Unit) -> Vec<Diagnostic> {
+        diagnostics(sema::resolve(&unit.program, &unit.table))
+    }
+
+    fn typecheck(&mut self, unit: &mut Unit) -> Vec<Diagnostic> {
+        diagnostics(sema::typecheck(&unit.program, &unit.table))
+    }
+}
+
+/// Runs programs in [`kxi_ref::interp`], in process. Runtime errors are
+/// reported on stderr and end the program with status 1, like `kxi-run`.
+impl Backend for Reference {
+    fn name(&self) -> &str {
+        "reference"
+    }
+
+    fn execute(&mut self, case: &RunCase) -> Execution {
+        let program = match kxi_ref::parse(&case.source) {
+            Ok(program) => program,
+            Err(e) => return Execution::Rejected(vec![syntax_error(e)]),
+        };
+        if let Err(errors) = sema::analyze(&program) {
+            return Execution::Rejected(diagnostics(errors));
+        }
+        let run = Interpreter::new(&program)
+            .stdin(case.stdin.as_str())
+            .time_limit(case.timeout)
+            .run();
+        let status = match &run.error {
+            Some(e) if e.kind == RuntimeErrorKind::TimeLimit => Status::TimedOut,
+            _ => Status::Exited(run.exit_code()),
+        };
+        let stderr = match &run.error {
+            Some(e) => format!("{}:{}\n", case.path.display(), e),
+            None => String::new(),
+        };
+        Execution::Ran(Run {
+            status,
+            stdout: run.stdout,
+            stderr,
+        })
+    }
+}
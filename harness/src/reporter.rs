@@ -0,0 +1,348 @@
+//! Writing a [`Report`] in formats other tools read.
+//!
+//! [`Format::Text`] is the report's own `Display`. [`Format::Junit`] is the
+//! JUnit XML read by most CI dashboards, with one `<testcase>` per `.kxi`
+//! file, and [`Format::Tap`] is the Test Anything Protocol read by `prove`.
+//! The failure body of both lists what the file expects next to what the
+//! compiler reported. [`Format::Html`] is a single static page for people,
+//! showing the source of every file with its error lines highlighted.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use crate::corpus::{Expect, TestCase};
+use crate::runner::{CaseResult, Failure, Report};
+
+/// An output format of [`render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Junit,
+    Tap,
+    Html,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "junit" => Ok(Format::Junit),
+            "tap" => Ok(Format::Tap),
+            "html" => Ok(Format::Html),
+            _ => Err(format!(
+                "unknown format `{}`, expected text, junit, tap or html",
+                s
+            )),
+        }
+    }
+}
+
+/// Writes `report` in `format`.
+pub fn render(report: &Report, format: Format) -> String {
+    match format {
+        Format::Text => report.to_string(),
+        Format::Junit => junit(report),
+        Format::Tap => tap(report),
+        Format::Html => html(report),
+    }
+}
+
+/// Writes `report` as a JUnit XML document with a single test suite named
+/// after the compiler.
+pub fn junit(report: &Report) -> String {
+    let panicked = |r: &&CaseResult| r.outcome.is_none();
+    let errors = report.results.iter().filter(panicked).count();
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let suite = format!(
+        "name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\"",
+        escape(&report.compiler),
+        report.results.len(),
+        report.failed() - errors,
+        errors
+    );
+    writeln!(out, "<testsuites {}>", suite).unwrap();
+    writeln!(out, "  <testsuite {}>", suite).unwrap();
+    for result in &report.results {
+        let case = &result.case;
+        write!(
+            out,
+            "    <testcase classname=\"{}\" name=\"{}\" file=\"{}\"",
+            escape(&case.dir),
+            escape(&case.name),
+            escape(&case.path.to_string_lossy())
+        )
+        .unwrap();
+        if result.passed() {
+            out.push_str("/>\n");
+            continue;
+        }
+        out.push_str(">\n");
+        let tag = if result.outcome.is_none() {
+            "error"
+        } else {
+            "failure"
+        };
+        let message = result.failures[0].to_string();
+        writeln!(
+            out,
+            "      <{} message=\"{}\">{}</{}>",
+            tag,
+            escape(&message),
+            escape(&details(result)),
+            tag
+        )
+        .unwrap();
+        out.push_str("    </testcase>\n");
+    }
+    out.push_str("  </testsuite>\n</testsuites>\n");
+    out
+}
+
+/// Writes `report` as TAP version 13, one test point per file. Failed
+/// points are followed by their details as `#` comments.
+pub fn tap(report: &Report) -> String {
+    let mut out = format!("TAP version 13\n1..{}\n", report.results.len());
+    for (i, result) in report.results.iter().enumerate() {
+        let status = if result.passed() { "ok" } else { "not ok" };
+        writeln!(out, "{} {} - {}", status, i + 1, result.case.id()).unwrap();
+        if !result.passed() {
+            for line in details(result).lines() {
+                writeln!(out, "# {}", line).unwrap();
+            }
+        }
+    }
+    out
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+summary { cursor: pointer; padding: 0.2em 0; }
+.ok summary::before { content: \"ok \"; color: #2a7d2a; font-weight: bold; }
+.failed summary::before { content: \"FAILED \"; color: #b22222; font-weight: bold; }
+.phase { color: #666; }
+table.diagnostics { border-collapse: collapse; margin: 0.5em 0; }
+table.diagnostics td, table.diagnostics th { border: 1px solid #ccc; padding: 0.2em 0.6em; vertical-align: top; text-align: left; }
+pre { background: #f8f8f8; padding: 0.5em 0; }
+pre span { display: block; padding: 0 0.5em; }
+pre i { display: inline-block; width: 3em; color: #999; font-style: normal; user-select: none; }
+.expected { background: #d4f4d4; }
+.missed { background: #f8c8c8; }
+.unexpected { background: #fde2b8; }
+";
+
+/// How a source line is highlighted in the HTML report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Mark {
+    /// Annotated, and the annotated errors were reported.
+    Expected,
+    /// An error was reported here that no annotation asks for.
+    Unexpected,
+    /// Annotated, but at least one annotated error was not reported.
+    Missed,
+}
+
+impl Mark {
+    fn class(self) -> &'static str {
+        match self {
+            Mark::Expected => "expected",
+            Mark::Unexpected => "unexpected",
+            Mark::Missed => "missed",
+        }
+    }
+}
+
+/// Writes `report` as a self-contained HTML page, one collapsible section
+/// per file. Failed files are open, and their source lines are green where
+/// the annotated errors were reported, red where one was missed and orange
+/// where an error nobody asked for was reported.
+pub fn html(report: &Report) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let title = format!("KXI conformance: {}", report.compiler);
+    writeln!(out, "<title>{}</title>", escape(&title)).unwrap();
+    writeln!(out, "<style>\n{}</style>\n</head>\n<body>", STYLE).unwrap();
+    writeln!(out, "<h1>{}</h1>", escape(&title)).unwrap();
+    writeln!(
+        out,
+        "<p>{} passed, {} failed, {} missed errors, {} cascading errors</p>",
+        report.passed(),
+        report.failed(),
+        report.missed(),
+        report.cascading()
+    )
+    .unwrap();
+    for result in &report.results {
+        let case = &result.case;
+        let (class, open) = if result.passed() {
+            ("ok", "")
+        } else {
+            ("failed", " open")
+        };
+        writeln!(out, "<details class=\"{}\"{}>", class, open).unwrap();
+        write!(out, "<summary>{}", escape(&case.id())).unwrap();
+        if let Some(phase) = case.header.phase {
+            write!(out, " <span class=\"phase\">{}</span>", phase).unwrap();
+        }
+        out.push_str("</summary>\n");
+        if !result.failures.is_empty() {
+            out.push_str("<ul>\n");
+            for failure in &result.failures {
+                writeln!(out, "<li>{}</li>", escape(&failure.to_string())).unwrap();
+            }
+            out.push_str("</ul>\n");
+        }
+        let reported = match &result.outcome {
+            Some(outcome) if outcome.is_accepted() => vec!["nothing".to_string()],
+            Some(outcome) => outcome.diagnostics.iter().map(|d| d.to_string()).collect(),
+            None => vec!["the compiler panicked".to_string()],
+        };
+        out.push_str(
+            "<table class=\"diagnostics\">\n<tr><th>expected</th><th>reported</th></tr>\n",
+        );
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td></tr>\n</table>",
+            escape(&expected(case).join("\n")).replace('\n', "<br>"),
+            escape(&reported.join("\n")).replace('\n', "<br>")
+        )
+        .unwrap();
+        let marks = marks(result);
+        out.push_str("<pre>");
+        for (idx, line) in case.source.lines().enumerate() {
+            match marks.get(&(idx + 1)) {
+                Some(mark) => write!(out, "<span class=\"{}\">", mark.class()).unwrap(),
+                None => out.push_str("<span>"),
+            }
+            // Each line is its own block, a newline would add an empty one.
+            write!(out, "<i>{}</i>{}</span>", idx + 1, escape(line)).unwrap();
+        }
+        out.push_str("</pre>\n</details>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// The highlighted lines of `result`'s source. A line that is both missed
+/// and unexpected, because an error of the wrong kind was reported on it,
+/// counts as missed.
+fn marks(result: &CaseResult) -> BTreeMap<usize, Mark> {
+    let mut marks = BTreeMap::new();
+    let mut mark = |line: Option<usize>, mark: Mark| {
+        if let Some(line) = line {
+            let slot = marks.entry(line).or_insert(mark);
+            *slot = (*slot).max(mark);
+        }
+    };
+    // Nothing was matched against the annotations if the compiler panicked
+    // or accepted the program, so every one of them was missed.
+    let unmatched = result.outcome.as_ref().is_none_or(|o| o.is_accepted());
+    for annotation in &result.case.annotations {
+        let status = if unmatched {
+            Mark::Missed
+        } else {
+            Mark::Expected
+        };
+        mark(Some(annotation.line), status);
+    }
+    for failure in &result.failures {
+        match failure {
+            Failure::MissingError { line, .. } | Failure::WrongKind { line, .. } => {
+                mark(Some(*line), Mark::Missed)
+            }
+            Failure::UnexpectedError(d) => mark(d.line, Mark::Unexpected),
+            Failure::Cascade(diagnostics) => {
+                for d in diagnostics {
+                    mark(d.line, Mark::Unexpected);
+                }
+            }
+            Failure::Rejected => {
+                for d in result.outcome.iter().flat_map(|o| &o.diagnostics) {
+                    mark(d.line, Mark::Unexpected);
+                }
+            }
+            _ => {}
+        }
+    }
+    marks
+}
+
+/// Why `result` failed, what its file expects and what was reported.
+fn details(result: &CaseResult) -> String {
+    let mut out = String::new();
+    for failure in &result.failures {
+        writeln!(out, "{}", failure).unwrap();
+    }
+    out.push_str("expected:\n");
+    for line in expected(&result.case) {
+        writeln!(out, "  {}", line).unwrap();
+    }
+    match &result.outcome {
+        Some(outcome) if outcome.is_accepted() => out.push_str("reported:\n  nothing\n"),
+        Some(outcome) => {
+            out.push_str("reported:\n");
+            for diagnostic in &outcome.diagnostics {
+                writeln!(out, "  {}", diagnostic).unwrap();
+            }
+        }
+        None => {}
+    }
+    out
+}
+
+fn expected(case: &TestCase) -> Vec<String> {
+    if case.expect == Expect::Accept {
+        return vec!["no errors".to_string()];
+    }
+    if !case.annotations.is_empty() {
+        return case
+            .annotations
+            .iter()
+            .map(|a| format!("{}: {}", a.line, a.kind))
+            .collect();
+    }
+    match case.header.errors {
+        Some(n) => vec![format!("{} errors", n)],
+        None => vec!["at least one error".to_string()],
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Not allowed in XML 1.0 at all.
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {
+                out.push(char::REPLACEMENT_CHARACTER)
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape("a < b && \"c\""),
+            "a &lt; b &amp;&amp; &quot;c&quot;"
+        );
+        assert_eq!(escape("bell\u{7}"), "bell\u{fffd}");
+    }
+
+    #[test]
+    fn parses_formats() {
+        assert_eq!("junit".parse(), Ok(Format::Junit));
+        assert!("xml".parse::<Format>().is_err());
+    }
+}
@@ -0,0 +1,380 @@
+//! Running a compiler over a corpus and judging the results.
+
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use crate::compiler::{Compiler, Diagnostic, Outcome};
+use crate::corpus::{Corpus, Expect, TestCase, CASCADE_DIR};
+use crate::kind::Kind;
+use crate::phase::Phase;
+
+/// Why a single test case failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+    /// A valid program was rejected.
+    Rejected,
+    /// An invalid program was accepted.
+    Accepted,
+    /// The number of errors differs from the one in the file's header.
+    ErrorCount { expected: usize, actual: usize },
+    /// An annotated error was not reported on its line.
+    MissingError { line: usize, kind: Kind },
+    /// An error was reported where none is annotated.
+    UnexpectedError(Diagnostic),
+    /// Every annotated error of a `cascade_kxi` program was reported,
+    /// followed by these errors, which a compiler that recovers well would
+    /// not have reported.
+    Cascade(Vec<Diagnostic>),
+    /// An error was reported on an annotated line, but of another kind.
+    WrongKind {
+        line: usize,
+        expected: Kind,
+        actual: Kind,
+    },
+    /// The program was rejected by a different pass than the one its header
+    /// names.
+    WrongPhase { expected: Phase, actual: Phase },
+    /// The compiler panicked while compiling the program.
+    Panicked(String),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Rejected => write!(f, "valid program was rejected"),
+            Failure::Accepted => write!(f, "invalid program was accepted"),
+            Failure::ErrorCount { expected, actual } => {
+                write!(
+                    f,
+                    "expected {} errors, compiler reported {}",
+                    expected, actual
+                )
+            }
+            Failure::MissingError { line, kind } => {
+                write!(f, "missing {} error on line {}", kind, line)
+            }
+            Failure::UnexpectedError(diagnostic) => match diagnostic.line {
+                Some(line) => write!(
+                    f,
+                    "unexpected error on line {}: {}",
+                    line, diagnostic.message
+                ),
+                None => write!(f, "unexpected error without a line: {}", diagnostic.message),
+            },
+            Failure::Cascade(diagnostics) => {
+                write!(f, "{} cascading errors:", diagnostics.len())?;
+                for (i, diagnostic) in diagnostics.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { "; " };
+                    match diagnostic.line {
+                        Some(line) => write!(f, "{}line {}: {}", sep, line, diagnostic.message)?,
+                        None => write!(f, "{}{}", sep, diagnostic.message)?,
+                    }
+                }
+                Ok(())
+            }
+            Failure::WrongKind {
+                line,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "expected {} error on line {}, compiler reported {}",
+                expected, line, actual
+            ),
+            Failure::WrongPhase { expected, actual } => {
+                let when = if actual < expected { "early" } else { "late" };
+                write!(
+                    f,
+                    "rejected too {}: expected {} errors, got {} errors",
+                    when, expected, actual
+                )
+            }
+            Failure::Panicked(msg) => write!(f, "compiler panicked: {}", msg),
+        }
+    }
+}
+
+/// The result of running one test case.
+#[derive(Debug, Clone)]
+pub struct CaseResult {
+    pub case: TestCase,
+    /// `None` if the compiler panicked.
+    pub outcome: Option<Outcome>,
+    /// Empty when the case passed.
+    pub failures: Vec<Failure>,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// The results of running a compiler over a whole corpus.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub compiler: String,
+    pub results: Vec<CaseResult>,
+}
+
+impl Report {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &CaseResult> {
+        self.results.iter().filter(|r| !r.passed())
+    }
+
+    /// Number of annotated errors the compiler did not report.
+    pub fn missed(&self) -> usize {
+        self.all_failures()
+            .filter(|f| matches!(f, Failure::MissingError { .. }))
+            .count()
+    }
+
+    /// Number of errors reported on top of a complete set of annotated
+    /// errors, see [`Failure::Cascade`].
+    pub fn cascading(&self) -> usize {
+        self.all_failures()
+            .map(|f| match f {
+                Failure::Cascade(diagnostics) => diagnostics.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn all_failures(&self) -> impl Iterator<Item = &Failure> {
+        self.results.iter().flat_map(|r| &r.failures)
+    }
+
+    /// Panics with the full report if any case failed.
+    ///
+    /// Meant to be called from a `#[test]` in the crate of the compiler
+    /// under test.
+    #[track_caller]
+    pub fn assert_all_passed(&self) {
+        if self.failed() > 0 {
+            panic!("{}", self);
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            if result.passed() {
+                writeln!(f, "ok      {}", result.case.id())?;
+                continue;
+            }
+            writeln!(f, "FAILED  {}", result.case.id())?;
+            for failure in &result.failures {
+                writeln!(f, "        {}", failure)?;
+            }
+            if let Some(outcome) = &result.outcome {
+                for diagnostic in &outcome.diagnostics {
+                    writeln!(f, "        reported {}", diagnostic)?;
+                }
+            }
+        }
+        write!(
+            f,
+            "{}: {} passed, {} failed",
+            self.compiler,
+            self.passed(),
+            self.failed()
+        )?;
+        let (missed, cascading) = (self.missed(), self.cascading());
+        if missed + cascading > 0 {
+            write!(
+                f,
+                " ({} missed errors, {} cascading errors)",
+                missed, cascading
+            )?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Runs every case in `corpus` through `compiler`.
+///
+/// Panics inside the compiler are caught and reported as failures of the
+/// case that triggered them, so one crash does not hide the rest of the run.
+pub fn run(corpus: &Corpus, compiler: &mut dyn Compiler) -> Report {
+    let results = corpus
+        .cases
+        .iter()
+        .map(|case| run_case(case, compiler))
+        .collect();
+    Report {
+        compiler: compiler.name().to_string(),
+        results,
+    }
+}
+
+/// Runs and judges a single case.
+pub fn run_case(case: &TestCase, compiler: &mut dyn Compiler) -> CaseResult {
+    match panic::catch_unwind(AssertUnwindSafe(|| compiler.compile(case))) {
+        Ok(outcome) => CaseResult {
+            case: case.clone(),
+            failures: judge(case, &outcome),
+            outcome: Some(outcome),
+        },
+        Err(payload) => CaseResult {
+            case: case.clone(),
+            outcome: None,
+            failures: vec![Failure::Panicked(panic_message(payload))],
+        },
+    }
+}
+
+/// Runs a single file and panics with a report if it fails.
+///
+/// This is what the tests generated by [`crate::corpus_tests!`] call.
+#[track_caller]
+pub fn assert_case(compiler: &mut dyn Compiler, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let case =
+        TestCase::load(path).unwrap_or_else(|e| panic!("could not load {}: {}", path.display(), e));
+    let result = run_case(&case, compiler);
+    if !result.passed() {
+        let report = Report {
+            compiler: compiler.name().to_string(),
+            results: vec![result],
+        };
+        panic!("{}", report);
+    }
+}
+
+fn judge(case: &TestCase, outcome: &Outcome) -> Vec<Failure> {
+    let target = case.header.phase;
+    // A valid program only has to get through the passes up to the one its
+    // header names; what later passes think of it is out of scope.
+    let diagnostics: Vec<Diagnostic> = outcome
+        .diagnostics
+        .iter()
+        .filter(|d| case.expect == Expect::Reject || !is_after(d, target))
+        .cloned()
+        .collect();
+    let mut failures = Vec::new();
+    match (case.expect, diagnostics.is_empty()) {
+        (Expect::Accept, false) => failures.push(Failure::Rejected),
+        (Expect::Reject, true) => failures.push(Failure::Accepted),
+        _ => {}
+    }
+    // An accepted invalid program is already reported as such.
+    if diagnostics.is_empty() {
+        return failures;
+    }
+    if let (Expect::Reject, Some(expected)) = (case.expect, target) {
+        // The passes after the root error's in a cascade program only have
+        // cascading errors to report.
+        let cascade = case.dir == CASCADE_DIR;
+        let mut wrong: Vec<Phase> = diagnostics
+            .iter()
+            .filter(|d| !(cascade && is_after(d, target)))
+            .filter_map(|d| d.phase)
+            .filter(|&p| p != expected)
+            .collect();
+        wrong.sort();
+        wrong.dedup();
+        failures.extend(
+            wrong
+                .into_iter()
+                .map(|actual| Failure::WrongPhase { expected, actual }),
+        );
+    }
+    if !case.annotations.is_empty() {
+        failures.extend(match_lines(case, &diagnostics));
+    } else if let Some(expected) = case.header.errors {
+        let actual = diagnostics.len();
+        if actual != expected {
+            failures.push(Failure::ErrorCount { expected, actual });
+        }
+    }
+    failures
+}
+
+/// Whether `diagnostic` comes from a pass after `target`. Diagnostics that
+/// do not say which pass produced them never are.
+fn is_after(diagnostic: &Diagnostic, target: Option<Phase>) -> bool {
+    matches!((diagnostic.phase, target), (Some(p), Some(t)) if p > t)
+}
+
+/// Pairs reported diagnostics with annotated lines.
+///
+/// Each annotation is satisfied by one diagnostic on the same line, of the
+/// same kind if the diagnostic names one. Whatever is left over on either
+/// side is a missing or an unexpected error, or an error of the wrong kind
+/// when both are on the same line. Diagnostics without a line can never be
+/// matched. In `cascade_kxi`, diagnostics left over once every annotation is
+/// satisfied are reported together as a cascade rather than one by one.
+fn match_lines(case: &TestCase, diagnostics: &[Diagnostic]) -> Vec<Failure> {
+    let mut unmatched: Vec<&Diagnostic> = diagnostics.iter().collect();
+    // Diagnostics of the annotated kind are matched before those without
+    // one, so that the latter are not used up by annotations the former
+    // would have satisfied.
+    let mut annotations: Vec<_> = case.annotations.iter().collect();
+    for unnamed in [false, true] {
+        annotations.retain(|a| {
+            let found = unmatched.iter().position(|d| {
+                d.line == Some(a.line) && (d.kind == Some(a.kind) || unnamed && d.kind.is_none())
+            });
+            match found {
+                Some(idx) => {
+                    unmatched.remove(idx);
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+    if annotations.is_empty() && case.dir == CASCADE_DIR {
+        if unmatched.is_empty() {
+            return Vec::new();
+        }
+        return vec![Failure::Cascade(unmatched.into_iter().cloned().collect())];
+    }
+    let mut failures = Vec::new();
+    for annotation in annotations {
+        let wrong = unmatched
+            .iter()
+            .position(|d| d.line == Some(annotation.line));
+        match wrong {
+            Some(idx) => {
+                let d = unmatched.remove(idx);
+                failures.push(Failure::WrongKind {
+                    line: annotation.line,
+                    expected: annotation.kind,
+                    actual: d.kind.expect("diagnostics without a kind match any"),
+                });
+            }
+            None => failures.push(Failure::MissingError {
+                line: annotation.line,
+                kind: annotation.kind,
+            }),
+        }
+    }
+    failures.extend(
+        unmatched
+            .into_iter()
+            .map(|d| Failure::UnexpectedError(d.clone())),
+    );
+    failures
+}
+
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
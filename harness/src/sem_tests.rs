@@ -0,0 +1,232 @@
+//! Renders the corpus as a `sem_tests.rs` file.
+//!
+//! `dons_tests/sem_tests.rs` was written by hand against one particular
+//! compiler (`kxi::CompUnitParser`, `tok_gen`, `SymbolTable`, `SymbolInner`,
+//! `TypeCheck`). This module produces the same kind of file from the `.kxi`
+//! corpus, so the corpus stays the single source of truth and the Rust tests
+//! can be regenerated whenever it changes.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+use crate::corpus::{Corpus, Expect, TestCase};
+use crate::expect::strip_expectations;
+use crate::phase::Phase;
+
+/// Indentation of every source line after the first inside the literal,
+/// matching the hand-written tests.
+const SOURCE_INDENT: &str = "            ";
+
+/// Renders one `#[test]` per case, grouped by the pass under test.
+pub fn render(corpus: &Corpus) -> String {
+    let mut out = String::from(
+        "// Generated from the .kxi corpus by `cargo run -p kxi_harness --bin gen-sem-tests`.\n\
+         // Edit the .kxi files instead of this file.\n",
+    );
+    let names = test_names(corpus);
+    for (phase, title) in [
+        (Phase::Parse, "Parser tests"),
+        (Phase::Declare, "Symbol table tests"),
+        (Phase::Resolve, "Inner Symbol Table and scope tests"),
+        (Phase::TypeCheck, "Type Checking Tests"),
+    ] {
+        let mut cases: Vec<(&TestCase, &String)> = corpus
+            .cases
+            .iter()
+            .zip(&names)
+            .filter(|(c, _)| phase_of(c) == phase)
+            .collect();
+        if cases.is_empty() {
+            continue;
+        }
+        cases.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+        writeln!(out, "\n// {}\n", title).unwrap();
+        for (case, name) in cases {
+            render_case(&mut out, name, case, phase);
+        }
+    }
+    out
+}
+
+/// The name of each case's test: its file name, followed by its directory
+/// if a file of the same name is in another one, as in `scope_out` and
+/// `scope_out_invalid`. Files in `valid_kxi/` keep the bare name.
+///
+/// Panics if two files of one directory still give the same name, such as
+/// `a-b.kxi` and `a_b.kxi`.
+fn test_names(corpus: &Corpus) -> Vec<String> {
+    let ident = |case: &TestCase| case.name.replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+    let names: Vec<String> = corpus
+        .cases
+        .iter()
+        .map(|case| {
+            let name = ident(case);
+            let shared = corpus
+                .cases
+                .iter()
+                .any(|other| other.dir != case.dir && ident(other) == name);
+            if !shared || case.dir == "valid_kxi" {
+                return name;
+            }
+            let dir = case.dir.strip_suffix("_kxi").unwrap_or(&case.dir);
+            format!(
+                "{}_{}",
+                name,
+                dir.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+            )
+        })
+        .collect();
+    let mut seen = HashSet::new();
+    for (case, name) in corpus.cases.iter().zip(&names) {
+        assert!(
+            seen.insert(name),
+            "{} gives the test name `{}` a second time",
+            case.id(),
+            name
+        );
+    }
+    names
+}
+
+/// The pass a case targets. Files without a header are run through every
+/// pass.
+fn phase_of(case: &TestCase) -> Phase {
+    case.header.phase.unwrap_or(Phase::TypeCheck)
+}
+
+fn render_case(out: &mut String, name: &str, case: &TestCase, phase: Phase) {
+    let literal = raw_literal(&indent(&strip_expectations(&case.source)));
+    writeln!(out, "#[test]").unwrap();
+    writeln!(out, "fn {}() {{", name).unwrap();
+    writeln!(out, "    let parser = kxi::CompUnitParser::new();").unwrap();
+    if phase == Phase::Parse {
+        writeln!(
+            out,
+            "    let res = parser.parse(tok_gen(\n        {},\n    ));",
+            literal
+        )
+        .unwrap();
+        match case.expect {
+            Expect::Accept => writeln!(out, "    assert!(res.is_ok());").unwrap(),
+            Expect::Reject => writeln!(out, "    assert!(res.is_err());").unwrap(),
+        }
+        writeln!(out, "}}").unwrap();
+        return;
+    }
+    writeln!(out, "    let mut res = parser").unwrap();
+    writeln!(
+        out,
+        "        .parse(tok_gen(\n            {},\n        ))",
+        literal
+    )
+    .unwrap();
+    writeln!(out, "        .unwrap();").unwrap();
+    writeln!(out, "    let mut st = SymbolTable::default();").unwrap();
+    writeln!(out, "    res.accept(&mut st);").unwrap();
+    if phase >= Phase::Resolve {
+        writeln!(out, "    let table = st.table;").unwrap();
+        writeln!(out, "    let scope = st.c_scopes;").unwrap();
+        writeln!(out, "    let mut st = SymbolInner::new(table, scope);").unwrap();
+        writeln!(out, "    res.accept(&mut st);").unwrap();
+    }
+    if phase >= Phase::TypeCheck {
+        writeln!(out, "    let table = st.table;").unwrap();
+        writeln!(out, "    let mut st = TypeCheck::new(table);").unwrap();
+        writeln!(out, "    res.accept(&mut st);").unwrap();
+    }
+    let expected = match case.expect {
+        Expect::Accept => Some(0),
+        Expect::Reject => case
+            .header
+            .errors
+            .or((!case.annotations.is_empty()).then_some(case.annotations.len())),
+    };
+    match expected {
+        Some(n) => writeln!(out, "    assert_eq!(st.errs.len(), {});", n).unwrap(),
+        None => writeln!(out, "    assert!(!st.errs.is_empty());").unwrap(),
+    }
+    writeln!(out, "}}").unwrap();
+}
+
+/// Indents every line but the first, the way the hand-written tests lay
+/// out their string literals.
+fn indent(source: &str) -> String {
+    let mut lines = source.trim_end().lines();
+    let mut out = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        out.push('\n');
+        if !line.is_empty() {
+            out.push_str(SOURCE_INDENT);
+        }
+        out.push_str(line);
+    }
+    out.push('\n');
+    out
+}
+
+/// Wraps `text` in a raw string literal with enough `#`s to hold it.
+fn raw_literal(text: &str) -> String {
+    let mut hashes = String::new();
+    while text.contains(&format!("\"{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("r{0}\"{1}\"{0}", hashes, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_literals_escape_quotes() {
+        assert_eq!(raw_literal("int x;"), "r\"int x;\"");
+        assert_eq!(raw_literal("string z = \"s\";"), "r#\"string z = \"s\";\"#");
+    }
+
+    fn case(dir: &str, name: &str) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            dir: dir.to_string(),
+            path: format!("{}/{}.kxi", dir, name).into(),
+            source: "void main(){}\n".to_string(),
+            expect: Expect::Accept,
+            header: Default::default(),
+            annotations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn names_shared_between_directories_get_the_directory() {
+        let corpus = Corpus {
+            root: ".".into(),
+            cases: vec![
+                case("cascade_kxi", "scope"),
+                case("invalid_kxi", "scope"),
+                case("invalid_kxi", "x"),
+                case("valid_kxi", "scope"),
+            ],
+        };
+        assert_eq!(
+            test_names(&corpus),
+            ["scope_cascade", "scope_invalid", "x", "scope"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "second time")]
+    fn clashing_names_in_one_directory_panic() {
+        let corpus = Corpus {
+            root: ".".into(),
+            cases: vec![case("valid_kxi", "a-b"), case("valid_kxi", "a_b")],
+        };
+        test_names(&corpus);
+    }
+
+    #[test]
+    fn indents_continuation_lines() {
+        assert_eq!(
+            indent("void main(){\n    int x;\n\n}\n"),
+            "void main(){\n                int x;\n\n            }\n"
+        );
+    }
+}
@@ -0,0 +1,67 @@
+// Shared by several test binaries, including those of kxi_sem_check, each of
+// which uses only part of it.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use kxi_harness::{Compiler, Diagnostic, Expect, Outcome, TestCase};
+
+/// A throwaway corpus directory, removed again on drop.
+pub struct Scratch {
+    root: PathBuf,
+}
+
+impl Scratch {
+    pub fn new(name: &str) -> Scratch {
+        let root =
+            std::env::temp_dir().join(format!("kxi_harness_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Scratch { root }
+    }
+
+    /// Writes `source` to `rel`, creating parent directories as needed.
+    pub fn file(&self, rel: &str, source: &str) -> PathBuf {
+        let path = self.root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, source).unwrap();
+        path
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Rejects exactly the programs the corpus says are invalid, reporting the
+/// annotated errors or as many errors as the header asks for.
+pub struct Oracle;
+
+impl Compiler for Oracle {
+    fn name(&self) -> &str {
+        "oracle"
+    }
+
+    fn compile(&mut self, case: &TestCase) -> Outcome {
+        match case.expect {
+            Expect::Accept => Outcome::accepted(),
+            Expect::Reject if !case.annotations.is_empty() => Outcome::rejected(
+                case.annotations
+                    .iter()
+                    .map(|a| Diagnostic::at(a.line, a.kind.to_string()))
+                    .collect(),
+            ),
+            Expect::Reject => {
+                let count = case.header.errors.unwrap_or(1);
+                Outcome::rejected(vec![Diagnostic::new("rejected"); count])
+            }
+        }
+    }
+}
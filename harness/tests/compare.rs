@@ -0,0 +1,122 @@
+mod common;
+
+use common::{Oracle, Scratch};
+use kxi_harness::compare::{self, load_compilers};
+use kxi_harness::{Compiler, Corpus, Diagnostic, Expect, Matrix, Outcome, TestCase};
+
+/// Accepts every program.
+struct AcceptAll;
+
+impl Compiler for AcceptAll {
+    fn name(&self) -> &str {
+        "accept-all"
+    }
+
+    fn compile(&mut self, _case: &TestCase) -> Outcome {
+        Outcome::accepted()
+    }
+}
+
+/// Rejects the invalid programs, but always with a single error.
+struct OneError;
+
+impl Compiler for OneError {
+    fn name(&self) -> &str {
+        "one-error"
+    }
+
+    fn compile(&mut self, case: &TestCase) -> Outcome {
+        match case.expect {
+            Expect::Accept => Outcome::accepted(),
+            Expect::Reject => Outcome::rejected(vec![Diagnostic::new("rejected")]),
+        }
+    }
+}
+
+fn corpus(scratch: &Scratch) -> Corpus {
+    scratch.file("valid_kxi/ok.kxi", "void main(){}\n");
+    scratch.file(
+        "invalid_kxi/one.kxi",
+        "// expect: phase=typecheck errors=1\nvoid main(){\n    int x = true; // error: type-mismatch\n}\n",
+    );
+    scratch.file(
+        "invalid_kxi/two.kxi",
+        "// expect: phase=typecheck errors=2\nvoid main(){\n    int x = true; // error: type-mismatch\n    bool b = 1; // error: type-mismatch\n}\n",
+    );
+    Corpus::discover(scratch.root()).unwrap()
+}
+
+#[test]
+fn flags_files_compilers_disagree_on() {
+    let scratch = Scratch::new("compare");
+    let corpus = corpus(&scratch);
+    let mut compilers: Vec<Box<dyn Compiler>> =
+        vec![Box::new(Oracle), Box::new(OneError), Box::new(AcceptAll)];
+    let matrix = compare::compare(&corpus, &mut compilers);
+    assert_eq!(matrix.len(), 3);
+    assert_eq!(
+        matrix.disagreements(),
+        ["invalid_kxi/one.kxi", "invalid_kxi/two.kxi"]
+    );
+    assert_eq!(
+        matrix.leaderboard(),
+        [("oracle", 3), ("one-error", 1), ("accept-all", 1)]
+    );
+
+    let text = matrix.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines[0].split_whitespace().collect::<Vec<_>>(),
+        ["file", "oracle", "one-error", "accept-all"]
+    );
+    assert_eq!(
+        lines[2].split_whitespace().collect::<Vec<_>>(),
+        ["!", "invalid_kxi/two.kxi", "ok", "2", "FAIL", "1", "FAIL"]
+    );
+    assert!(lines[3].starts_with("   valid_kxi/ok.kxi"));
+    assert!(text.contains("2 of 3 files with disagreements\n1. oracle: 3 passed\n"));
+}
+
+#[test]
+fn agreeing_on_a_wrong_answer_is_not_a_disagreement() {
+    let scratch = Scratch::new("compare_agree");
+    let corpus = corpus(&scratch);
+    let matrix = Matrix::new(vec![
+        kxi_harness::run(&corpus, &mut AcceptAll),
+        kxi_harness::run(&corpus, &mut AcceptAll),
+    ]);
+    assert!(matrix.disagreements().is_empty());
+    assert!(matrix.row(0).all(|r| !r.passed()));
+}
+
+#[test]
+#[should_panic(expected = "were run over different files")]
+fn reports_must_cover_the_same_files() {
+    let scratch = Scratch::new("compare_files");
+    let corpus = corpus(&scratch);
+    let mut fewer = corpus.clone();
+    fewer.cases.pop();
+    Matrix::new(vec![
+        kxi_harness::run(&corpus, &mut Oracle),
+        kxi_harness::run(&fewer, &mut Oracle),
+    ]);
+}
+
+#[test]
+fn loads_compilers_from_json() {
+    let compilers = load_compilers(
+        r#"[
+            {"name": "reference"},
+            {"name": "alice", "command": ["./kxic", "{file}"], "json": true},
+            {"name": "bob", "command": ["bob"], "pattern": "^(?P<line>\\d+): (?P<message>.*)$"}
+        ]"#,
+    )
+    .unwrap();
+    let names: Vec<&str> = compilers.iter().map(|c| c.name()).collect();
+    assert_eq!(names, ["reference", "alice", "bob"]);
+
+    let error = |json| load_compilers(json).err().unwrap();
+    assert_eq!(error(r#"[{"name": "carol"}]"#), "carol: no command");
+    assert!(error(r#"[{"name": "d", "command": ["d"], "pattern": "("}]"#).starts_with("d: "));
+    assert!(error(r#"[{"name": "e", "cmd": ["e"]}]"#).contains("unknown field `cmd`"));
+}
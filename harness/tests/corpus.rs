@@ -0,0 +1,411 @@
+mod common;
+
+use common::{Oracle, Scratch};
+use kxi_harness::{
+    Compiler, Corpus, Coverage, Diagnostic, Expect, Failure, Filter, Kind, Outcome, Phase, TestCase,
+};
+
+/// Accepts every program.
+struct AcceptAll;
+
+impl Compiler for AcceptAll {
+    fn name(&self) -> &str {
+        "accept-all"
+    }
+
+    fn compile(&mut self, _case: &TestCase) -> Outcome {
+        Outcome::accepted()
+    }
+}
+
+/// Rejects the invalid programs, but always with a single error.
+struct OneError;
+
+impl Compiler for OneError {
+    fn name(&self) -> &str {
+        "one-error"
+    }
+
+    fn compile(&mut self, case: &TestCase) -> Outcome {
+        match case.expect {
+            Expect::Accept => Outcome::accepted(),
+            Expect::Reject => Outcome::rejected(vec![Diagnostic::new("rejected")]),
+        }
+    }
+}
+
+/// Reports the right number of errors, all on the first line.
+struct WrongLines;
+
+impl Compiler for WrongLines {
+    fn name(&self) -> &str {
+        "wrong-lines"
+    }
+
+    fn compile(&mut self, case: &TestCase) -> Outcome {
+        let count = match case.expect {
+            Expect::Accept => 0,
+            Expect::Reject => case.header.errors.unwrap_or(1),
+        };
+        Outcome::rejected(vec![Diagnostic::at(1, "error"); count])
+    }
+}
+
+/// Reports the same diagnostics for every program.
+struct Fixed(Vec<Diagnostic>);
+
+impl Compiler for Fixed {
+    fn name(&self) -> &str {
+        "fixed"
+    }
+
+    fn compile(&mut self, _case: &TestCase) -> Outcome {
+        Outcome::rejected(self.0.clone())
+    }
+}
+
+/// Panics on one particular file.
+struct Fragile;
+
+impl Compiler for Fragile {
+    fn name(&self) -> &str {
+        "fragile"
+    }
+
+    fn compile(&mut self, case: &TestCase) -> Outcome {
+        if case.name == "scope_out" {
+            panic!("unexpected token");
+        }
+        Oracle.compile(case)
+    }
+}
+
+#[test]
+fn discovers_dons_tests() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let valid = corpus
+        .cases
+        .iter()
+        .filter(|c| c.expect == Expect::Accept)
+        .count();
+    let invalid = corpus
+        .cases
+        .iter()
+        .filter(|c| c.expect == Expect::Reject)
+        .count();
+    assert!(valid > 0 && invalid > 0);
+    assert_eq!(valid + invalid, corpus.len());
+    assert!(corpus
+        .cases
+        .iter()
+        .any(|c| c.id() == "valid_kxi/dec_type_check.kxi"));
+    assert!(corpus
+        .cases
+        .iter()
+        .any(|c| c.id() == "invalid_kxi/bad_dec_type_check.kxi"));
+}
+
+#[test]
+fn filters_select_by_phase_dir_tag_and_name() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let names = |filter: Filter| -> Vec<String> {
+        corpus
+            .filter(&filter)
+            .cases
+            .into_iter()
+            .map(|c| c.name)
+            .collect()
+    };
+    assert_eq!(
+        names(Filter::new().name("double_dec_*").phase(Phase::Resolve)),
+        ["double_dec_parm", "double_dec_var"]
+    );
+    assert_eq!(
+        names(Filter::new().tag("arrays").dir("invalid_kxi")),
+        ["newa_bad_type_check"]
+    );
+    assert_eq!(
+        names(
+            Filter::new()
+                .name("valid_kxi/nested_dot*")
+                .name("this_?n_static")
+        ),
+        ["this_in_static", "nested_dot", "nested_dot_type_check"]
+    );
+    assert_eq!(names(Filter::new().tag("loops").tag("switch")).len(), 4);
+    assert!(names(Filter::new().name("double.dec*")).is_empty());
+    assert_eq!(corpus.filter(&Filter::new()).len(), corpus.len());
+    for case in &corpus.cases {
+        assert!(!case.header.tags.is_empty(), "{} has no tags", case.id());
+    }
+}
+
+#[test]
+fn cases_are_sorted() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let ids: Vec<_> = corpus.cases.iter().map(TestCase::id).collect();
+    let mut sorted = ids.clone();
+    sorted.sort();
+    assert_eq!(ids, sorted);
+}
+
+#[test]
+fn accept_all_fails_every_invalid_case() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let report = kxi_harness::run(&corpus, &mut AcceptAll);
+    for result in &report.results {
+        match result.case.expect {
+            Expect::Accept => assert!(result.passed(), "{}", result.case.id()),
+            Expect::Reject => assert_eq!(result.failures, [Failure::Accepted]),
+        }
+    }
+}
+
+#[test]
+fn oracle_passes_everything() {
+    let corpus = Corpus::dons_tests().unwrap();
+    kxi_harness::run(&corpus, &mut Oracle).assert_all_passed();
+}
+
+#[test]
+fn every_invalid_case_has_a_header() {
+    let corpus = Corpus::dons_tests().unwrap();
+    for case in corpus.cases.iter().filter(|c| c.expect == Expect::Reject) {
+        assert!(case.header.phase.is_some(), "{} has no phase", case.id());
+        assert!(
+            case.header.errors.is_some_and(|n| n > 0),
+            "{} has no error count",
+            case.id()
+        );
+    }
+}
+
+#[test]
+fn header_counts_are_enforced() {
+    let scratch = Scratch::new("counts");
+    scratch.file(
+        "invalid_kxi/two_errors.kxi",
+        "// expect: phase=typecheck errors=2\nvoid main(){\n    +true;\n    !1;\n}\n",
+    );
+    let corpus = Corpus::discover(scratch.root()).unwrap();
+    let report = kxi_harness::run(&corpus, &mut OneError);
+    let case = &report.results[0];
+    assert_eq!(case.case.header.phase, Some(Phase::TypeCheck));
+    assert_eq!(
+        case.failures,
+        vec![Failure::ErrorCount {
+            expected: 2,
+            actual: 1
+        }]
+    );
+    kxi_harness::run(&corpus, &mut Oracle).assert_all_passed();
+}
+
+#[test]
+fn every_valid_case_names_its_last_phase() {
+    let corpus = Corpus::dons_tests().unwrap();
+    for case in corpus.cases.iter().filter(|c| c.expect == Expect::Accept) {
+        assert!(case.header.phase.is_some(), "{} has no phase", case.id());
+    }
+}
+
+#[test]
+fn every_invalid_case_is_annotated() {
+    let corpus = Corpus::dons_tests().unwrap();
+    for case in corpus.cases.iter().filter(|c| c.expect == Expect::Reject) {
+        assert_eq!(
+            Some(case.annotations.len()),
+            case.header.errors,
+            "{}",
+            case.id()
+        );
+    }
+}
+
+#[test]
+fn coverage_counts_tests_and_errors_per_kind() {
+    let scratch = Scratch::new("coverage");
+    scratch.file(
+        "invalid_kxi/args.kxi",
+        "// expect: phase=typecheck errors=3\nvoid main(){\n    f('c', 1); // error: type-mismatch, type-mismatch\n    f(); // error: bad-arg-count\n}\n",
+    );
+    scratch.file(
+        "invalid_kxi/assign.kxi",
+        "// expect: phase=typecheck\nvoid main(){\n    int x = true; // error: type-mismatch\n}\n",
+    );
+    scratch.file("invalid_kxi/bare.kxi", "void main(){ x = 1; }\n");
+    scratch.file("valid_kxi/ok.kxi", "void main(){}\n");
+    let coverage = Coverage::of(&Corpus::discover(scratch.root()).unwrap());
+    assert_eq!(coverage.tests(Kind::TypeMismatch).len(), 2);
+    assert_eq!(coverage.errors(Kind::TypeMismatch), 3);
+    assert_eq!(coverage.tests(Kind::BadArgCount), ["invalid_kxi/args.kxi"]);
+    assert!(coverage.gaps().contains(&Kind::MissingReturn));
+    assert!(!coverage.gaps().contains(&Kind::BadArgCount));
+    assert_eq!(coverage.unannotated(), ["invalid_kxi/bare.kxi"]);
+    let report = coverage.to_string();
+    assert!(report
+        .lines()
+        .any(|l| l.split_whitespace().eq(["type-mismatch", "2", "3"])));
+    assert!(report.contains("not annotated: invalid_kxi/bare.kxi"));
+}
+
+#[test]
+fn unreachable_code_is_a_gap_in_dons_tests() {
+    let coverage = Coverage::of(&Corpus::dons_tests().unwrap());
+    assert!(coverage.gaps().contains(&Kind::UnreachableCode));
+    assert!(coverage.unannotated().is_empty());
+    assert_eq!(
+        coverage.tests(Kind::MissingReturn),
+        ["invalid_kxi/bad_return_type_check.kxi"]
+    );
+}
+
+#[test]
+fn errors_on_the_wrong_lines_fail() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let report = kxi_harness::run(&corpus, &mut WrongLines);
+    for result in &report.results {
+        let fails = result.case.expect == Expect::Reject;
+        assert_eq!(result.passed(), !fails, "{}", result.case.id());
+    }
+    let dec = report
+        .results
+        .iter()
+        .find(|r| r.case.name == "bad_dec_type_check")
+        .unwrap();
+    let missing: Vec<_> = dec
+        .failures
+        .iter()
+        .filter_map(|f| match f {
+            Failure::MissingError { line, kind } => Some((*line, kind.as_str())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        missing,
+        [
+            (2, "type-mismatch"),
+            (3, "type-mismatch"),
+            (4, "type-mismatch"),
+            (5, "type-mismatch"),
+        ]
+    );
+    let unexpected = dec
+        .failures
+        .iter()
+        .filter(|f| matches!(f, Failure::UnexpectedError(d) if d.line == Some(1)))
+        .count();
+    assert_eq!(unexpected, 4);
+}
+
+#[test]
+fn cascades_are_told_apart_from_missed_errors() {
+    let scratch = Scratch::new("cascade");
+    scratch.file(
+        "cascade_kxi/undeclared.kxi",
+        "// expect: phase=resolve errors=1\nvoid main(){\n    int y = nope + 1; // error: undeclared-identifier\n    y = nope * 2;\n}\n",
+    );
+    let corpus = Corpus::discover(scratch.root()).unwrap();
+    assert_eq!(corpus.cases[0].expect, Expect::Reject);
+
+    let root = Diagnostic::at(3, "nope is not declared");
+    let again = Diagnostic::at(4, "nope is not declared");
+    let report = kxi_harness::run(&corpus, &mut Fixed(vec![root, again.clone()]));
+    assert_eq!(
+        report.results[0].failures,
+        [Failure::Cascade(vec![again.clone()])]
+    );
+    assert_eq!((report.missed(), report.cascading()), (0, 1));
+    assert!(report
+        .to_string()
+        .contains("1 cascading errors: line 4: nope is not declared"));
+    assert!(report
+        .to_string()
+        .contains("0 passed, 1 failed (0 missed errors, 1 cascading errors)"));
+
+    let elsewhere = Diagnostic::at(4, "nope is not declared");
+    let report = kxi_harness::run(&corpus, &mut Fixed(vec![elsewhere]));
+    assert_eq!((report.missed(), report.cascading()), (1, 0));
+
+    // Elsewhere, errors on top of the annotated ones are just unexpected.
+    let path = scratch.file("invalid_kxi/undeclared.kxi", &corpus.cases[0].source);
+    let case = TestCase::load(&path).unwrap();
+    let root = Diagnostic::at(3, "nope is not declared");
+    let result = kxi_harness::run_case(&case, &mut Fixed(vec![root, again.clone()]));
+    assert_eq!(result.failures, [Failure::UnexpectedError(again)]);
+}
+
+#[test]
+fn cascade_cases_have_a_single_annotation() {
+    let scratch = Scratch::new("cascade_two");
+    let path = scratch.file(
+        "cascade_kxi/two.kxi",
+        "// expect: phase=typecheck\nvoid main(){\n    int x = true; // error: type-mismatch\n    bool b = 1; // error: type-mismatch\n}\n",
+    );
+    let err = TestCase::load(&path).unwrap_err();
+    assert!(err.to_string().contains("exactly one error, found 2"));
+}
+
+#[test]
+fn reported_kinds_must_match_annotations() {
+    let scratch = Scratch::new("kinds");
+    let path = scratch.file(
+        "invalid_kxi/kinds.kxi",
+        "// expect: phase=typecheck errors=2\nclass A { A(int x) {} }\nvoid main(){\n    A a = new A(true, 1); // error: type-mismatch, bad-arg-count\n}\n",
+    );
+    let case = TestCase::load(&path).unwrap();
+    let unnamed = Diagnostic::at(4, "something");
+    let arity = Diagnostic::at(4, "too many").with_kind(Kind::BadArgCount);
+    let result = kxi_harness::run_case(&case, &mut Fixed(vec![unnamed, arity.clone()]));
+    assert!(result.passed(), "{:?}", result.failures);
+
+    let undeclared = Diagnostic::at(4, "no A").with_kind(Kind::UndeclaredIdentifier);
+    let result = kxi_harness::run_case(&case, &mut Fixed(vec![undeclared, arity]));
+    assert_eq!(
+        result.failures,
+        [Failure::WrongKind {
+            line: 4,
+            expected: Kind::TypeMismatch,
+            actual: Kind::UndeclaredIdentifier,
+        }]
+    );
+}
+
+#[test]
+fn annotations_must_agree_with_the_header() {
+    let scratch = Scratch::new("mismatch");
+    let path = scratch.file(
+        "invalid_kxi/mismatch.kxi",
+        "// expect: phase=resolve errors=2\nvoid main(){\n    y = 4; // error: undeclared-identifier\n}\n",
+    );
+    let err = TestCase::load(&path).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("header expects 2 errors but 1 are annotated"));
+}
+
+#[test]
+fn valid_programs_cannot_be_annotated() {
+    let scratch = Scratch::new("annotated_valid");
+    let path = scratch.file(
+        "valid_kxi/annotated.kxi",
+        "void main(){\n    y = 4; // error: undeclared-identifier\n}\n",
+    );
+    assert!(TestCase::load(&path).is_err());
+}
+
+#[test]
+fn panics_are_reported_per_case() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let report = kxi_harness::run(&corpus, &mut Fragile);
+    assert_eq!(report.failed(), 1);
+    let failed = report.failures().next().unwrap();
+    assert_eq!(failed.case.id(), "invalid_kxi/scope_out.kxi");
+    assert_eq!(
+        failed.failures,
+        vec![Failure::Panicked("unexpected token".to_string())]
+    );
+    assert!(report
+        .to_string()
+        .contains("FAILED  invalid_kxi/scope_out.kxi"));
+}
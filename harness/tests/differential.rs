@@ -0,0 +1,152 @@
+use kxi_harness::corpus::dons_tests_dir;
+use kxi_harness::differential::{generated, load_programs};
+use kxi_harness::{
+    golden, Backend, Comparison, Differential, Execution, Generator, Reference, Run, RunCase,
+    RunFailure, RuntimeError, Status,
+};
+
+/// Runs programs in the reference with every `%` taken for a `/`.
+struct NoRemainder;
+
+impl Backend for NoRemainder {
+    fn name(&self) -> &str {
+        "no-remainder"
+    }
+
+    fn execute(&mut self, case: &RunCase) -> Execution {
+        let case = RunCase {
+            source: case.source.replace('%', "/"),
+            ..case.clone()
+        };
+        Reference.execute(&case)
+    }
+}
+
+/// Runs programs in the reference, but fails with the status of an abort.
+struct Aborts;
+
+impl Backend for Aborts {
+    fn name(&self) -> &str {
+        "aborts"
+    }
+
+    fn execute(&mut self, case: &RunCase) -> Execution {
+        match Reference.execute(case) {
+            Execution::Ran(run) if run.status == Status::Exited(1) => Execution::Ran(Run {
+                status: Status::Exited(134),
+                ..run
+            }),
+            other => other,
+        }
+    }
+}
+
+/// Rejects every program.
+struct Rejects;
+
+impl Backend for Rejects {
+    fn name(&self) -> &str {
+        "rejects"
+    }
+
+    fn execute(&mut self, _: &RunCase) -> Execution {
+        Execution::Rejected(Vec::new())
+    }
+}
+
+fn program(name: &str, source: &str) -> RunCase {
+    RunCase {
+        name: name.to_string(),
+        path: format!("programs/{}.kxi", name).into(),
+        source: source.to_string(),
+        ..generated(&Generator::new(0))
+    }
+}
+
+#[test]
+fn the_reference_agrees_with_itself() {
+    let mut cases = load_programs(&dons_tests_dir().join("valid_kxi")).unwrap();
+    cases.extend(load_programs(&golden::dir("run_kxi")).unwrap());
+    cases.extend((0..3).map(|seed| generated(&Generator::new(seed))));
+    let report = Differential::new().run(&cases, &mut Reference);
+    assert!(report.divergences().is_empty(), "{}", report);
+    assert_eq!(report.agreed() + report.skipped(), cases.len());
+    let skipped = |id: &str| {
+        report
+            .programs
+            .iter()
+            .any(|(i, c)| i == id && matches!(c, Comparison::Skipped(_)))
+    };
+    assert!(skipped("valid_kxi/nested_dot.kxi"), "{}", report);
+    assert!(!skipped("valid_kxi/binary_type_check.kxi"), "{}", report);
+}
+
+#[test]
+fn reduces_a_divergence() {
+    let case = generated(&Generator::new(11));
+    assert!(case.source.contains('%'));
+    let differential = Differential::new().reduce(true);
+    let Comparison::Diverged(divergence) = differential.compare(&case, &mut NoRemainder) else {
+        panic!("no divergence on {}", case.source);
+    };
+    assert!(matches!(
+        divergence.result.failures[0],
+        RunFailure::Stdout { .. }
+    ));
+    let reduced = divergence.reduced.as_ref().unwrap();
+    assert!(!reduced.passed());
+    assert!(
+        reduced.case.source.lines().count() <= 4,
+        "{}",
+        reduced.case.source
+    );
+    assert!(reduced.case.source.contains('%'), "{}", reduced.case.source);
+    assert_eq!(reduced.case.id(), "generated/gen_11.kxi");
+}
+
+#[test]
+fn expects_what_the_reference_does() {
+    let case = program(
+        "divide",
+        "void main() {\n    cout << 7 % 4;\n    cout << 1 / 0;\n}\n",
+    );
+    let Comparison::Diverged(divergence) = Differential::new().compare(&case, &mut NoRemainder)
+    else {
+        panic!("no divergence");
+    };
+    assert_eq!(divergence.result.case.stdout, "3");
+    assert_eq!(
+        divergence.result.case.runtime_error,
+        Some(RuntimeError::DivisionByZero)
+    );
+    assert!(divergence.reduced.is_none());
+
+    let report = Differential::new().run(&[case], &mut Rejects);
+    let divergences = report.divergences();
+    assert!(matches!(
+        divergences[0].result.failures[..],
+        [RunFailure::Rejected]
+    ));
+}
+
+#[test]
+fn runtime_errors_may_end_with_any_failing_status() {
+    let case = program(
+        "divide",
+        "void main() {\n    cout << 7;\n    cout << 1 / 0;\n}\n",
+    );
+    let comparison = Differential::new().compare(&case, &mut Aborts);
+    assert!(matches!(comparison, Comparison::Agreed), "{:?}", comparison);
+}
+
+#[test]
+fn skips_what_the_reference_cannot_tell() {
+    let cases = [
+        program("rejected", "void main() {\n    x = 1;\n}\n"),
+        program("forever", "void main() {\n    while (true) {\n    }\n}\n"),
+    ];
+    let report = Differential::new()
+        .max_steps(1000)
+        .run(&cases, &mut Rejects);
+    assert_eq!(report.skipped(), 2, "{}", report);
+}
@@ -0,0 +1,254 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::Scratch;
+use kxi_harness::corpus::dons_tests_dir;
+use kxi_harness::{Compiler, Corpus, Diagnostic, External, Failure, Kind, Phase};
+
+/// A shell compiler that reports every `// error:` annotation of the file it
+/// is given in `file:line:col: error: kind` form and fails if there was one.
+const ORACLE: &str = r#"awk '/\/\/ error:/ {
+    n = split(substr($0, index($0, "// error:") + 9), kinds, ",")
+    for (i = 1; i <= n; i++) printf "%s:%d:1: error: %s\n", FILENAME, NR, kinds[i] > "/dev/stderr"
+    found = 1
+} END { exit found }' "$0""#;
+
+fn shell(script: &str) -> External {
+    External::new("sh").arg("-c").arg(script)
+}
+
+#[test]
+fn shell_oracle_passes_dons_tests() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let mut oracle = shell(ORACLE).named("awk-oracle");
+    let report = kxi_harness::run(&corpus, &mut oracle);
+    assert_eq!(report.compiler, "awk-oracle");
+    report.assert_all_passed();
+}
+
+#[test]
+fn parses_file_line_column_diagnostics() {
+    let scratch = Scratch::new("external_parse");
+    let path = scratch.file(
+        "invalid_kxi/scope_out.kxi",
+        "// expect: phase=resolve errors=1\nvoid main(){\n    y = 4; // error: undeclared-identifier\n}\n",
+    );
+    let case = kxi_harness::TestCase::load(&path).unwrap();
+    let mut compiler = shell(
+        r#"echo "note: checking $0" >&2; echo "$0:3:5: error: y is not declared" >&2; exit 1"#,
+    );
+    let outcome = compiler.compile(&case);
+    assert_eq!(outcome.diagnostics.len(), 1);
+    let diagnostic = &outcome.diagnostics[0];
+    assert_eq!(diagnostic.line, Some(3));
+    assert_eq!(diagnostic.column, Some(5));
+    assert_eq!(diagnostic.message, "y is not declared");
+    assert!(kxi_harness::run_case(&case, &mut compiler).passed());
+}
+
+#[test]
+fn diagnostics_about_other_files_are_left_out() {
+    let compiler = shell("true");
+    let file = Path::new("/corpus/invalid_kxi/scope_out.kxi");
+    let stderr = "lib.kxi:1:1: error: unused\n\
+                  ./invalid_kxi/scope_out.kxi:3:5: error: y is not declared\n\
+                  scope_out.kxi:4:1: error: again\n\
+                  other/scope_out.kxi:5:1: error: elsewhere\n\
+                  /corpus/invalid_kxi/scope_out.kxi:6:1: error: absolute\n";
+    let messages: Vec<String> = compiler
+        .parse_diagnostics(stderr, file)
+        .unwrap()
+        .into_iter()
+        .map(|d| d.message)
+        .collect();
+    assert_eq!(messages, ["y is not declared", "again", "absolute"]);
+}
+
+#[test]
+fn absolute_paths_are_compared_resolved() {
+    let compiler = shell("true");
+    // The corpus lies at `harness/../dons_tests`.
+    let file = dons_tests_dir().join("invalid_kxi/scope_out.kxi");
+    let resolved = fs::canonicalize(&file).unwrap();
+    let stderr = format!("{}:3:5: error: y is not declared\n", resolved.display());
+    let diagnostics = compiler.parse_diagnostics(&stderr, &file).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].line, Some(3));
+}
+
+#[test]
+fn custom_pattern_with_phase() {
+    let compiler = shell("true")
+        .diagnostic_pattern(r"^\[(?P<phase>\w+)\] line (?P<line>\d+): (?P<message>.*)$")
+        .unwrap();
+    let diagnostics = compiler
+        .parse_diagnostics(
+            "[resolve] line 6: undeclared y\nsomething else\n",
+            Path::new("scope_out.kxi"),
+        )
+        .unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].phase, Some(Phase::Resolve));
+    assert_eq!(diagnostics[0].line, Some(6));
+    assert_eq!(diagnostics[0].column, None);
+    assert_eq!(diagnostics[0].message, "undeclared y");
+}
+
+#[test]
+fn unparsed_failure_keeps_stderr() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let case = &corpus.cases[0];
+    let outcome = shell("echo 'Segmentation fault?' >&2; exit 3").compile(case);
+    assert_eq!(outcome.diagnostics.len(), 1);
+    assert_eq!(outcome.diagnostics[0].line, None);
+    assert_eq!(outcome.diagnostics[0].message, "Segmentation fault?");
+
+    let outcome = shell("exit 2").compile(case);
+    assert_eq!(outcome.diagnostics[0].message, "exited with status 2");
+}
+
+#[test]
+fn zero_exit_status_accepts() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let outcome = shell("echo 'warning: unused' >&2").compile(&corpus.cases[0]);
+    assert!(outcome.is_accepted());
+}
+
+#[test]
+fn file_placeholder_is_substituted() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let case = corpus.cases.iter().find(|c| c.name == "scope_out").unwrap();
+    let mut compiler = External::new("sh")
+        .arg("-c")
+        .arg(r#"case "$1" in *scope_out.kxi) echo "$1:6:5: undeclared" >&2; exit 1;; esac"#)
+        .arg("kxi")
+        .arg("{file}");
+    assert!(kxi_harness::run_case(case, &mut compiler).passed());
+}
+
+#[test]
+fn crashes_are_reported_as_panics() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let case = &corpus.cases[0];
+    let result = kxi_harness::run_case(case, &mut shell("kill -SEGV $$"));
+    assert!(matches!(&result.failures[..], [Failure::Panicked(msg)] if msg.contains("killed")));
+
+    let mut missing = External::new("/nonexistent/kxi-compiler");
+    let result = kxi_harness::run_case(case, &mut missing);
+    assert!(
+        matches!(&result.failures[..], [Failure::Panicked(msg)] if msg.contains("could not run"))
+    );
+}
+
+#[test]
+fn hanging_compilers_are_stopped() {
+    let scratch = Scratch::new("external_hang");
+    let path = scratch.file(
+        "valid_kxi/slow.kxi",
+        "// expect: timeout=200ms\nvoid main(){}\n",
+    );
+    let case = kxi_harness::TestCase::load(&path).unwrap();
+    let result = kxi_harness::run_case(&case, &mut shell("sleep 5"));
+    assert!(
+        matches!(&result.failures[..], [Failure::Panicked(msg)] if msg.contains("still running")),
+        "{:?}",
+        result.failures
+    );
+}
+
+#[test]
+fn phases_reported_on_stderr_are_checked() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let find = |name: &str| corpus.cases.iter().find(|c| c.name == name).unwrap();
+    let mut compiler = shell(r#"echo "[typecheck] line 6: y is not declared" >&2; exit 1"#)
+        .diagnostic_pattern(r"^\[(?P<phase>\w+)\] line (?P<line>\d+): (?P<message>.*)$")
+        .unwrap();
+
+    let late = kxi_harness::run_case(find("scope_out"), &mut compiler);
+    assert_eq!(
+        late.failures,
+        vec![Failure::WrongPhase {
+            expected: Phase::Resolve,
+            actual: Phase::TypeCheck
+        }]
+    );
+    assert!(late.failures[0]
+        .to_string()
+        .starts_with("rejected too late"));
+
+    // class_scope only has to get through the declare pass, so what type
+    // checking thinks of it does not matter.
+    assert!(kxi_harness::run_case(find("class_scope"), &mut compiler).passed());
+}
+
+/// Runs the corpus against the compiler named by `KXI_COMPILER`, e.g.
+/// `KXI_COMPILER=path/to/kxic cargo test -p kxi_harness --test external`.
+#[test]
+fn configured_compiler_passes_dons_tests() {
+    let Some(compiler) = External::from_env() else {
+        eprintln!("KXI_COMPILER is not set, skipping");
+        return;
+    };
+    let mut compiler = compiler.expect("KXI_DIAGNOSTIC_PATTERN is not a valid regex");
+    let corpus = Corpus::dons_tests().unwrap();
+    kxi_harness::run(&corpus, &mut compiler).assert_all_passed();
+}
+
+#[test]
+fn json_diagnostics_carry_kinds() {
+    let scratch = Scratch::new("external_json");
+    let path = scratch.file(
+        "invalid_kxi/scope_out.kxi",
+        "// expect: phase=resolve errors=1\nvoid main(){\n    y = 4; // error: undeclared-identifier\n}\n",
+    );
+    let case = kxi_harness::TestCase::load(&path).unwrap();
+    let json =
+        r#"{"line":3,"column":5,"phase":"resolve","kind":"undeclared-identifier","message":"y"}"#;
+    let mut compiler =
+        shell(&format!("echo 'checking' >&2; echo '{}' >&2; exit 1", json)).json_diagnostics();
+    let outcome = compiler.compile(&case);
+    assert_eq!(
+        outcome.diagnostics,
+        [Diagnostic::at(3, "y")
+            .with_column(5)
+            .in_phase(Phase::Resolve)
+            .with_kind(Kind::UndeclaredIdentifier)]
+    );
+    assert!(kxi_harness::run_case(&case, &mut compiler).passed());
+
+    let wrong = json.replace("undeclared-identifier", "type-mismatch");
+    let mut compiler = shell(&format!("echo '{}' >&2; exit 1", wrong)).json_diagnostics();
+    assert!(!kxi_harness::run_case(&case, &mut compiler).passed());
+}
+
+#[test]
+fn malformed_json_diagnostics_fail_the_case() {
+    let scratch = Scratch::new("external_bad_json");
+    // Any one error would do for this file.
+    let path = scratch.file(
+        "invalid_kxi/anything.kxi",
+        "// expect: errors=1\nvoid main(){\n    y = 4;\n}\n",
+    );
+    let case = kxi_harness::TestCase::load(&path).unwrap();
+    let mut compiler = shell("echo '{\"line\":3,' >&2; exit 1").json_diagnostics();
+    let result = kxi_harness::run_case(&case, &mut compiler);
+    assert!(
+        matches!(&result.failures[..], [Failure::Panicked(msg)] if msg.starts_with("malformed JSON diagnostic: ")),
+        "{:?}",
+        result.failures
+    );
+}
+
+#[test]
+fn pattern_can_capture_kinds() {
+    let compiler = shell("true")
+        .diagnostic_pattern(r"^(?P<line>\d+): (?P<message>.*) \[(?P<kind>[a-z-]+)\]$")
+        .unwrap();
+    let diagnostics = compiler
+        .parse_diagnostics("4: bad call [bad-arg-count]\n", Path::new("args.kxi"))
+        .unwrap();
+    assert_eq!(diagnostics[0].kind, Some(Kind::BadArgCount));
+    assert_eq!(diagnostics[0].message, "bad call");
+}
@@ -0,0 +1,156 @@
+use kxi_harness::{Corpus, Diagnostic, Expect, Failure, FrontEnd, Phase, Phased};
+
+/// A front end whose passes report exactly the annotated errors, each in the
+/// pass named by the file's header, and record the order they ran in.
+#[derive(Default)]
+struct Scripted {
+    ran: Vec<Phase>,
+    /// Reports every error in this pass instead of the annotated one.
+    report_in: Option<Phase>,
+}
+
+struct Unit {
+    phase: Option<Phase>,
+    errors: Vec<Diagnostic>,
+}
+
+impl Scripted {
+    fn pass(&mut self, unit: &mut Unit, phase: Phase) -> Vec<Diagnostic> {
+        self.ran.push(phase);
+        if unit.phase == Some(phase) {
+            std::mem::take(&mut unit.errors)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl FrontEnd for Scripted {
+    type Unit = Unit;
+
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    fn parse(&mut self, source: &str) -> Result<Unit, Vec<Diagnostic>> {
+        self.ran.push(Phase::Parse);
+        if source.contains("@@") {
+            return Err(Vec::new());
+        }
+        let header = kxi_harness::Header::parse(source).unwrap();
+        let errors = kxi_harness::parse_annotations(source)
+            .unwrap()
+            .into_iter()
+            .map(|a| Diagnostic::at(a.line, a.kind.to_string()))
+            .collect();
+        Ok(Unit {
+            phase: self.report_in.or(header.phase),
+            errors,
+        })
+    }
+
+    fn declare(&mut self, unit: &mut Unit) -> Vec<Diagnostic> {
+        self.pass(unit, Phase::Declare)
+    }
+
+    fn resolve(&mut self, unit: &mut Unit) -> Vec<Diagnostic> {
+        self.pass(unit, Phase::Resolve)
+    }
+
+    fn typecheck(&mut self, unit: &mut Unit) -> Vec<Diagnostic> {
+        self.pass(unit, Phase::TypeCheck)
+    }
+}
+
+#[test]
+fn scripted_front_end_passes_the_corpus() {
+    let corpus = Corpus::dons_tests().unwrap();
+    kxi_harness::run(&corpus, &mut Phased(Scripted::default())).assert_all_passed();
+}
+
+#[test]
+fn diagnostics_are_stamped_with_their_phase() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let report = kxi_harness::run(&corpus, &mut Phased(Scripted::default()));
+    for result in &report.results {
+        let outcome = result.outcome.as_ref().unwrap();
+        for diagnostic in &outcome.diagnostics {
+            assert_eq!(diagnostic.phase, result.case.header.phase);
+        }
+    }
+}
+
+#[test]
+fn stops_after_the_first_failing_pass() {
+    let mut phased = Phased(Scripted::default());
+    let outcome = phased.run_through(
+        "// expect: phase=declare\nclass A { int x; int x; } // error: duplicate-declaration\n",
+        Phase::TypeCheck,
+    );
+    assert_eq!(outcome.diagnostics.len(), 1);
+    assert_eq!(phased.0.ran, [Phase::Parse, Phase::Declare]);
+}
+
+#[test]
+fn stops_after_the_requested_pass() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let case = corpus
+        .cases
+        .iter()
+        .find(|c| c.expect == Expect::Accept)
+        .unwrap();
+    let mut phased = Phased(Scripted::default());
+    let outcome = phased.run_through(&case.source, Phase::Resolve);
+    assert!(outcome.is_accepted());
+    assert_eq!(phased.0.ran, [Phase::Parse, Phase::Declare, Phase::Resolve]);
+}
+
+#[test]
+fn syntax_errors_reject_the_program() {
+    let mut phased = Phased(Scripted::default());
+    let outcome = phased.run_through("void main(){ @@ }", Phase::TypeCheck);
+    assert!(!outcome.is_accepted());
+    assert_eq!(outcome.diagnostics[0].phase, Some(Phase::Parse));
+    assert_eq!(phased.0.ran, [Phase::Parse]);
+}
+
+fn result_for<'a>(report: &'a kxi_harness::Report, name: &str) -> &'a kxi_harness::CaseResult {
+    report.results.iter().find(|r| r.case.name == name).unwrap()
+}
+
+#[test]
+fn errors_caught_too_early_fail() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let mut early = Phased(Scripted {
+        report_in: Some(Phase::Declare),
+        ..Scripted::default()
+    });
+    let report = kxi_harness::run(&corpus, &mut early);
+    let dec = result_for(&report, "bad_dec_type_check");
+    assert_eq!(
+        dec.failures,
+        vec![Failure::WrongPhase {
+            expected: Phase::TypeCheck,
+            actual: Phase::Declare
+        }]
+    );
+    assert!(dec.failures[0]
+        .to_string()
+        .starts_with("rejected too early"));
+    assert!(result_for(&report, "double_dec_data").passed());
+}
+
+#[test]
+fn errors_not_caught_in_time_fail() {
+    let corpus = Corpus::dons_tests().unwrap();
+    let mut late = Phased(Scripted {
+        report_in: Some(Phase::TypeCheck),
+        ..Scripted::default()
+    });
+    let report = kxi_harness::run(&corpus, &mut late);
+    // The resolve pass is the last to run for scope_out, so the error that
+    // would only show up in type checking is never seen.
+    let scope = result_for(&report, "scope_out");
+    assert_eq!(scope.failures, vec![Failure::Accepted]);
+    assert!(result_for(&report, "bad_dec_type_check").passed());
+}
@@ -0,0 +1,103 @@
+use kxi_harness::generate::MAIN_BUDGET;
+use kxi_harness::Generator;
+use kxi_ref::{sema, Interpreter};
+
+const SEEDS: u64 = 200;
+
+#[test]
+fn generated_programs_are_accepted_by_the_reference() {
+    for seed in 0..SEEDS {
+        let generator = Generator::new(seed);
+        if let Err(errors) = sema::analyze(&generator.program()) {
+            let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            panic!(
+                "seed {}:\n{}\n{}",
+                seed,
+                errors.join("\n"),
+                generator.source()
+            );
+        }
+    }
+}
+
+#[test]
+fn generated_programs_run_to_the_end() {
+    for seed in 0..SEEDS {
+        let generator = Generator::new(seed);
+        let program = generator.program();
+        let run = Interpreter::new(&program).max_steps(200_000).run();
+        if let Some(e) = run.error {
+            panic!("seed {}: {}\n{}", seed, e, generator.source());
+        }
+    }
+}
+
+#[test]
+fn generated_programs_stay_within_the_step_budget() {
+    for seed in (0..50).chain([1051]) {
+        let generators = [
+            ("default", Generator::new(seed)),
+            (
+                "depth 5, 7 statements",
+                Generator::new(seed).depth(5).statements(7),
+            ),
+            (
+                "8 members, 6 classes",
+                Generator::new(seed).members(8).classes(6),
+            ),
+            (
+                "depth 4, 6 members, 6 statements",
+                Generator::new(seed).depth(4).members(6).statements(6),
+            ),
+        ];
+        for (name, generator) in generators {
+            let program = generator.program();
+            let run = Interpreter::new(&program).max_steps(MAIN_BUDGET).run();
+            if let Some(e) = run.error {
+                panic!("{}, seed {}: {}\n{}", name, seed, e, generator.source());
+            }
+        }
+    }
+}
+
+#[test]
+fn a_seed_always_gives_the_same_program() {
+    assert_eq!(Generator::new(5).source(), Generator::new(5).source());
+    assert_ne!(Generator::new(5).source(), Generator::new(6).source());
+}
+
+#[test]
+fn generated_programs_use_every_construct() {
+    let sources: String = (0..50).map(|seed| Generator::new(seed).source()).collect();
+    for construct in [
+        "static public",
+        "private",
+        "this.",
+        "switch (",
+        "case 'a':",
+        "while (",
+        "for (",
+        "[][][]",
+        "break;",
+        "else {",
+        "cout << ",
+    ] {
+        assert!(sources.contains(construct), "no `{}`", construct);
+    }
+    let constructor = regex::Regex::new(r"(?m)^    C\d+\(").unwrap();
+    assert!(constructor.is_match(&sources), "no constructor");
+    let chain = regex::Regex::new(r"C\d+\.m\d+\([^;]*\)\.[fm]\d+").unwrap();
+    assert!(chain.is_match(&sources), "no call chain");
+}
+
+#[test]
+fn limits_keep_programs_small() {
+    let source = Generator::new(1)
+        .classes(1)
+        .members(1)
+        .statements(1)
+        .depth(0)
+        .source();
+    assert_eq!(source.matches("class ").count(), 1);
+    assert!(source.lines().count() < 20, "{}", source);
+}
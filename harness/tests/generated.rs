@@ -0,0 +1,18 @@
+mod common;
+
+use common::Oracle;
+
+kxi_harness::corpus_tests!(Oracle);
+
+#[test]
+fn rendered_sem_tests_cover_the_corpus() {
+    let corpus = kxi_harness::Corpus::dons_tests().unwrap();
+    let rendered = kxi_harness::sem_tests::render(&corpus);
+    assert_eq!(rendered.matches("#[test]").count(), corpus.len());
+    assert!(rendered.contains("fn bad_dec_type_check() {"));
+    assert!(rendered.contains("assert_eq!(st.errs.len(), 4);"));
+    assert!(rendered.contains("let mut st = SymbolInner::new(table, scope);"));
+    assert!(!rendered.contains("// error:"));
+    assert!(!rendered.contains("// expect:"));
+    assert!(rendered.contains(r#"r#"class MyClass { "#));
+}
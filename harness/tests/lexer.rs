@@ -0,0 +1,27 @@
+use kxi_harness::golden::{self, assert_goldens, check_goldens};
+
+#[test]
+fn reference_lexer_matches_golden_tokens() {
+    assert_goldens(golden::dir("lexer_kxi"), "tokens", kxi_ref::lexer::dump);
+}
+
+#[test]
+fn every_program_has_a_golden_file() {
+    for path in golden::programs(&golden::dir("lexer_kxi")).unwrap() {
+        assert!(
+            path.with_extension("tokens").exists(),
+            "{} has no .tokens file",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn a_wrong_lexer_is_caught_on_the_first_differing_line() {
+    let mismatches = check_goldens(&golden::dir("lexer_kxi"), "tokens", |source| {
+        kxi_ref::lexer::dump(source).replace(" keyword ", " ident ")
+    })
+    .unwrap();
+    assert!(!mismatches.is_empty());
+    assert!(mismatches.iter().all(|m| m.actual.contains(" ident ")));
+}
@@ -0,0 +1,93 @@
+use kxi_harness::{mutants, reference, run_case, Corpus, Expect, Generator, Mutant, Mutation};
+
+fn valid_programs() -> Vec<kxi_ref::ast::Program> {
+    let corpus = Corpus::dons_tests().unwrap();
+    let valid = corpus
+        .cases
+        .iter()
+        .filter(|c| c.expect == Expect::Accept)
+        .map(|c| kxi_ref::parse(&c.source).unwrap())
+        // Some are only valid up to the pass their header names.
+        .filter(|p| kxi_ref::sema::analyze(p).is_ok());
+    let generated = (0..5).map(|seed| Generator::new(seed).program());
+    valid.chain(generated).collect()
+}
+
+fn assert_reference_agrees(mutant: &Mutant) {
+    let case = mutant.test_case("mutant", &[]);
+    let result = run_case(&case, &mut reference());
+    if !result.passed() {
+        let failures: Vec<String> = result.failures.iter().map(|f| f.to_string()).collect();
+        panic!("{}\n{}", failures.join("\n"), case.source);
+    }
+}
+
+#[test]
+fn the_reference_reports_what_mutants_predict() {
+    for program in valid_programs() {
+        for mutant in mutants(&program) {
+            assert_reference_agrees(&mutant);
+        }
+    }
+}
+
+#[test]
+fn every_mutation_applies_to_the_corpus() {
+    let mut found: Vec<Mutation> = valid_programs()
+        .iter()
+        .flat_map(mutants)
+        .map(|m| m.mutation)
+        .collect();
+    found.sort();
+    found.dedup();
+    assert_eq!(found, Mutation::ALL);
+}
+
+#[test]
+fn mutants_are_annotated_where_the_change_is() {
+    let program = kxi_ref::parse(
+        "class A {\n\
+         \x20   public int x = 1;\n\
+         \x20   static public int n;\n\
+         \x20   static public int get() {\n\
+         \x20       return n;\n\
+         \x20   }\n\
+         }\n\
+         void main() {\n\
+         \x20   A a = new A();\n\
+         \x20   if (true) {\n\
+         \x20       int y = a.x;\n\
+         \x20       cout << y;\n\
+         \x20   }\n\
+         }\n",
+    )
+    .unwrap();
+    let sources: Vec<String> = mutants(&program)
+        .iter()
+        .map(|m| m.source(&["mutants".to_string()]))
+        .collect();
+    for expected in [
+        "// expect: phase=typecheck errors=1 tags=mutants\n\
+         // swap-literal: 1 became \"1\"\n\
+         class A {\n\
+         \x20   public int x = \"1\"; // error: type-mismatch\n",
+        "// expect: phase=declare errors=1 tags=mutants\n\
+         // duplicate-declaration: `A.x` declared twice\n\
+         class A {\n\
+         \x20   public int x = 1;\n\
+         \x20   public int x = 1; // error: duplicate-declaration\n",
+        "// move-out-of-block: `cout << y;` moved out of its block\n",
+        "\x20       int y = a.x;\n\
+         \x20   }\n\
+         \x20   cout << y; // error: undeclared-identifier\n\
+         }\n",
+        "\x20       int y = A.x; // error: static-context\n",
+        "\x20       return this.n; // error: invalid-this\n",
+    ] {
+        assert!(
+            sources.iter().any(|s| s.contains(expected)),
+            "no mutant contains\n{}",
+            expected
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use kxi_harness::golden::{self, assert_goldens};
+use kxi_harness::Corpus;
+
+#[test]
+fn reference_parser_matches_golden_trees() {
+    assert_goldens(golden::dir("valid_parse"), "ast", kxi_ref::parser::dump);
+}
+
+#[test]
+fn reference_parser_rejects_at_the_golden_positions() {
+    assert_goldens(golden::dir("invalid_parse"), "ast", kxi_ref::parser::dump);
+}
+
+#[test]
+fn golden_trees_and_errors_are_in_the_right_directory() {
+    for (dir, expect_error) in [("valid_parse", false), ("invalid_parse", true)] {
+        for path in golden::programs(&golden::dir(dir)).unwrap() {
+            let golden = std::fs::read_to_string(path.with_extension("ast")).unwrap();
+            let is_error = !golden.starts_with('(');
+            assert_eq!(is_error, expect_error, "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn every_program_in_dons_tests_parses() {
+    for case in Corpus::dons_tests().unwrap().cases {
+        if let Err(e) = kxi_ref::parse(&case.source) {
+            panic!("{}: {}", case.id(), e);
+        }
+    }
+}
+
+#[test]
+fn printed_programs_parse_back_to_the_same_tree() {
+    let mut sources: Vec<(String, String)> = Corpus::dons_tests()
+        .unwrap()
+        .cases
+        .into_iter()
+        .map(|case| (case.id(), case.source))
+        .collect();
+    for path in golden::programs(&golden::dir("valid_parse")).unwrap() {
+        let source = std::fs::read_to_string(&path).unwrap();
+        sources.push((path.display().to_string(), source));
+    }
+    for (id, source) in sources {
+        let program = kxi_ref::parse(&source).unwrap();
+        let printed = kxi_ref::print::program(&program);
+        let reparsed =
+            kxi_ref::parse(&printed).unwrap_or_else(|e| panic!("{}: {}\n{}", id, e, printed));
+        assert_eq!(
+            kxi_ref::sexp::program(&reparsed),
+            kxi_ref::sexp::program(&program),
+            "{}",
+            id
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use kxi_harness::expect::{parse_annotations, Header};
+use kxi_harness::{
+    check, reduce, reference, reference_case, run_case, Compiler, Expect, Failure, Generator, Kind,
+    Outcome, Signature, TestCase,
+};
+
+/// Crashes on any program that takes a remainder.
+struct Crashes;
+
+impl Compiler for Crashes {
+    fn name(&self) -> &str {
+        "crashes"
+    }
+
+    fn compile(&mut self, case: &TestCase) -> Outcome {
+        if case.source.contains('%') {
+            panic!("cannot take a remainder");
+        }
+        Outcome::accepted()
+    }
+}
+
+/// Accepts every program.
+struct Accepts;
+
+impl Compiler for Accepts {
+    fn name(&self) -> &str {
+        "accepts"
+    }
+
+    fn compile(&mut self, _: &TestCase) -> Outcome {
+        Outcome::accepted()
+    }
+}
+
+#[test]
+fn reduces_a_crash_to_the_expression_causing_it() {
+    let generator = Generator::new(3);
+    assert!(generator.source().contains('%'));
+    let reduced = reduce(&generator.program(), |source| source.contains('%'));
+    let reduced = kxi_ref::print::program(&reduced);
+    assert!(reduced.lines().count() <= 4, "{}", reduced);
+    assert!(reduced.contains('%'), "{}", reduced);
+}
+
+#[test]
+fn reduces_through_a_compiler() {
+    let generator = Generator::new(8);
+    assert!(generator.source().contains('%'));
+    let program = generator.program();
+    let reduced = reduce(&program, |source| {
+        let result = check(&mut Crashes, "crash", source);
+        let panicked = |f: &Failure| matches!(f, Failure::Panicked(_));
+        result.failures.iter().any(panicked)
+    });
+    let reduced = kxi_ref::print::program(&reduced);
+    assert!(reduced.lines().count() <= 4, "{}", reduced);
+    assert!(reduced.contains('%'), "{}", reduced);
+}
+
+#[test]
+fn reduces_a_missed_error_to_an_invalid_program() {
+    let source = "class A {\n    public int x;\n}\n\nvoid main() {\n    A a = new A();\n    int y = a.x;\n    a.x = y + 'c';\n    cout << y;\n}\n";
+    let first = Signature::of(&check(&mut Accepts, "missed", source));
+    let reduced = reduce(&kxi_ref::parse(source).unwrap(), |source| {
+        Signature::of(&check(&mut Accepts, "missed", source)) == first
+    });
+    let reduced = kxi_ref::print::program(&reduced);
+    let case = reference_case("missed", &reduced);
+    assert_eq!(case.expect, Expect::Reject);
+    // Not the undeclared `y` left once its declaration is gone.
+    assert!(!case.annotations.is_empty(), "{}", case.source);
+    assert!(
+        case.annotations
+            .iter()
+            .all(|a| a.kind == Kind::TypeMismatch),
+        "{}",
+        case.source
+    );
+    assert!(reduced.contains("y + 'c'"), "{}", reduced);
+    assert!(
+        reduced.lines().count() < source.lines().count(),
+        "{}",
+        reduced
+    );
+}
+
+#[test]
+fn returns_programs_that_do_not_fail_as_they_are() {
+    let program = Generator::new(1).program();
+    let mut tests = 0;
+    let reduced = reduce(&program, |_| {
+        tests += 1;
+        false
+    });
+    assert_eq!(reduced, program);
+    assert_eq!(tests, 1);
+}
+
+#[test]
+fn reference_cases_carry_the_reference_errors() {
+    let valid = reference_case("ok", "void main() {\n    cout << 1;\n}\n");
+    assert_eq!(valid.dir, "valid_kxi");
+    assert_eq!(valid.source, "void main() {\n    cout << 1;\n}\n");
+
+    let invalid = reference_case("bad", "void main() {\n    int x = 'c';\n    y = 2;\n}\n");
+    assert_eq!(invalid.dir, "invalid_kxi");
+    assert_eq!(
+        invalid.source,
+        "// expect: phase=resolve errors=1\nvoid main() {\n    int x = 'c';\n    y = 2; // error: undeclared-identifier\n}\n"
+    );
+    assert_eq!(Header::parse(&invalid.source).unwrap(), invalid.header);
+    assert_eq!(
+        parse_annotations(&invalid.source).unwrap(),
+        invalid.annotations
+    );
+    for case in [valid, invalid] {
+        assert!(
+            run_case(&case, &mut reference()).passed(),
+            "{}",
+            case.source
+        );
+    }
+}
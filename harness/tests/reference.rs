@@ -0,0 +1,50 @@
+use std::collections::BTreeMap;
+
+use kxi_harness::{reference, Corpus, Expect, Phase};
+use kxi_ref::sema::{self, SemanticError};
+
+kxi_harness::corpus_tests!(reference());
+
+/// Runs the passes up to `last`, stopping at the first one with errors, the
+/// way `Phased` does.
+fn errors_through(source: &str, last: Phase) -> Vec<SemanticError> {
+    let program = kxi_ref::parse(source).unwrap();
+    let (table, errors) = sema::declare(&program);
+    if !errors.is_empty() || last == Phase::Declare {
+        return errors;
+    }
+    let errors = sema::resolve(&program, &table);
+    if !errors.is_empty() || last == Phase::Resolve {
+        return errors;
+    }
+    sema::typecheck(&program, &table)
+}
+
+#[test]
+fn reference_reports_the_annotated_kinds() {
+    for case in Corpus::dons_tests().unwrap().cases {
+        if case.expect == Expect::Accept {
+            continue;
+        }
+        let last = case.header.phase.unwrap_or(Phase::TypeCheck);
+        let mut expected: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for a in &case.annotations {
+            expected.entry(a.line).or_default().push(a.kind.to_string());
+        }
+        let mut actual: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for e in errors_through(&case.source, last) {
+            actual.entry(e.line).or_default().push(e.kind.to_string());
+        }
+        for kinds in expected.values_mut().chain(actual.values_mut()) {
+            kinds.sort();
+        }
+        assert_eq!(actual, expected, "{}", case.id());
+    }
+}
+
+#[test]
+fn reference_reports_each_root_error_once() {
+    let corpus = Corpus::cascade().unwrap();
+    assert!(corpus.len() >= 10);
+    kxi_harness::run(&corpus, &mut reference()).assert_all_passed();
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::{Oracle, Scratch};
+use kxi_harness::reporter::{self, Format};
+use kxi_harness::{Compiler, Corpus, Diagnostic, Outcome, TestCase};
+
+/// Reports a type mismatch on line 3 of every program.
+struct LineThree;
+
+impl Compiler for LineThree {
+    fn name(&self) -> &str {
+        "line-three"
+    }
+
+    fn compile(&mut self, _case: &TestCase) -> Outcome {
+        Outcome::rejected(vec![Diagnostic::at(3, "int & bool don't mix")])
+    }
+}
+
+fn corpus(scratch: &Scratch) -> Corpus {
+    scratch.file("valid_kxi/ok.kxi", "void main(){}\n");
+    scratch.file(
+        "invalid_kxi/bad.kxi",
+        "// expect: phase=typecheck errors=1\nvoid main(){\n    int x;\n    x = true; // error: type-mismatch\n}\n",
+    );
+    Corpus::discover(scratch.root()).unwrap()
+}
+
+#[test]
+fn junit_has_a_testcase_per_file() {
+    let scratch = Scratch::new("junit");
+    let report = kxi_harness::run(&corpus(&scratch), &mut LineThree);
+    let xml = reporter::render(&report, Format::Junit);
+    assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+    assert!(xml.contains("<testsuite name=\"line-three\" tests=\"2\" failures=\"2\" errors=\"0\">"));
+    assert_eq!(xml.matches("<testcase ").count(), 2);
+    assert!(xml.contains("classname=\"invalid_kxi\" name=\"bad\""));
+    let failure = xml
+        .split("<failure message=\"missing type-mismatch error on line 4\">")
+        .nth(1)
+        .expect("a failure for bad.kxi")
+        .split("</failure>")
+        .next()
+        .unwrap();
+    assert!(failure.contains("expected:\n  4: type-mismatch\n"));
+    assert!(failure.contains("reported:\n  3: int &amp; bool don&apos;t mix\n"));
+    assert!(xml.contains("<failure message=\"valid program was rejected\">"));
+
+    let report = kxi_harness::run(&corpus(&scratch), &mut Oracle);
+    let xml = reporter::junit(&report);
+    assert!(xml.contains("name=\"ok\" file="));
+    assert!(!xml.contains("<failure"));
+}
+
+#[test]
+fn tap_has_a_test_point_per_file() {
+    let scratch = Scratch::new("tap");
+    let report = kxi_harness::run(&corpus(&scratch), &mut LineThree);
+    let tap = reporter::render(&report, Format::Tap);
+    let lines: Vec<&str> = tap.lines().collect();
+    assert_eq!(
+        lines[..3],
+        ["TAP version 13", "1..2", "not ok 1 - invalid_kxi/bad.kxi"]
+    );
+    assert!(lines.contains(&"#   4: type-mismatch"));
+    assert!(lines.contains(&"not ok 2 - valid_kxi/ok.kxi"));
+    assert!(lines.iter().all(|l| l.starts_with('#')
+        || l.starts_with("ok ")
+        || l.starts_with("not ok ")
+        || l.starts_with("TAP ")
+        || l.starts_with("1..")));
+
+    let report = kxi_harness::run(&corpus(&scratch), &mut Oracle);
+    assert_eq!(
+        reporter::tap(&report),
+        "TAP version 13\n1..2\nok 1 - invalid_kxi/bad.kxi\nok 2 - valid_kxi/ok.kxi\n"
+    );
+}
+
+#[test]
+fn html_highlights_expected_missed_and_unexpected_lines() {
+    let scratch = Scratch::new("html");
+    let report = kxi_harness::run(&corpus(&scratch), &mut LineThree);
+    let html = reporter::render(&report, Format::Html);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(!html.contains("<script") && !html.contains("<link"));
+    assert!(html.contains("<p>0 passed, 2 failed, 1 missed errors, 0 cascading errors</p>"));
+    assert!(html
+        .contains("<summary>invalid_kxi/bad.kxi <span class=\"phase\">typecheck</span></summary>"));
+    assert!(html.contains("<span class=\"unexpected\"><i>3</i>    int x;</span>"));
+    assert!(html
+        .contains("<span class=\"missed\"><i>4</i>    x = true; // error: type-mismatch</span>"));
+    assert!(html.contains("<td>4: type-mismatch</td><td>3: int &amp; bool don&apos;t mix</td>"));
+
+    let report = kxi_harness::run(&corpus(&scratch), &mut Oracle);
+    let html = reporter::html(&report);
+    assert!(html
+        .contains("<span class=\"expected\"><i>4</i>    x = true; // error: type-mismatch</span>"));
+    assert!(!html.contains("class=\"missed\"") && !html.contains("class=\"unexpected\""));
+    assert!(!html.contains(" open>"));
+}
+
+#[test]
+fn text_is_the_plain_report() {
+    let scratch = Scratch::new("text");
+    let report = kxi_harness::run(&corpus(&scratch), &mut Oracle);
+    assert_eq!(reporter::render(&report, Format::Text), report.to_string());
+}
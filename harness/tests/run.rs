@@ -0,0 +1,221 @@
+mod common;
+
+use std::fs;
+
+use common::Scratch;
+use kxi_harness::golden::{self, programs};
+use kxi_harness::{
+    assert_runs, run_program, run_programs, Execution, External, Reference, RunCase, RunFailure,
+    RuntimeError, Status,
+};
+
+#[test]
+fn reference_runs_run_kxi() {
+    assert_runs(golden::dir("run_kxi"), &mut Reference);
+}
+
+#[test]
+fn reference_traps_runtime_errors() {
+    assert_runs(golden::dir("runtime_error_kxi"), &mut Reference);
+}
+
+#[test]
+fn every_program_has_expected_output() {
+    for path in programs(&golden::dir("run_kxi")).unwrap() {
+        RunCase::load(&path).unwrap_or_else(|e| panic!("{}", e));
+    }
+    for path in programs(&golden::dir("runtime_error_kxi")).unwrap() {
+        let case = RunCase::load(&path).unwrap_or_else(|e| panic!("{}", e));
+        assert!(
+            case.runtime_error.is_some(),
+            "{} has no runtime-error",
+            case.id()
+        );
+    }
+}
+
+/// A "compiler" that writes `script` as the compiled program.
+fn emits(script: &str) -> External {
+    let compile = format!(
+        "printf '#!/bin/sh\\n%s\\n' '{}' > \"$1\" && chmod +x \"$1\"",
+        script
+    );
+    External::new("sh").args(["-c", &compile, "{file}", "{out}"])
+}
+
+fn program(scratch: &Scratch, header: &str, stdout: &str, exit_code: &str) -> RunCase {
+    let path = scratch.file("run_kxi/p.kxi", &format!("{}void main(){{}}\n", header));
+    fs::write(path.with_extension("stdout"), stdout).unwrap();
+    fs::write(path.with_extension("exitcode"), exit_code).unwrap();
+    RunCase::load(&path).unwrap()
+}
+
+#[test]
+fn external_compiles_then_runs_the_output() {
+    let scratch = Scratch::new("run_external");
+    let case = program(&scratch, "", "hi\n", "3\n");
+    fs::write(case.path.with_extension("stdin"), "hi").unwrap();
+    let case = RunCase::load(&case.path).unwrap();
+    let result = run_program(&case, &mut emits("cat; echo; exit 3"));
+    assert!(result.passed(), "{:?}", result.failures);
+
+    let result = run_program(&case, &mut emits("echo ho"));
+    assert_eq!(
+        result.failures,
+        [
+            RunFailure::Stdout {
+                line: 1,
+                expected: "hi".into(),
+                actual: "ho".into()
+            },
+            RunFailure::ExitCode {
+                expected: 3,
+                actual: 0
+            },
+        ]
+    );
+}
+
+#[test]
+fn the_final_newline_is_compared() {
+    let scratch = Scratch::new("run_newline");
+    let case = program(&scratch, "", "a\nhi\n", "0\n");
+    let result = run_program(&case, &mut emits("echo a; printf hi"));
+    assert_eq!(
+        result.failures,
+        [RunFailure::Stdout {
+            line: 2,
+            expected: "hi\\n".into(),
+            actual: "hi".into()
+        }]
+    );
+    let result = run_program(&case, &mut emits("printf \"a\\r\\nhi\\r\\n\""));
+    assert!(result.passed(), "{:?}", result.failures);
+}
+
+#[test]
+fn output_can_be_run_by_another_command() {
+    let scratch = Scratch::new("run_with");
+    let case = program(&scratch, "", "void main(){}\n", "0");
+    let mut copy = External::new("cp")
+        .args(["{file}", "{out}.txt"])
+        .runs_with(["cat", "{out}.txt"]);
+    assert!(run_program(&case, &mut copy).passed());
+}
+
+#[test]
+fn header_timeout_stops_the_program() {
+    let scratch = Scratch::new("run_timeout");
+    let case = program(&scratch, "// expect: timeout=100ms\n", "", "0");
+    let result = run_program(&case, &mut emits("sleep 5"));
+    assert_eq!(
+        result.failures,
+        [RunFailure::TimedOut(std::time::Duration::from_millis(100))]
+    );
+    let Some(Execution::Ran(run)) = result.execution else {
+        panic!("not run: {:?}", result.execution);
+    };
+    assert_eq!(run.status, Status::TimedOut);
+
+    // What it printed before being stopped is not compared.
+    let case = program(&scratch, "// expect: timeout=100ms\n", "1\n2\n", "0");
+    let result = run_program(&case, &mut emits("echo 1; sleep 5"));
+    assert_eq!(
+        result.failures,
+        [RunFailure::TimedOut(std::time::Duration::from_millis(100))]
+    );
+}
+
+#[test]
+fn output_held_open_by_a_leftover_process_times_out() {
+    let scratch = Scratch::new("run_leftover");
+    let case = program(&scratch, "// expect: timeout=200ms\n", "1\n", "0");
+    let start = std::time::Instant::now();
+    let result = run_program(&case, &mut emits("sleep 5 & echo 1"));
+    assert_eq!(
+        result.failures,
+        [RunFailure::TimedOut(std::time::Duration::from_millis(200))]
+    );
+    assert!(start.elapsed() < std::time::Duration::from_secs(4));
+}
+
+#[test]
+fn rejected_programs_fail() {
+    let scratch = Scratch::new("run_rejected");
+    let path = scratch.file("run_kxi/bad.kxi", "void main(){\n    x = 1;\n}\n");
+    fs::write(path.with_extension("stdout"), "").unwrap();
+    fs::write(path.with_extension("exitcode"), "0").unwrap();
+    let report = run_programs(&scratch.root().join("run_kxi"), &mut Reference).unwrap();
+    assert_eq!(report.failed(), 1);
+    assert_eq!(report.results[0].failures, [RunFailure::Rejected]);
+    assert!(report.to_string().contains("reported 2: "));
+}
+
+#[test]
+fn reference_stops_runaway_programs() {
+    let scratch = Scratch::new("run_runaway");
+    let source = fs::read_to_string(
+        kxi_harness::corpus::dons_tests_dir().join("valid_kxi/loop_type_check.kxi"),
+    )
+    .unwrap();
+    let path = scratch.file(
+        "run_kxi/loop.kxi",
+        &source.replace("// expect: phase=typecheck", "// expect: timeout=50ms"),
+    );
+    fs::write(path.with_extension("stdout"), "").unwrap();
+    fs::write(path.with_extension("exitcode"), "0").unwrap();
+    let result = run_program(&RunCase::load(&path).unwrap(), &mut Reference);
+    assert_eq!(
+        result.failures,
+        [RunFailure::TimedOut(std::time::Duration::from_millis(50))]
+    );
+}
+
+#[test]
+fn runtime_errors_must_be_trapped_and_named() {
+    let scratch = Scratch::new("run_trap");
+    let case = program(
+        &scratch,
+        "// expect: runtime-error=division-by-zero\n",
+        "1\n",
+        "",
+    );
+    let trapped = "echo 1; echo \"error: division-by-zero\" >&2; exit 134";
+    assert!(run_program(&case, &mut emits(trapped)).passed());
+
+    let result = run_program(&case, &mut emits("echo 1; echo 0"));
+    assert_eq!(
+        result.failures,
+        [
+            RunFailure::Stdout {
+                line: 2,
+                expected: "<end of file>".into(),
+                actual: "0".into()
+            },
+            RunFailure::NoRuntimeError {
+                expected: RuntimeError::DivisionByZero
+            },
+        ]
+    );
+
+    let result = run_program(&case, &mut emits("echo 1; exit 1"));
+    assert_eq!(
+        result.failures,
+        [RunFailure::WrongRuntimeError {
+            expected: RuntimeError::DivisionByZero,
+            actual: None,
+            status: 1
+        }]
+    );
+
+    let wrong = "echo 1; echo \"error: index-out-of-bounds\" >&2; exit 134";
+    let result = run_program(&case, &mut emits(wrong));
+    assert_eq!(
+        result.failures,
+        [RunFailure::WrongRuntimeError {
+            expected: RuntimeError::DivisionByZero,
+            actual: Some(RuntimeError::IndexOutOfBounds),
+            status: 134
+        }]
+    );
+}
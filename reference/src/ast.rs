@@ -0,0 +1,306 @@
+//! Syntax tree produced by the [`parser`](crate::parser).
+//!
+//! Every node that can be the subject of an error keeps the line it starts
+//! on, since that is what the corpus annotations point at.
+
+use std::fmt;
+
+/// A whole compilation unit: the classes, then the `void main()` function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub classes: Vec<ClassDef>,
+    pub main: Main,
+}
+
+/// The function closing every program. Its name is not checked by the
+/// parser, a program ending in `void fred(){}` parses fine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Main {
+    pub name: String,
+    pub line: usize,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassDef {
+    pub name: String,
+    pub line: usize,
+    pub members: Vec<Member>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Member {
+    Field(Field),
+    Method(Method),
+    Constructor(Constructor),
+}
+
+impl Member {
+    pub fn name(&self) -> &str {
+        match self {
+            Member::Field(f) => &f.name,
+            Member::Method(m) => &m.name,
+            Member::Constructor(c) => &c.name,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        match self {
+            Member::Field(f) => f.line,
+            Member::Method(m) => m.line,
+            Member::Constructor(c) => c.line,
+        }
+    }
+}
+
+/// `[static] (public|private)` in front of a field or method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers {
+    pub is_static: bool,
+    pub visibility: Visibility,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    Private,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub modifiers: Modifiers,
+    pub ty: Type,
+    pub name: String,
+    pub init: Option<Expr>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Method {
+    pub modifiers: Modifiers,
+    pub ret: Type,
+    pub name: String,
+    pub params: Vec<Param>,
+    pub body: Vec<Stmt>,
+    pub line: usize,
+}
+
+/// A member written `Name(params) { ... }`. Whether `Name` is the class's
+/// name is for the semantic passes to decide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constructor {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub body: Vec<Stmt>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub ty: Type,
+    pub name: String,
+    pub line: usize,
+}
+
+/// A type as written in the source.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    Void,
+    Int,
+    Char,
+    Bool,
+    String,
+    Class(String),
+    Array(Box<Type>),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Void => f.write_str("void"),
+            Type::Int => f.write_str("int"),
+            Type::Char => f.write_str("char"),
+            Type::Bool => f.write_str("bool"),
+            Type::String => f.write_str("string"),
+            Type::Class(name) => f.write_str(name),
+            Type::Array(elem) => write!(f, "{}[]", elem),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stmt {
+    pub line: usize,
+    pub kind: StmtKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StmtKind {
+    Block(Vec<Stmt>),
+    Var {
+        ty: Type,
+        name: String,
+        init: Option<Expr>,
+    },
+    Expr(Expr),
+    If {
+        cond: Expr,
+        then: Box<Stmt>,
+        otherwise: Option<Box<Stmt>>,
+    },
+    While {
+        cond: Expr,
+        body: Box<Stmt>,
+    },
+    /// Any of the three clauses may be left out.
+    For {
+        init: Option<Expr>,
+        cond: Option<Expr>,
+        step: Option<Expr>,
+        body: Box<Stmt>,
+    },
+    Return(Option<Expr>),
+    /// `cin >> target;`
+    Cin(Expr),
+    /// `cout << value;`
+    Cout(Expr),
+    Switch {
+        scrutinee: Expr,
+        cases: Vec<Case>,
+        default: Option<Vec<Stmt>>,
+    },
+    Break,
+}
+
+/// `case label: body`. Control falls through into the next case unless the
+/// body breaks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    pub line: usize,
+    pub label: Literal,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr {
+    /// Position of the operator for operations, of the first token for
+    /// everything else.
+    pub line: usize,
+    pub column: usize,
+    pub kind: ExprKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprKind {
+    Literal(Literal),
+    Ident(String),
+    This,
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// The target is an identifier, a member or an index.
+    Assign(Box<Expr>, Box<Expr>),
+    /// `object.name`
+    Member(Box<Expr>, String),
+    /// The callee is an identifier or a member.
+    Call(Box<Expr>, Vec<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+    /// `new Class(args)`
+    New(String, Vec<Expr>),
+    /// `new elem[size]`, where `elem` may itself be an array type:
+    /// `new int[][][10]` has `int[][]` elements.
+    NewArray(Type, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Literal {
+    Int(i32),
+    Char(char),
+    Str(String),
+    Bool(bool),
+    Null,
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Int(n) => write!(f, "{}", n),
+            Literal::Char(c) => write!(f, "'{}'", escape(*c, '\'')),
+            Literal::Str(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    f.write_str(&escape(c, '"'))?;
+                }
+                f.write_str("\"")
+            }
+            Literal::Bool(b) => write!(f, "{}", b),
+            Literal::Null => f.write_str("null"),
+        }
+    }
+}
+
+/// Writes `c` the way it would appear inside a literal quoted by `quote`.
+fn escape(c: char, quote: char) -> String {
+    match c {
+        '\n' => "\\n".into(),
+        '\t' => "\\t".into(),
+        '\r' => "\\r".into(),
+        '\0' => "\\0".into(),
+        '\\' => "\\\\".into(),
+        c if c == quote => format!("\\{}", c),
+        c => c.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Plus,
+    Not,
+}
+
+impl UnaryOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UnaryOp::Neg => "-",
+            UnaryOp::Plus => "+",
+            UnaryOp::Not => "!",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::NotEq => "!=",
+            BinaryOp::Less => "<",
+            BinaryOp::LessEq => "<=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterEq => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        }
+    }
+}
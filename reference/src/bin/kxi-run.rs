@@ -0,0 +1,53 @@
+//! Checks a KXI program with the reference passes and runs it.
+//!
+//! Usage: `kxi-run FILE`. The program reads `cin` from stdin and writes
+//! `cout` to stdout. Diagnostics and runtime errors go to stderr as
+//! `FILE:LINE: message`. Exits with the program's status, 0 or 1 after a
+//! runtime error, or with 2 if the program is rejected or cannot be read.
+
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use kxi_ref::{sema, Interpreter};
+
+fn main() -> ExitCode {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("usage: kxi-run FILE");
+        return ExitCode::from(2);
+    };
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("kxi-run: {}: {}", path, e);
+            return ExitCode::from(2);
+        }
+    };
+    let program = match kxi_ref::parse(&source) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("{}:{}", path, e);
+            return ExitCode::from(2);
+        }
+    };
+    if let Err(errors) = sema::analyze(&program) {
+        for e in errors {
+            eprintln!("{}:{}", path, e);
+        }
+        return ExitCode::from(2);
+    }
+    let mut input = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut input) {
+        eprintln!("kxi-run: stdin: {}", e);
+        return ExitCode::from(2);
+    }
+    let run = Interpreter::new(&program).stdin(input).run();
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(run.stdout.as_bytes());
+    let _ = stdout.flush();
+    if let Some(e) = &run.error {
+        eprintln!("{}:{}", path, e);
+    }
+    ExitCode::from(run.exit_code() as u8)
+}
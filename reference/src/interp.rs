@@ -0,0 +1,1058 @@
+//! Tree-walking interpreter for programs that got through [`sema`].
+//!
+//! The program's behaviour, where `sem_tests.rs` says nothing:
+//!
+//! * `int` is a 32-bit integer that wraps on overflow. `/` and `%` round
+//!   towards zero and trap on a zero divisor.
+//! * Fields, array elements and variables without an initializer start out
+//!   as `0`, `'\0'`, `false`, `""` or `null`. `new int[][][10]` is ten
+//!   `null` arrays of arrays.
+//! * `cin >> x` skips whitespace, then reads one character into a `char` or
+//!   an optionally signed decimal number into an `int`.
+//! * `cout << x` writes the value with no separator or newline.
+//! * `switch` jumps to the matching case, or `default`, and falls through
+//!   into the cases below it until a `break`.
+//! * Static fields are initialized in declaration order before `main`
+//!   runs. `new C(args)` runs `C`'s field initializers, then its
+//!   constructor.
+//! * Using `null` as an object or array, indexing outside an array and
+//!   dividing by zero stop the program with a [`RuntimeError`].
+//!
+//! [`sema`]: crate::sema
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::ast::*;
+
+/// A value at run time.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Void,
+    Int(i32),
+    Char(char),
+    Bool(bool),
+    Str(Rc<str>),
+    Null,
+    Object(Rc<RefCell<Object>>),
+    Array(Rc<RefCell<Vec<Value>>>),
+}
+
+#[derive(Debug)]
+pub struct Object {
+    pub class: String,
+    pub fields: HashMap<String, Value>,
+}
+
+impl Value {
+    /// The value of a variable of type `ty` before anything is stored in it.
+    pub fn default_of(ty: &Type) -> Value {
+        match ty {
+            Type::Int => Value::Int(0),
+            Type::Char => Value::Char('\0'),
+            Type::Bool => Value::Bool(false),
+            Type::String => Value::Str("".into()),
+            Type::Void => Value::Void,
+            Type::Class(_) | Type::Array(_) => Value::Null,
+        }
+    }
+
+    fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Char(a), Value::Char(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Null, Value::Null) => true,
+            (Value::Object(a), Value::Object(b)) => Rc::ptr_eq(a, b),
+            (Value::Array(a), Value::Array(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Void => Ok(()),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Char(c) => write!(f, "{}", c),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Str(s) => f.write_str(s),
+            Value::Null => f.write_str("null"),
+            Value::Object(o) => write!(f, "<{}>", o.borrow().class),
+            Value::Array(a) => write!(f, "<array of {}>", a.borrow().len()),
+        }
+    }
+}
+
+/// Why a program stopped before the end of `main`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RuntimeErrorKind {
+    NullDereference,
+    IndexOutOfBounds,
+    DivisionByZero,
+    NegativeArraySize,
+    /// `cin` found no input, or input of the wrong form.
+    InvalidInput,
+    /// Calls nested deeper than the interpreter allows.
+    StackOverflow,
+    /// The program ran longer than the step limit allows.
+    StepLimit,
+    /// The program ran longer than the time limit allows.
+    TimeLimit,
+}
+
+impl RuntimeErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RuntimeErrorKind::NullDereference => "null-dereference",
+            RuntimeErrorKind::IndexOutOfBounds => "index-out-of-bounds",
+            RuntimeErrorKind::DivisionByZero => "division-by-zero",
+            RuntimeErrorKind::NegativeArraySize => "negative-array-size",
+            RuntimeErrorKind::InvalidInput => "invalid-input",
+            RuntimeErrorKind::StackOverflow => "stack-overflow",
+            RuntimeErrorKind::StepLimit => "step-limit",
+            RuntimeErrorKind::TimeLimit => "time-limit",
+        }
+    }
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeError {
+    pub line: usize,
+    pub kind: RuntimeErrorKind,
+    pub message: String,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} [{}]", self.line, self.message, self.kind)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// What a run produced. Output written before a runtime error is kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Execution {
+    pub stdout: String,
+    pub error: Option<RuntimeError>,
+}
+
+impl Execution {
+    /// The exit status of an equivalent native program: 0 after a normal
+    /// end, 1 after a runtime error.
+    pub fn exit_code(&self) -> i32 {
+        match self.error {
+            None => 0,
+            Some(_) => 1,
+        }
+    }
+}
+
+/// Default for [`Interpreter::max_depth`].
+pub const MAX_DEPTH: usize = 10_000;
+
+/// Stack reserved for each nested call. The statements and expressions
+/// between two calls recurse too, so this is generous.
+const STACK_PER_CALL: usize = 64 * 1024;
+
+/// Runs a program.
+///
+/// ```
+/// let program = kxi_ref::parse("void main(){ cout << 6 * 7; }").unwrap();
+/// let run = kxi_ref::Interpreter::new(&program).run();
+/// assert_eq!(run.stdout, "42");
+/// ```
+pub struct Interpreter<'a> {
+    program: &'a Program,
+    input: String,
+    max_steps: Option<u64>,
+    time_limit: Option<Duration>,
+    max_depth: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    /// The program must have passed all semantic passes. Anything they
+    /// would have rejected may panic.
+    pub fn new(program: &'a Program) -> Interpreter<'a> {
+        Interpreter {
+            program,
+            input: String::new(),
+            max_steps: None,
+            time_limit: None,
+            max_depth: MAX_DEPTH,
+        }
+    }
+
+    /// Text read by `cin`.
+    pub fn stdin(mut self, input: impl Into<String>) -> Interpreter<'a> {
+        self.input = input.into();
+        self
+    }
+
+    /// Stops the program with [`RuntimeErrorKind::StepLimit`] after this
+    /// many statements, loop iterations and calls.
+    pub fn max_steps(mut self, steps: u64) -> Interpreter<'a> {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// Stops the program with [`RuntimeErrorKind::TimeLimit`] once it has
+    /// run this long.
+    pub fn time_limit(mut self, limit: Duration) -> Interpreter<'a> {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Stops the program with [`RuntimeErrorKind::StackOverflow`] when calls
+    /// nest deeper than this.
+    pub fn max_depth(mut self, depth: usize) -> Interpreter<'a> {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Runs on a thread of its own, whose stack is sized for
+    /// [`Interpreter::max_depth`] nested calls.
+    pub fn run(self) -> Execution {
+        let stack = STACK_PER_CALL * (self.max_depth + 1);
+        std::thread::scope(|scope| {
+            let thread = std::thread::Builder::new()
+                .name("kxi-interpreter".into())
+                .stack_size(stack)
+                .spawn_scoped(scope, move || {
+                    let mut machine = Machine::new(self);
+                    let error = machine.start().err();
+                    Execution {
+                        stdout: machine.stdout,
+                        error,
+                    }
+                })
+                .expect("cannot start the interpreter thread");
+            thread
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e))
+        })
+    }
+}
+
+type Result<T> = std::result::Result<T, RuntimeError>;
+
+/// How a statement ended.
+enum Flow {
+    Normal,
+    Break,
+    Return(Value),
+}
+
+/// Where a value can be stored.
+enum Place {
+    Local(String),
+    Field(Rc<RefCell<Object>>, String),
+    Static(String, String),
+    Element(Rc<RefCell<Vec<Value>>>, usize),
+}
+
+/// One method activation.
+struct Frame<'a> {
+    class: Option<&'a ClassDef>,
+    this: Option<Rc<RefCell<Object>>>,
+    scopes: Vec<HashMap<String, Value>>,
+}
+
+struct Machine<'a> {
+    program: &'a Program,
+    classes: HashMap<&'a str, &'a ClassDef>,
+    statics: HashMap<(String, String), Value>,
+    input: Vec<char>,
+    cursor: usize,
+    stdout: String,
+    steps: u64,
+    max_steps: Option<u64>,
+    /// The time limit and when it runs out.
+    deadline: Option<(Duration, Instant)>,
+    max_depth: usize,
+    frames: Vec<Frame<'a>>,
+}
+
+/// How many steps run between two looks at the clock.
+const STEPS_PER_CLOCK_CHECK: u64 = 1024;
+
+fn error<T>(line: usize, kind: RuntimeErrorKind, message: impl Into<String>) -> Result<T> {
+    Err(RuntimeError {
+        line,
+        kind,
+        message: message.into(),
+    })
+}
+
+impl<'a> Machine<'a> {
+    fn new(interpreter: Interpreter<'a>) -> Machine<'a> {
+        let program = interpreter.program;
+        Machine {
+            program,
+            classes: program
+                .classes
+                .iter()
+                .map(|c| (c.name.as_str(), c))
+                .collect(),
+            statics: HashMap::new(),
+            input: interpreter.input.chars().collect(),
+            cursor: 0,
+            stdout: String::new(),
+            steps: 0,
+            max_steps: interpreter.max_steps,
+            deadline: interpreter
+                .time_limit
+                .map(|limit| (limit, Instant::now() + limit)),
+            max_depth: interpreter.max_depth,
+            frames: Vec::new(),
+        }
+    }
+
+    fn start(&mut self) -> Result<()> {
+        let program = self.program;
+        for class in &program.classes {
+            for field in fields(class).filter(|f| f.modifiers.is_static) {
+                let key = (class.name.clone(), field.name.clone());
+                self.statics.insert(key, Value::default_of(&field.ty));
+            }
+        }
+        for class in &program.classes {
+            for field in fields(class).filter(|f| f.modifiers.is_static) {
+                if let Some(init) = &field.init {
+                    self.frames.push(Frame {
+                        class: Some(class),
+                        this: None,
+                        scopes: vec![HashMap::new()],
+                    });
+                    let value = self.expr(init);
+                    self.frames.pop();
+                    let key = (class.name.clone(), field.name.clone());
+                    self.statics.insert(key, value?);
+                }
+            }
+        }
+        self.invoke(
+            None,
+            None,
+            &[],
+            &program.main.body,
+            Vec::new(),
+            program.main.line,
+        )?;
+        Ok(())
+    }
+
+    fn step(&mut self, line: usize) -> Result<()> {
+        self.steps += 1;
+        if let Some(max) = self.max_steps.filter(|&max| self.steps > max) {
+            return error(
+                line,
+                RuntimeErrorKind::StepLimit,
+                format!("still running after {} steps", max),
+            );
+        }
+        match self.deadline {
+            Some((limit, deadline))
+                if self.steps.is_multiple_of(STEPS_PER_CLOCK_CHECK)
+                    && Instant::now() >= deadline =>
+            {
+                error(
+                    line,
+                    RuntimeErrorKind::TimeLimit,
+                    format!("still running after {:?}", limit),
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn frame(&mut self) -> &mut Frame<'a> {
+        self.frames.last_mut().expect("no frame")
+    }
+
+    /// Runs a method, constructor or `main` body in a fresh frame.
+    fn invoke(
+        &mut self,
+        class: Option<&'a ClassDef>,
+        this: Option<Rc<RefCell<Object>>>,
+        params: &'a [Param],
+        body: &'a [Stmt],
+        args: Vec<Value>,
+        line: usize,
+    ) -> Result<Value> {
+        if self.frames.len() >= self.max_depth {
+            return error(
+                line,
+                RuntimeErrorKind::StackOverflow,
+                format!("calls nested more than {} deep", self.max_depth),
+            );
+        }
+        self.step(line)?;
+        let locals = params.iter().map(|p| p.name.clone()).zip(args).collect();
+        self.frames.push(Frame {
+            class,
+            this,
+            scopes: vec![locals],
+        });
+        let flow = self.stmts(body);
+        self.frames.pop();
+        Ok(match flow? {
+            Flow::Return(value) => value,
+            _ => Value::Void,
+        })
+    }
+
+    fn scoped(&mut self, stmts: &'a [Stmt]) -> Result<Flow> {
+        self.frame().scopes.push(HashMap::new());
+        let flow = self.stmts(stmts);
+        self.frame().scopes.pop();
+        flow
+    }
+
+    fn stmts(&mut self, stmts: &'a [Stmt]) -> Result<Flow> {
+        for s in stmts {
+            match self.stmt(s)? {
+                Flow::Normal => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn condition(&mut self, cond: &'a Expr) -> Result<bool> {
+        match self.expr(cond)? {
+            Value::Bool(b) => Ok(b),
+            other => unreachable!("condition is {:?}", other),
+        }
+    }
+
+    fn stmt(&mut self, s: &'a Stmt) -> Result<Flow> {
+        self.step(s.line)?;
+        match &s.kind {
+            StmtKind::Block(body) => self.scoped(body),
+            StmtKind::Var { ty, name, init } => {
+                let value = match init {
+                    Some(init) => self.expr(init)?,
+                    None => Value::default_of(ty),
+                };
+                let scope = self.frame().scopes.last_mut().expect("no scope");
+                scope.insert(name.clone(), value);
+                Ok(Flow::Normal)
+            }
+            StmtKind::Expr(e) => {
+                self.expr(e)?;
+                Ok(Flow::Normal)
+            }
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                if self.condition(cond)? {
+                    self.scoped(std::slice::from_ref(then))
+                } else if let Some(otherwise) = otherwise {
+                    self.scoped(std::slice::from_ref(otherwise))
+                } else {
+                    Ok(Flow::Normal)
+                }
+            }
+            StmtKind::While { cond, body } => {
+                while self.condition(cond)? {
+                    self.step(s.line)?;
+                    match self.scoped(std::slice::from_ref(body))? {
+                        Flow::Break => break,
+                        Flow::Return(v) => return Ok(Flow::Return(v)),
+                        Flow::Normal => {}
+                    }
+                }
+                Ok(Flow::Normal)
+            }
+            StmtKind::For {
+                init,
+                cond,
+                step,
+                body,
+            } => {
+                if let Some(init) = init {
+                    self.expr(init)?;
+                }
+                loop {
+                    if let Some(cond) = cond {
+                        if !self.condition(cond)? {
+                            break;
+                        }
+                    }
+                    self.step(s.line)?;
+                    match self.scoped(std::slice::from_ref(body))? {
+                        Flow::Break => break,
+                        Flow::Return(v) => return Ok(Flow::Return(v)),
+                        Flow::Normal => {}
+                    }
+                    if let Some(step) = step {
+                        self.expr(step)?;
+                    }
+                }
+                Ok(Flow::Normal)
+            }
+            StmtKind::Return(value) => {
+                let value = match value {
+                    Some(e) => self.expr(e)?,
+                    None => Value::Void,
+                };
+                Ok(Flow::Return(value))
+            }
+            StmtKind::Break => Ok(Flow::Break),
+            StmtKind::Cin(target) => {
+                let place = self.place(target)?;
+                let value = match self.load(&place) {
+                    Value::Int(_) => Value::Int(self.read_int(s.line)?),
+                    Value::Char(_) => Value::Char(self.read_char(s.line)?),
+                    other => unreachable!("cin into {:?}", other),
+                };
+                self.store(place, value);
+                Ok(Flow::Normal)
+            }
+            StmtKind::Cout(value) => {
+                let value = self.expr(value)?;
+                self.stdout.push_str(&value.to_string());
+                Ok(Flow::Normal)
+            }
+            StmtKind::Switch {
+                scrutinee,
+                cases,
+                default,
+            } => {
+                let value = self.expr(scrutinee)?;
+                let start = cases
+                    .iter()
+                    .position(|c| literal(&c.label).equals(&value))
+                    .unwrap_or(cases.len());
+                if start == cases.len() && default.is_none() {
+                    return Ok(Flow::Normal);
+                }
+                // The case bodies share one scope, so a jump can pass over
+                // the declaration of a local used further down.
+                let mut scope = HashMap::new();
+                for s in cases
+                    .iter()
+                    .flat_map(|c| &c.body)
+                    .chain(default.iter().flatten())
+                {
+                    if let StmtKind::Var { ty, name, .. } = &s.kind {
+                        scope.insert(name.clone(), Value::default_of(ty));
+                    }
+                }
+                self.frame().scopes.push(scope);
+                let bodies = cases[start..]
+                    .iter()
+                    .map(|c| c.body.as_slice())
+                    .chain(default.as_deref());
+                let mut flow = Ok(Flow::Normal);
+                for body in bodies {
+                    flow = self.stmts(body);
+                    if !matches!(flow, Ok(Flow::Normal)) {
+                        break;
+                    }
+                }
+                self.frame().scopes.pop();
+                match flow? {
+                    Flow::Break => Ok(Flow::Normal),
+                    flow => Ok(flow),
+                }
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .input
+            .get(self.cursor)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.cursor += 1;
+        }
+    }
+
+    fn read_char(&mut self, line: usize) -> Result<char> {
+        self.skip_whitespace();
+        match self.input.get(self.cursor) {
+            Some(&c) => {
+                self.cursor += 1;
+                Ok(c)
+            }
+            None => error(line, RuntimeErrorKind::InvalidInput, "end of input"),
+        }
+    }
+
+    fn read_int(&mut self, line: usize) -> Result<i32> {
+        self.skip_whitespace();
+        let start = self.cursor;
+        if matches!(self.input.get(self.cursor), Some('-' | '+')) {
+            self.cursor += 1;
+        }
+        while self
+            .input
+            .get(self.cursor)
+            .is_some_and(|c| c.is_ascii_digit())
+        {
+            self.cursor += 1;
+        }
+        let text: String = self.input[start..self.cursor].iter().collect();
+        match text.parse() {
+            Ok(n) => Ok(n),
+            Err(_) if start == self.input.len() => {
+                error(line, RuntimeErrorKind::InvalidInput, "end of input")
+            }
+            Err(_) => error(
+                line,
+                RuntimeErrorKind::InvalidInput,
+                format!("expected an int, found {:?}", text),
+            ),
+        }
+    }
+
+    /// The class `e` names, when it is used as the receiver of a static
+    /// member rather than as a value.
+    fn static_receiver(&self, e: &Expr) -> Option<&'a ClassDef> {
+        let ExprKind::Ident(name) = &e.kind else {
+            return None;
+        };
+        let frame = self.frames.last().expect("no frame");
+        if frame.scopes.iter().any(|s| s.contains_key(name)) {
+            return None;
+        }
+        if frame
+            .class
+            .is_some_and(|c| fields(c).any(|f| &f.name == name))
+        {
+            return None;
+        }
+        self.classes.get(name.as_str()).copied()
+    }
+
+    fn object(&mut self, e: &'a Expr) -> Result<Rc<RefCell<Object>>> {
+        match self.expr(e)? {
+            Value::Object(o) => Ok(o),
+            Value::Null => error(
+                e.line,
+                RuntimeErrorKind::NullDereference,
+                "used a null object",
+            ),
+            other => unreachable!("member of {:?}", other),
+        }
+    }
+
+    fn place(&mut self, e: &'a Expr) -> Result<Place> {
+        match &e.kind {
+            ExprKind::Ident(name) => {
+                let frame = self.frames.last().expect("no frame");
+                if frame.scopes.iter().any(|s| s.contains_key(name)) {
+                    return Ok(Place::Local(name.clone()));
+                }
+                let class = frame.class.expect("unresolved name");
+                let field = fields(class)
+                    .find(|f| &f.name == name)
+                    .expect("unresolved name");
+                if field.modifiers.is_static {
+                    Ok(Place::Static(class.name.clone(), name.clone()))
+                } else {
+                    let this = frame.this.clone().expect("instance field without this");
+                    Ok(Place::Field(this, name.clone()))
+                }
+            }
+            ExprKind::Member(object, name) => {
+                if let Some(class) = self.static_receiver(object) {
+                    return Ok(Place::Static(class.name.clone(), name.clone()));
+                }
+                let object = self.object(object)?;
+                let class = self.classes[object.borrow().class.as_str()];
+                if fields(class).any(|f| &f.name == name && f.modifiers.is_static) {
+                    return Ok(Place::Static(class.name.clone(), name.clone()));
+                }
+                Ok(Place::Field(object, name.clone()))
+            }
+            ExprKind::Index(array, index) => {
+                let array = match self.expr(array)? {
+                    Value::Array(a) => a,
+                    Value::Null => {
+                        return error(
+                            e.line,
+                            RuntimeErrorKind::NullDereference,
+                            "indexed a null array",
+                        )
+                    }
+                    other => unreachable!("index into {:?}", other),
+                };
+                let Value::Int(i) = self.expr(index)? else {
+                    unreachable!("index is not an int")
+                };
+                let len = array.borrow().len();
+                match usize::try_from(i) {
+                    Ok(i) if i < len => Ok(Place::Element(array, i)),
+                    _ => error(
+                        e.line,
+                        RuntimeErrorKind::IndexOutOfBounds,
+                        format!("index {} is outside an array of {}", i, len),
+                    ),
+                }
+            }
+            other => unreachable!("not a place: {:?}", other),
+        }
+    }
+
+    fn load(&self, place: &Place) -> Value {
+        match place {
+            Place::Local(name) => {
+                let frame = self.frames.last().expect("no frame");
+                let scope = frame.scopes.iter().rev().find_map(|s| s.get(name));
+                scope.expect("unresolved local").clone()
+            }
+            Place::Field(object, name) => object.borrow().fields[name].clone(),
+            Place::Static(class, name) => self.statics[&(class.clone(), name.clone())].clone(),
+            Place::Element(array, i) => array.borrow()[*i].clone(),
+        }
+    }
+
+    fn store(&mut self, place: Place, value: Value) {
+        match place {
+            Place::Local(name) => {
+                let frame = self.frame();
+                let scope = frame
+                    .scopes
+                    .iter_mut()
+                    .rev()
+                    .find(|s| s.contains_key(&name))
+                    .expect("unresolved local");
+                scope.insert(name, value);
+            }
+            Place::Field(object, name) => {
+                object.borrow_mut().fields.insert(name, value);
+            }
+            Place::Static(class, name) => {
+                self.statics.insert((class, name), value);
+            }
+            Place::Element(array, i) => array.borrow_mut()[i] = value,
+        }
+    }
+
+    fn expr(&mut self, e: &'a Expr) -> Result<Value> {
+        match &e.kind {
+            ExprKind::Literal(l) => Ok(literal(l)),
+            ExprKind::This => {
+                let this = self.frames.last().and_then(|f| f.this.clone());
+                Ok(Value::Object(this.expect("this outside an instance")))
+            }
+            ExprKind::Ident(_) | ExprKind::Member(..) | ExprKind::Index(..) => {
+                let place = self.place(e)?;
+                Ok(self.load(&place))
+            }
+            ExprKind::Assign(target, value) => {
+                let place = self.place(target)?;
+                let value = self.expr(value)?;
+                self.store(place, value.clone());
+                Ok(value)
+            }
+            ExprKind::Unary(op, operand) => Ok(match (op, self.expr(operand)?) {
+                (UnaryOp::Not, Value::Bool(b)) => Value::Bool(!b),
+                (UnaryOp::Neg, Value::Int(n)) => Value::Int(n.wrapping_neg()),
+                (UnaryOp::Plus, Value::Int(n)) => Value::Int(n),
+                (op, v) => unreachable!("{:?} {:?}", op, v),
+            }),
+            ExprKind::Binary(op, lhs, rhs) => self.binary(e.line, *op, lhs, rhs),
+            ExprKind::Call(callee, args) => self.call(e.line, callee, args),
+            ExprKind::New(class, args) => {
+                let args = self.args(args)?;
+                self.construct(e.line, class, args)
+            }
+            ExprKind::NewArray(elem, size) => {
+                let Value::Int(n) = self.expr(size)? else {
+                    unreachable!("size is not an int")
+                };
+                match usize::try_from(n) {
+                    Ok(n) => Ok(Value::Array(Rc::new(RefCell::new(vec![
+                        Value::default_of(
+                            elem
+                        );
+                        n
+                    ])))),
+                    Err(_) => error(
+                        e.line,
+                        RuntimeErrorKind::NegativeArraySize,
+                        format!("array of size {}", n),
+                    ),
+                }
+            }
+        }
+    }
+
+    fn binary(&mut self, line: usize, op: BinaryOp, lhs: &'a Expr, rhs: &'a Expr) -> Result<Value> {
+        let l = self.expr(lhs)?;
+        match (op, &l) {
+            (BinaryOp::And, Value::Bool(false)) => return Ok(Value::Bool(false)),
+            (BinaryOp::Or, Value::Bool(true)) => return Ok(Value::Bool(true)),
+            _ => {}
+        }
+        let r = self.expr(rhs)?;
+        Ok(match (op, l, r) {
+            (BinaryOp::And | BinaryOp::Or, _, r) => r,
+            (BinaryOp::Eq, l, r) => Value::Bool(l.equals(&r)),
+            (BinaryOp::NotEq, l, r) => Value::Bool(!l.equals(&r)),
+            (BinaryOp::Div | BinaryOp::Mod, Value::Int(_), Value::Int(0)) => {
+                return error(line, RuntimeErrorKind::DivisionByZero, "divided by zero")
+            }
+            (op, Value::Int(a), Value::Int(b)) => match op {
+                BinaryOp::Add => Value::Int(a.wrapping_add(b)),
+                BinaryOp::Sub => Value::Int(a.wrapping_sub(b)),
+                BinaryOp::Mul => Value::Int(a.wrapping_mul(b)),
+                BinaryOp::Div => Value::Int(a.wrapping_div(b)),
+                BinaryOp::Mod => Value::Int(a.wrapping_rem(b)),
+                _ => Value::Bool(compare(op, a, b)),
+            },
+            (op, Value::Char(a), Value::Char(b)) => Value::Bool(compare(op, a, b)),
+            (op, l, r) => unreachable!("{:?} {:?} {:?}", l, op, r),
+        })
+    }
+
+    fn args(&mut self, args: &'a [Expr]) -> Result<Vec<Value>> {
+        args.iter().map(|a| self.expr(a)).collect()
+    }
+
+    fn call(&mut self, line: usize, callee: &'a Expr, args: &'a [Expr]) -> Result<Value> {
+        let (class, this, name) = match &callee.kind {
+            ExprKind::Ident(name) => {
+                let frame = self.frames.last().expect("no frame");
+                match frame.class {
+                    Some(class) if methods(class).any(|m| &m.name == name) => {
+                        (Some(class), frame.this.clone(), name)
+                    }
+                    _ => (None, None, name),
+                }
+            }
+            ExprKind::Member(object, name) => match self.static_receiver(object) {
+                Some(class) => (Some(class), None, name),
+                None => {
+                    let object = self.object(object)?;
+                    let class = self.classes[object.borrow().class.as_str()];
+                    (Some(class), Some(object), name)
+                }
+            },
+            other => unreachable!("call of {:?}", other),
+        };
+        let args = self.args(args)?;
+        let Some(class) = class else {
+            let main = &self.program.main;
+            return self.invoke(None, None, &[], &main.body, args, line);
+        };
+        let method = methods(class)
+            .find(|m| &m.name == name)
+            .expect("unresolved method");
+        let this = if method.modifiers.is_static {
+            None
+        } else {
+            this
+        };
+        self.invoke(Some(class), this, &method.params, &method.body, args, line)
+    }
+
+    fn construct(&mut self, line: usize, class: &str, args: Vec<Value>) -> Result<Value> {
+        let class = self.classes[class];
+        let defaults = fields(class)
+            .filter(|f| !f.modifiers.is_static)
+            .map(|f| (f.name.clone(), Value::default_of(&f.ty)))
+            .collect();
+        let object = Rc::new(RefCell::new(Object {
+            class: class.name.clone(),
+            fields: defaults,
+        }));
+        self.frames.push(Frame {
+            class: Some(class),
+            this: Some(object.clone()),
+            scopes: vec![HashMap::new()],
+        });
+        let mut initialized = Ok(());
+        for field in fields(class).filter(|f| !f.modifiers.is_static) {
+            if let Some(init) = &field.init {
+                match self.expr(init) {
+                    Ok(value) => {
+                        object.borrow_mut().fields.insert(field.name.clone(), value);
+                    }
+                    Err(e) => {
+                        initialized = Err(e);
+                        break;
+                    }
+                }
+            }
+        }
+        self.frames.pop();
+        initialized?;
+        let constructor = class.members.iter().find_map(|m| match m {
+            Member::Constructor(c) if c.name == class.name => Some(c),
+            _ => None,
+        });
+        if let Some(c) = constructor {
+            let this = Some(object.clone());
+            self.invoke(Some(class), this, &c.params, &c.body, args, line)?;
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+fn fields(class: &ClassDef) -> impl Iterator<Item = &Field> {
+    class.members.iter().filter_map(|m| match m {
+        Member::Field(f) => Some(f),
+        _ => None,
+    })
+}
+
+fn methods(class: &ClassDef) -> impl Iterator<Item = &Method> {
+    class.members.iter().filter_map(|m| match m {
+        Member::Method(m) => Some(m),
+        _ => None,
+    })
+}
+
+fn literal(l: &Literal) -> Value {
+    match l {
+        Literal::Int(n) => Value::Int(*n),
+        Literal::Char(c) => Value::Char(*c),
+        Literal::Str(s) => Value::Str(s.as_str().into()),
+        Literal::Bool(b) => Value::Bool(*b),
+        Literal::Null => Value::Null,
+    }
+}
+
+fn compare<T: PartialOrd>(op: BinaryOp, a: T, b: T) -> bool {
+    match op {
+        BinaryOp::Less => a < b,
+        BinaryOp::LessEq => a <= b,
+        BinaryOp::Greater => a > b,
+        BinaryOp::GreaterEq => a >= b,
+        other => unreachable!("{:?} is not a comparison", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str, input: &str) -> Execution {
+        let program = crate::parse(source).unwrap();
+        crate::sema::analyze(&program).unwrap();
+        Interpreter::new(&program)
+            .stdin(input)
+            .max_steps(10_000)
+            .run()
+    }
+
+    fn stdout(source: &str, input: &str) -> String {
+        let run = run(source, input);
+        assert_eq!(run.error, None);
+        run.stdout
+    }
+
+    fn failure(source: &str) -> (usize, RuntimeErrorKind) {
+        let error = run(source, "").error.expect("the program ran to the end");
+        (error.line, error.kind)
+    }
+
+    #[test]
+    fn cin_reads_ints_and_chars() {
+        let source = "void main(){\n    int a; char c; int b;\n    cin >> a; cin >> c; cin >> b;\n\
+                      cout << a + b; cout << c;\n}";
+        assert_eq!(stdout(source, " 40\n x-2 "), "38x");
+        assert_eq!(
+            stdout("void main(){ cout << -7 / 2; cout << -7 % 2; }", ""),
+            "-3-1"
+        );
+    }
+
+    #[test]
+    fn objects_run_initializers_then_the_constructor() {
+        let source = "class Counter {\n    static public int made = 0;\n    private int n = 10;\n\
+                      Counter(int start) { n = n + start; made = made + 1; }\n\
+                      public int next() { n = n + 1; return n; }\n}\n\
+                      void main(){\n    Counter a = new Counter(1);\n    Counter b = new Counter(5);\n\
+                      cout << a.next(); cout << ' '; cout << b.next(); cout << ' ';\n\
+                      cout << Counter.made;\n}";
+        assert_eq!(stdout(source, ""), "12 16 2");
+    }
+
+    #[test]
+    fn inner_arrays_start_out_null() {
+        let source = "void main(){\n    int[][] grid = new int[][3];\n    grid[1] = new int[2];\n\
+                      grid[1][1] = 7;\n    cout << grid[1][1] + grid[1][0];\n\
+                      if (grid[0] == null) { cout << \"!\"; }\n}";
+        assert_eq!(stdout(source, ""), "7!");
+    }
+
+    #[test]
+    fn switch_falls_through_until_break() {
+        let source = "class S {\n    static public void f(int n) {\n        switch (n) {\n\
+                      case 1: cout << 'a';\n            case 2: cout << 'b'; break;\n\
+                      case 3: cout << 'c';\n            default: cout << 'd';\n        }\n    }\n}\n\
+                      void main(){\n    S.f(1); S.f(2); S.f(3); S.f(4);\n}";
+        assert_eq!(stdout(source, ""), "abbcdd");
+    }
+
+    #[test]
+    fn switch_locals_exist_in_every_case() {
+        let source = "void main(){\n    int x = 1;\n    switch (x) {\n\
+                      case 0: int y = 1;\n        case 1: cout << y;\n    }\n}";
+        assert_eq!(stdout(source, ""), "0");
+    }
+
+    #[test]
+    fn return_leaves_loops_and_recursion() {
+        let source = "class M {\n    static public int fact(int n) {\n\
+                      if (n <= 1) { return 1; }\n        return n * fact(n - 1);\n    }\n\
+                      static public int first(int[] xs, int x) {\n\
+                      int i;\n        for (i = 0; i < 3; i = i + 1) { if (xs[i] == x) { return i; } }\n\
+                      return -1;\n    }\n}\n\
+                      void main(){\n    int[] xs = new int[3];\n    xs[2] = 9;\n\
+                      cout << M.fact(5); cout << M.first(xs, 9); cout << M.first(xs, 4);\n}";
+        assert_eq!(stdout(source, ""), "1202-1");
+    }
+
+    #[test]
+    fn runtime_errors_stop_the_program() {
+        let source =
+            "class A { public int x; }\nvoid main(){\n    A a;\n    cout << 1;\n    a.x = 2;\n}";
+        let run = run(source, "");
+        assert_eq!(run.stdout, "1");
+        assert_eq!(run.exit_code(), 1);
+        assert_eq!(failure(source), (5, RuntimeErrorKind::NullDereference));
+        let source = "void main(){\n    int[] a = new int[2];\n    a[2] = 1;\n}";
+        assert_eq!(failure(source), (3, RuntimeErrorKind::IndexOutOfBounds));
+        let source = "void main(){\n    int z = 0;\n    cout << 1 / z;\n}";
+        assert_eq!(failure(source), (3, RuntimeErrorKind::DivisionByZero));
+        let source = "void main(){\n    int n;\n    cin >> n;\n}";
+        assert_eq!(failure(source), (3, RuntimeErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn limits_stop_runaway_programs() {
+        let source = "void main(){\n    while (true) {}\n}";
+        assert_eq!(failure(source), (2, RuntimeErrorKind::StepLimit));
+        let program = crate::parse(source).unwrap();
+        let run = Interpreter::new(&program)
+            .time_limit(Duration::from_millis(10))
+            .run();
+        assert_eq!(run.error.unwrap().kind, RuntimeErrorKind::TimeLimit);
+        let source = "class R {\n    static public void f() {\n        f();\n    }\n}\nvoid main(){ R.f(); }";
+        let program = crate::parse(source).unwrap();
+        let run = Interpreter::new(&program).max_depth(100).run();
+        assert_eq!(run.error.unwrap().kind, RuntimeErrorKind::StackOverflow);
+    }
+}
@@ -0,0 +1,459 @@
+//! Tokenizer for KXI source text.
+//!
+//! [`dump`] renders the token stream in the format of the golden files under
+//! `lexer_kxi/`, one token per line:
+//!
+//! ```text
+//! 1:1 keyword void
+//! 1:6 ident main
+//! 1:10 punct (
+//! 2:13 int 42
+//! 3:14 char '\n'
+//! 4:5 eof
+//! ```
+//!
+//! Each line is the 1-based line and column of the token's first character,
+//! its category and its text exactly as written in the source. A lexical
+//! error ends the stream with an `error` line holding the message instead of
+//! `eof`.
+
+use std::fmt;
+
+/// What a token is. Keywords and punctuation get one variant each so the
+/// parser can match on them directly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    Ident(String),
+    /// An integer literal. Its sign is a separate `-` token.
+    IntLit(i32),
+    CharLit(char),
+    /// A string literal with its escapes resolved.
+    StrLit(String),
+
+    Bool,
+    Break,
+    Case,
+    Cin,
+    Char,
+    Class,
+    Cout,
+    Default,
+    Else,
+    False,
+    For,
+    If,
+    Int,
+    New,
+    Null,
+    Private,
+    Public,
+    Return,
+    Static,
+    String,
+    Switch,
+    This,
+    True,
+    Void,
+    While,
+
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Semi,
+    Comma,
+    Dot,
+    Colon,
+    Assign,
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    /// `<<`, only used by `cout`.
+    Insert,
+    /// `>>`, only used by `cin`.
+    Extract,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    And,
+    Or,
+    Not,
+
+    Eof,
+}
+
+const KEYWORDS: &[(&str, TokenKind)] = &[
+    ("bool", TokenKind::Bool),
+    ("break", TokenKind::Break),
+    ("case", TokenKind::Case),
+    ("char", TokenKind::Char),
+    ("cin", TokenKind::Cin),
+    ("class", TokenKind::Class),
+    ("cout", TokenKind::Cout),
+    ("default", TokenKind::Default),
+    ("else", TokenKind::Else),
+    ("false", TokenKind::False),
+    ("for", TokenKind::For),
+    ("if", TokenKind::If),
+    ("int", TokenKind::Int),
+    ("new", TokenKind::New),
+    ("null", TokenKind::Null),
+    ("private", TokenKind::Private),
+    ("public", TokenKind::Public),
+    ("return", TokenKind::Return),
+    ("static", TokenKind::Static),
+    ("string", TokenKind::String),
+    ("switch", TokenKind::Switch),
+    ("this", TokenKind::This),
+    ("true", TokenKind::True),
+    ("void", TokenKind::Void),
+    ("while", TokenKind::While),
+];
+
+/// Longest first, so `<<` wins over `<`.
+const PUNCTUATION: &[(&str, TokenKind)] = &[
+    ("==", TokenKind::Eq),
+    ("!=", TokenKind::NotEq),
+    ("<=", TokenKind::LessEq),
+    (">=", TokenKind::GreaterEq),
+    ("<<", TokenKind::Insert),
+    (">>", TokenKind::Extract),
+    ("&&", TokenKind::And),
+    ("||", TokenKind::Or),
+    ("{", TokenKind::LBrace),
+    ("}", TokenKind::RBrace),
+    ("(", TokenKind::LParen),
+    (")", TokenKind::RParen),
+    ("[", TokenKind::LBracket),
+    ("]", TokenKind::RBracket),
+    (";", TokenKind::Semi),
+    (",", TokenKind::Comma),
+    (".", TokenKind::Dot),
+    (":", TokenKind::Colon),
+    ("=", TokenKind::Assign),
+    ("<", TokenKind::Less),
+    (">", TokenKind::Greater),
+    ("+", TokenKind::Plus),
+    ("-", TokenKind::Minus),
+    ("*", TokenKind::Star),
+    ("/", TokenKind::Slash),
+    ("%", TokenKind::Percent),
+    ("!", TokenKind::Not),
+];
+
+impl TokenKind {
+    /// The category written in golden files.
+    pub fn category(&self) -> &'static str {
+        match self {
+            TokenKind::Ident(_) => "ident",
+            TokenKind::IntLit(_) => "int",
+            TokenKind::CharLit(_) => "char",
+            TokenKind::StrLit(_) => "string",
+            TokenKind::Eof => "eof",
+            kind if KEYWORDS.iter().any(|(_, k)| k == kind) => "keyword",
+            _ => "punct",
+        }
+    }
+}
+
+/// A token and where it starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// The token exactly as written, empty for [`TokenKind::Eof`].
+    pub text: String,
+    /// 1-based line.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{} {}", self.line, self.column, self.kind.category())?;
+        if !self.text.is_empty() {
+            write!(f, " {}", self.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Text that is not a KXI token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+/// Splits `source` into tokens, ending with [`TokenKind::Eof`]. Whitespace
+/// and `//` comments are skipped.
+pub fn tokenize(source: &str) -> Result<Vec<Token>, LexError> {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token()?;
+        let eof = token.kind == TokenKind::Eof;
+        tokens.push(token);
+        if eof {
+            return Ok(tokens);
+        }
+    }
+}
+
+/// Renders the token stream of `source` in the golden file format.
+pub fn dump(source: &str) -> String {
+    let mut lexer = Lexer::new(source);
+    let mut out = String::new();
+    loop {
+        match lexer.next_token() {
+            Ok(token) => {
+                out.push_str(&token.to_string());
+                out.push('\n');
+                if token.kind == TokenKind::Eof {
+                    return out;
+                }
+            }
+            Err(e) => {
+                out.push_str(&format!("{}:{} error {}\n", e.line, e.column, e.message));
+                return out;
+            }
+        }
+    }
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    /// Byte offset of the next character.
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Lexer<'a> {
+        Lexer {
+            source,
+            pos: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('/') if self.rest().starts_with("//") => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, LexError> {
+        self.skip_trivia();
+        let (start, line, column) = (self.pos, self.line, self.column);
+        let error = |message: String| LexError {
+            line,
+            column,
+            message,
+        };
+        let Some(c) = self.peek() else {
+            return Ok(Token {
+                kind: TokenKind::Eof,
+                text: String::new(),
+                line,
+                column,
+            });
+        };
+
+        let kind = if c.is_ascii_alphabetic() || c == '_' {
+            while self
+                .peek()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                self.bump();
+            }
+            let word = &self.source[start..self.pos];
+            KEYWORDS
+                .iter()
+                .find(|(k, _)| *k == word)
+                .map_or_else(|| TokenKind::Ident(word.to_string()), |(_, k)| k.clone())
+        } else if c.is_ascii_digit() {
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.bump();
+            }
+            let digits = &self.source[start..self.pos];
+            match digits.parse() {
+                Ok(n) => TokenKind::IntLit(n),
+                Err(_) => return Err(error(format!("integer literal {} is too large", digits))),
+            }
+        } else if c == '\'' {
+            self.bump();
+            let value = match self.peek() {
+                None | Some('\n') => return Err(error("unterminated character literal".into())),
+                Some('\'') => return Err(error("empty character literal".into())),
+                Some(_) => self.literal_char().map_err(error)?,
+            };
+            if self.peek() != Some('\'') {
+                return Err(error("unterminated character literal".into()));
+            }
+            self.bump();
+            TokenKind::CharLit(value)
+        } else if c == '"' {
+            self.bump();
+            let mut value = String::new();
+            loop {
+                match self.peek() {
+                    None | Some('\n') => return Err(error("unterminated string literal".into())),
+                    Some('"') => break,
+                    Some(_) => value.push(self.literal_char().map_err(error)?),
+                }
+            }
+            self.bump();
+            TokenKind::StrLit(value)
+        } else if let Some((text, kind)) = PUNCTUATION
+            .iter()
+            .find(|(text, _)| self.rest().starts_with(text))
+        {
+            for _ in 0..text.len() {
+                self.bump();
+            }
+            kind.clone()
+        } else {
+            return Err(error(format!("unexpected character {:?}", c)));
+        };
+
+        Ok(Token {
+            kind,
+            text: self.source[start..self.pos].to_string(),
+            line,
+            column,
+        })
+    }
+
+    /// One character of a char or string literal, resolving escapes.
+    fn literal_char(&mut self) -> Result<char, String> {
+        match self.bump() {
+            Some('\\') => match self.bump() {
+                Some('n') => Ok('\n'),
+                Some('t') => Ok('\t'),
+                Some('r') => Ok('\r'),
+                Some('0') => Ok('\0'),
+                Some(c @ ('\\' | '\'' | '"')) => Ok(c),
+                Some(c) => Err(format!("unknown escape \\{}", c)),
+                None => Err("unterminated literal".into()),
+            },
+            Some(c) => Ok(c),
+            None => Err("unterminated literal".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn keywords_and_identifiers() {
+        assert_eq!(
+            kinds("class classy _x"),
+            vec![
+                TokenKind::Class,
+                TokenKind::Ident("classy".into()),
+                TokenKind::Ident("_x".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn longest_punctuation_wins() {
+        assert_eq!(
+            kinds("<<<=>>>"),
+            vec![
+                TokenKind::Insert,
+                TokenKind::LessEq,
+                TokenKind::Extract,
+                TokenKind::Greater,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes_are_resolved() {
+        assert_eq!(
+            kinds(r#"'\n' "a\"b""#),
+            vec![
+                TokenKind::CharLit('\n'),
+                TokenKind::StrLit("a\"b".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        assert_eq!(
+            kinds("// all of it\n1 // 2"),
+            vec![TokenKind::IntLit(1), TokenKind::Eof]
+        );
+    }
+
+    #[test]
+    fn errors_carry_the_start_of_the_token() {
+        let err = tokenize("int x;\n  \"open").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.message, "unterminated string literal");
+    }
+}
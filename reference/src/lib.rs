@@ -0,0 +1,27 @@
+//! Reference implementation of KXI.
+//!
+//! The corpus under `dons_tests/` says what a KXI compiler must accept and
+//! reject, but until now the only code that acted on it lived in other
+//! repositories. This crate implements the language in the most direct way
+//! possible so that every expectation in the corpus can be checked by
+//! running code.
+//!
+//! [`lexer`] turns source text into tokens, in the format of the golden
+//! `.tokens` files under `lexer_kxi/`. [`parser`] builds the [`ast`] from
+//! them, printed by [`sexp`] in the format of the `.ast` files under
+//! `valid_parse/` and `invalid_parse/`, and by [`print`] as KXI source.
+//! [`sema`] runs the declaration, resolution and type-checking passes
+//! `sem_tests.rs` describes, and [`interp`] runs the programs that pass
+//! them.
+
+pub mod ast;
+pub mod interp;
+pub mod lexer;
+pub mod parser;
+pub mod print;
+pub mod sema;
+pub mod sexp;
+
+pub use interp::{Execution, Interpreter, RuntimeError, RuntimeErrorKind, Value};
+pub use lexer::{tokenize, LexError, Token, TokenKind};
+pub use parser::{parse, SyntaxError};
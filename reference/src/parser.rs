@@ -0,0 +1,750 @@
+//! Recursive-descent parser for KXI.
+//!
+//! The grammar is the subset of KXI the corpus uses:
+//!
+//! ```text
+//! program     = class* "void" ident "(" ")" block
+//! class       = "class" ident "{" member* "}"
+//! member      = ident "(" params ")" block                      constructor
+//!             | ["static"] ("public"|"private") type ident
+//!               ( "(" params ")" block | ["=" expr] ";" )
+//! type        = ("void"|"int"|"char"|"bool"|"string"|ident) ("[" "]")*
+//! statement   = block | type ident ["=" expr] ";" | expr ";"
+//!             | "if" "(" expr ")" statement ["else" statement]
+//!             | "while" "(" expr ")" statement
+//!             | "for" "(" [expr] ";" [expr] ";" [expr] ")" statement
+//!             | "return" [expr] ";" | "break" ";"
+//!             | "cin" ">>" expr ";" | "cout" "<<" expr ";"
+//!             | "switch" "(" expr ")" "{" case* ["default" ":" statement*] "}"
+//! case        = "case" ("-"? int | char) ":" statement*
+//! ```
+//!
+//! Expressions bind, from loosest to tightest: `=` (to the right), `||`,
+//! `&&`, `==` `!=`, `<` `<=` `>` `>=`, `+` `-`, `*` `/` `%`, the prefix
+//! operators `!` `-` `+`, then member access, calls and indexing.
+//! `new int[][][10]` makes an array of ten `int[][]`.
+//!
+//! Parsing stops at the first syntax error.
+
+use std::fmt;
+
+use crate::ast::*;
+use crate::lexer::{tokenize, LexError, Token, TokenKind};
+use crate::sexp;
+
+/// A program that does not match the grammar, or does not even tokenize.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+impl From<LexError> for SyntaxError {
+    fn from(e: LexError) -> SyntaxError {
+        SyntaxError {
+            line: e.line,
+            column: e.column,
+            message: e.message,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, SyntaxError>;
+
+/// Parses a whole program.
+pub fn parse(source: &str) -> Result<Program> {
+    let tokens = tokenize(source)?;
+    Parser { tokens, pos: 0 }.program()
+}
+
+/// Renders the syntax tree of `source` in the format of the golden `.ast`
+/// files, or `line:column error message` if it does not parse.
+pub fn dump(source: &str) -> String {
+    match parse(source) {
+        Ok(program) => sexp::program(&program).pretty(),
+        Err(e) => format!("{}:{} error {}\n", e.line, e.column, e.message),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &TokenKind {
+        self.peek_at(0)
+    }
+
+    /// The token `n` places ahead, or the final `Eof`.
+    fn peek_at(&self, n: usize) -> &TokenKind {
+        let last = self.tokens.len() - 1;
+        &self.tokens[(self.pos + n).min(last)].kind
+    }
+
+    fn current(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.current().clone();
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek() == kind {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error<T>(&self, expected: &str) -> Result<T> {
+        let token = self.current();
+        let found = match token.kind {
+            TokenKind::Eof => "end of file".to_string(),
+            _ => format!("`{}`", token.text),
+        };
+        self.fail(format!("expected {}, found {}", expected, found))
+    }
+
+    /// An error at the current token.
+    fn fail<T>(&self, message: String) -> Result<T> {
+        let token = self.current();
+        Err(SyntaxError {
+            line: token.line,
+            column: token.column,
+            message,
+        })
+    }
+
+    /// Whether a list ended by `}` goes on. Running out of tokens first is
+    /// an error.
+    fn before_close(&mut self) -> Result<bool> {
+        match self.peek() {
+            TokenKind::RBrace => {
+                self.advance();
+                Ok(false)
+            }
+            TokenKind::Eof => self.error("`}`"),
+            _ => Ok(true),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, text: &str) -> Result<Token> {
+        if *self.peek() == kind {
+            Ok(self.advance())
+        } else {
+            self.error(&format!("`{}`", text))
+        }
+    }
+
+    fn ident(&mut self, what: &str) -> Result<String> {
+        match self.peek() {
+            TokenKind::Ident(name) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => self.error(what),
+        }
+    }
+
+    fn program(&mut self) -> Result<Program> {
+        let mut classes = Vec::new();
+        while *self.peek() == TokenKind::Class {
+            classes.push(self.class()?);
+        }
+        if *self.peek() != TokenKind::Void {
+            return self.error("`class` or `void`");
+        }
+        let line = self.advance().line;
+        let name = self.ident("a function name")?;
+        self.expect(TokenKind::LParen, "(")?;
+        self.expect(TokenKind::RParen, ")")?;
+        let body = self.block()?;
+        if *self.peek() != TokenKind::Eof {
+            return self.error("end of file");
+        }
+        Ok(Program {
+            classes,
+            main: Main { name, line, body },
+        })
+    }
+
+    fn class(&mut self) -> Result<ClassDef> {
+        let line = self.expect(TokenKind::Class, "class")?.line;
+        let name = self.ident("a class name")?;
+        self.expect(TokenKind::LBrace, "{")?;
+        let mut members = Vec::new();
+        while self.before_close()? {
+            members.push(self.member()?);
+        }
+        Ok(ClassDef {
+            name,
+            line,
+            members,
+        })
+    }
+
+    fn member(&mut self) -> Result<Member> {
+        let line = self.current().line;
+        if matches!(self.peek(), TokenKind::Ident(_)) && *self.peek_at(1) == TokenKind::LParen {
+            let name = self.ident("a constructor name")?;
+            let params = self.params()?;
+            let body = self.block()?;
+            return Ok(Member::Constructor(Constructor {
+                name,
+                params,
+                body,
+                line,
+            }));
+        }
+        let is_static = self.eat(&TokenKind::Static);
+        let visibility = match self.peek() {
+            TokenKind::Public => Visibility::Public,
+            TokenKind::Private => Visibility::Private,
+            _ if is_static => return self.error("`public` or `private`"),
+            _ => return self.error("a member declaration or `}`"),
+        };
+        self.advance();
+        let modifiers = Modifiers {
+            is_static,
+            visibility,
+        };
+        let ty = self.ty()?;
+        let name = self.ident("a member name")?;
+        if *self.peek() == TokenKind::LParen {
+            let params = self.params()?;
+            let body = self.block()?;
+            return Ok(Member::Method(Method {
+                modifiers,
+                ret: ty,
+                name,
+                params,
+                body,
+                line,
+            }));
+        }
+        let init = if self.eat(&TokenKind::Assign) {
+            Some(self.expr()?)
+        } else {
+            None
+        };
+        self.expect(TokenKind::Semi, ";")?;
+        Ok(Member::Field(Field {
+            modifiers,
+            ty,
+            name,
+            init,
+            line,
+        }))
+    }
+
+    fn params(&mut self) -> Result<Vec<Param>> {
+        self.expect(TokenKind::LParen, "(")?;
+        let mut params = Vec::new();
+        if self.eat(&TokenKind::RParen) {
+            return Ok(params);
+        }
+        loop {
+            let line = self.current().line;
+            let ty = self.ty()?;
+            let name = self.ident("a parameter name")?;
+            params.push(Param { ty, name, line });
+            if self.eat(&TokenKind::RParen) {
+                return Ok(params);
+            }
+            if !self.eat(&TokenKind::Comma) {
+                return self.error("`,` or `)`");
+            }
+        }
+    }
+
+    /// A type without any `[]`, if the next token starts one.
+    fn base_type(&self) -> Option<Type> {
+        Some(match self.peek() {
+            TokenKind::Void => Type::Void,
+            TokenKind::Int => Type::Int,
+            TokenKind::Char => Type::Char,
+            TokenKind::Bool => Type::Bool,
+            TokenKind::String => Type::String,
+            TokenKind::Ident(name) => Type::Class(name.clone()),
+            _ => return None,
+        })
+    }
+
+    fn ty(&mut self) -> Result<Type> {
+        let Some(mut ty) = self.base_type() else {
+            return self.error("a type");
+        };
+        self.advance();
+        while *self.peek() == TokenKind::LBracket {
+            self.advance();
+            self.expect(TokenKind::RBracket, "]")?;
+            ty = Type::Array(Box::new(ty));
+        }
+        Ok(ty)
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>> {
+        self.expect(TokenKind::LBrace, "{")?;
+        let mut body = Vec::new();
+        while self.before_close()? {
+            body.push(self.statement()?);
+        }
+        Ok(body)
+    }
+
+    /// Whether the statement ahead declares a variable: it starts with a
+    /// primitive type, a class name followed by the variable's name, or a
+    /// type followed by `[]`.
+    fn at_declaration(&self) -> bool {
+        match self.peek() {
+            TokenKind::Void
+            | TokenKind::Int
+            | TokenKind::Char
+            | TokenKind::Bool
+            | TokenKind::String => true,
+            TokenKind::Ident(_) => match self.peek_at(1) {
+                TokenKind::Ident(_) => true,
+                TokenKind::LBracket => *self.peek_at(2) == TokenKind::RBracket,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn statement(&mut self) -> Result<Stmt> {
+        let line = self.current().line;
+        let kind = match self.peek() {
+            TokenKind::LBrace => StmtKind::Block(self.block()?),
+            TokenKind::If => {
+                self.advance();
+                let cond = self.condition()?;
+                let then = Box::new(self.statement()?);
+                let otherwise = if self.eat(&TokenKind::Else) {
+                    Some(Box::new(self.statement()?))
+                } else {
+                    None
+                };
+                StmtKind::If {
+                    cond,
+                    then,
+                    otherwise,
+                }
+            }
+            TokenKind::While => {
+                self.advance();
+                let cond = self.condition()?;
+                let body = Box::new(self.statement()?);
+                StmtKind::While { cond, body }
+            }
+            TokenKind::For => {
+                self.advance();
+                self.expect(TokenKind::LParen, "(")?;
+                let init = self.optional_expr(TokenKind::Semi)?;
+                self.expect(TokenKind::Semi, ";")?;
+                let cond = self.optional_expr(TokenKind::Semi)?;
+                self.expect(TokenKind::Semi, ";")?;
+                let step = self.optional_expr(TokenKind::RParen)?;
+                self.expect(TokenKind::RParen, ")")?;
+                let body = Box::new(self.statement()?);
+                StmtKind::For {
+                    init,
+                    cond,
+                    step,
+                    body,
+                }
+            }
+            TokenKind::Return => {
+                self.advance();
+                let value = self.optional_expr(TokenKind::Semi)?;
+                self.expect(TokenKind::Semi, ";")?;
+                StmtKind::Return(value)
+            }
+            TokenKind::Break => {
+                self.advance();
+                self.expect(TokenKind::Semi, ";")?;
+                StmtKind::Break
+            }
+            TokenKind::Cin => {
+                self.advance();
+                self.expect(TokenKind::Extract, ">>")?;
+                let target = self.expr()?;
+                self.expect(TokenKind::Semi, ";")?;
+                StmtKind::Cin(target)
+            }
+            TokenKind::Cout => {
+                self.advance();
+                self.expect(TokenKind::Insert, "<<")?;
+                let value = self.expr()?;
+                self.expect(TokenKind::Semi, ";")?;
+                StmtKind::Cout(value)
+            }
+            TokenKind::Switch => self.switch()?,
+            _ if self.at_declaration() => {
+                let ty = self.ty()?;
+                let name = self.ident("a variable name")?;
+                let init = if self.eat(&TokenKind::Assign) {
+                    Some(self.expr()?)
+                } else {
+                    None
+                };
+                self.expect(TokenKind::Semi, ";")?;
+                StmtKind::Var { ty, name, init }
+            }
+            _ => {
+                let e = self.expr()?;
+                self.expect(TokenKind::Semi, ";")?;
+                StmtKind::Expr(e)
+            }
+        };
+        Ok(Stmt { line, kind })
+    }
+
+    /// `( expr )` after `if`, `while` and `switch`.
+    fn condition(&mut self) -> Result<Expr> {
+        self.expect(TokenKind::LParen, "(")?;
+        let cond = self.expr()?;
+        self.expect(TokenKind::RParen, ")")?;
+        Ok(cond)
+    }
+
+    fn optional_expr(&mut self, end: TokenKind) -> Result<Option<Expr>> {
+        if *self.peek() == end {
+            Ok(None)
+        } else {
+            self.expr().map(Some)
+        }
+    }
+
+    fn switch(&mut self) -> Result<StmtKind> {
+        self.expect(TokenKind::Switch, "switch")?;
+        let scrutinee = self.condition()?;
+        self.expect(TokenKind::LBrace, "{")?;
+        let mut cases = Vec::new();
+        while *self.peek() == TokenKind::Case {
+            let line = self.advance().line;
+            let label = match self.peek().clone() {
+                TokenKind::IntLit(n) => Literal::Int(n),
+                TokenKind::CharLit(c) => Literal::Char(c),
+                TokenKind::Minus => match *self.peek_at(1) {
+                    TokenKind::IntLit(n) => {
+                        self.advance();
+                        Literal::Int(-n)
+                    }
+                    _ => return self.error("an int or char literal"),
+                },
+                _ => return self.error("an int or char literal"),
+            };
+            self.advance();
+            self.expect(TokenKind::Colon, ":")?;
+            let body = self.case_body()?;
+            cases.push(Case { line, label, body });
+        }
+        let default = if self.eat(&TokenKind::Default) {
+            self.expect(TokenKind::Colon, ":")?;
+            Some(self.case_body()?)
+        } else {
+            None
+        };
+        if !self.eat(&TokenKind::RBrace) {
+            return self.error(if default.is_some() {
+                "`}`"
+            } else {
+                "`case`, `default` or `}`"
+            });
+        }
+        Ok(StmtKind::Switch {
+            scrutinee,
+            cases,
+            default,
+        })
+    }
+
+    fn case_body(&mut self) -> Result<Vec<Stmt>> {
+        let mut body = Vec::new();
+        while !matches!(
+            self.peek(),
+            TokenKind::Case | TokenKind::Default | TokenKind::RBrace | TokenKind::Eof
+        ) {
+            body.push(self.statement()?);
+        }
+        Ok(body)
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let target = self.binary(0)?;
+        if *self.peek() != TokenKind::Assign {
+            return Ok(target);
+        }
+        if !matches!(
+            target.kind,
+            ExprKind::Ident(_) | ExprKind::Member(..) | ExprKind::Index(..)
+        ) {
+            return self
+                .fail("only variables, members and array elements can be assigned to".into());
+        }
+        let op = self.advance();
+        let value = self.expr()?;
+        Ok(Expr {
+            line: op.line,
+            column: op.column,
+            kind: ExprKind::Assign(Box::new(target), Box::new(value)),
+        })
+    }
+
+    /// Binary operators, loosest first. Each level is left associative.
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        const LEVELS: &[&[(TokenKind, BinaryOp)]] = &[
+            &[(TokenKind::Or, BinaryOp::Or)],
+            &[(TokenKind::And, BinaryOp::And)],
+            &[
+                (TokenKind::Eq, BinaryOp::Eq),
+                (TokenKind::NotEq, BinaryOp::NotEq),
+            ],
+            &[
+                (TokenKind::Less, BinaryOp::Less),
+                (TokenKind::LessEq, BinaryOp::LessEq),
+                (TokenKind::Greater, BinaryOp::Greater),
+                (TokenKind::GreaterEq, BinaryOp::GreaterEq),
+            ],
+            &[
+                (TokenKind::Plus, BinaryOp::Add),
+                (TokenKind::Minus, BinaryOp::Sub),
+            ],
+            &[
+                (TokenKind::Star, BinaryOp::Mul),
+                (TokenKind::Slash, BinaryOp::Div),
+                (TokenKind::Percent, BinaryOp::Mod),
+            ],
+        ];
+        let Some(ops) = LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut lhs = self.binary(level + 1)?;
+        while let Some((_, op)) = ops.iter().find(|(kind, _)| kind == self.peek()) {
+            let token = self.advance();
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr {
+                line: token.line,
+                column: token.column,
+                kind: ExprKind::Binary(*op, Box::new(lhs), Box::new(rhs)),
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        let op = match self.peek() {
+            TokenKind::Not => UnaryOp::Not,
+            TokenKind::Minus => UnaryOp::Neg,
+            TokenKind::Plus => UnaryOp::Plus,
+            _ => return self.postfix(),
+        };
+        let token = self.advance();
+        let operand = self.unary()?;
+        Ok(Expr {
+            line: token.line,
+            column: token.column,
+            kind: ExprKind::Unary(op, Box::new(operand)),
+        })
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let mut e = self.primary()?;
+        loop {
+            let token = self.current().clone();
+            let kind = match token.kind {
+                TokenKind::Dot => {
+                    self.advance();
+                    let name = self.ident("a member name")?;
+                    ExprKind::Member(Box::new(e), name)
+                }
+                TokenKind::LParen => {
+                    if !matches!(e.kind, ExprKind::Ident(_) | ExprKind::Member(..)) {
+                        return self.fail("only methods can be called".into());
+                    }
+                    self.advance();
+                    let args = self.args()?;
+                    ExprKind::Call(Box::new(e), args)
+                }
+                TokenKind::LBracket => {
+                    self.advance();
+                    let index = self.expr()?;
+                    self.expect(TokenKind::RBracket, "]")?;
+                    ExprKind::Index(Box::new(e), Box::new(index))
+                }
+                _ => return Ok(e),
+            };
+            e = Expr {
+                line: token.line,
+                column: token.column,
+                kind,
+            };
+        }
+    }
+
+    /// Call arguments, after the opening `(`.
+    fn args(&mut self) -> Result<Vec<Expr>> {
+        let mut args = Vec::new();
+        if self.eat(&TokenKind::RParen) {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expr()?);
+            if self.eat(&TokenKind::RParen) {
+                return Ok(args);
+            }
+            if !self.eat(&TokenKind::Comma) {
+                return self.error("`,` or `)`");
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let token = self.current().clone();
+        let kind = match &token.kind {
+            TokenKind::IntLit(n) => ExprKind::Literal(Literal::Int(*n)),
+            TokenKind::CharLit(c) => ExprKind::Literal(Literal::Char(*c)),
+            TokenKind::StrLit(s) => ExprKind::Literal(Literal::Str(s.clone())),
+            TokenKind::True => ExprKind::Literal(Literal::Bool(true)),
+            TokenKind::False => ExprKind::Literal(Literal::Bool(false)),
+            TokenKind::Null => ExprKind::Literal(Literal::Null),
+            TokenKind::This => ExprKind::This,
+            TokenKind::Ident(name) => ExprKind::Ident(name.clone()),
+            TokenKind::LParen => {
+                self.advance();
+                let e = self.expr()?;
+                self.expect(TokenKind::RParen, ")")?;
+                return Ok(e);
+            }
+            TokenKind::New => {
+                self.advance();
+                return self.new_expr(token);
+            }
+            _ => return self.error("an expression"),
+        };
+        self.advance();
+        Ok(Expr {
+            line: token.line,
+            column: token.column,
+            kind,
+        })
+    }
+
+    /// The rest of `new Class(args)` or `new elem[size]`, after `new`.
+    fn new_expr(&mut self, new: Token) -> Result<Expr> {
+        let mut elem = match self.base_type() {
+            Some(Type::Void) | None => return self.error("a type"),
+            Some(ty) => ty,
+        };
+        self.advance();
+        let kind = match (&elem, self.peek()) {
+            (Type::Class(name), TokenKind::LParen) => {
+                let name = name.clone();
+                self.advance();
+                ExprKind::New(name, self.args()?)
+            }
+            (_, TokenKind::LBracket) => loop {
+                self.advance();
+                if self.eat(&TokenKind::RBracket) {
+                    elem = Type::Array(Box::new(elem));
+                    if *self.peek() != TokenKind::LBracket {
+                        return self.error("`[`");
+                    }
+                    continue;
+                }
+                let size = self.expr()?;
+                self.expect(TokenKind::RBracket, "]")?;
+                break ExprKind::NewArray(elem, Box::new(size));
+            },
+            (Type::Class(_), _) => return self.error("`(` or `[`"),
+            _ => return self.error("`[`"),
+        };
+        Ok(Expr {
+            line: new.line,
+            column: new.column,
+            kind,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expr_sexp(source: &str) -> String {
+        let program = parse(&format!("void main(){{ {}; }}", source)).unwrap();
+        match &program.main.body[0].kind {
+            StmtKind::Expr(e) => sexp::expr(e).pretty().trim_end().to_string(),
+            other => panic!("not an expression: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        assert_eq!(
+            expr_sexp("a = b = 1 + 2 * 3 - 4"),
+            "(= a (= b (- (+ 1 (* 2 3)) 4)))"
+        );
+        assert_eq!(
+            expr_sexp("!a || b && c == d < e"),
+            "(|| (! a) (&& b (== c (< d e))))"
+        );
+    }
+
+    #[test]
+    fn postfix_chains() {
+        assert_eq!(
+            expr_sexp("A.func().x[0][1]"),
+            "(index (index (. (call (. A func)) x) 0) 1)"
+        );
+    }
+
+    #[test]
+    fn array_creation_counts_empty_brackets() {
+        assert_eq!(expr_sexp("new int[][][10]"), "(new-array int[][] 10)");
+        assert_eq!(expr_sexp("new MyClass[n]"), "(new-array MyClass n)");
+        assert_eq!(expr_sexp("new MyClass(1, 'c')"), "(new MyClass 1 'c')");
+    }
+
+    #[test]
+    fn declarations_versus_expressions() {
+        let program = parse("void main(){ A b; A[] c; a[0] = 1; a = 2; }").unwrap();
+        let kinds: Vec<bool> = program
+            .main
+            .body
+            .iter()
+            .map(|s| matches!(s.kind, StmtKind::Var { .. }))
+            .collect();
+        assert_eq!(kinds, vec![true, true, false, false]);
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_token() {
+        let err = parse("void main(){\n    int x = ;\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 13));
+        assert_eq!(err.message, "expected an expression, found `;`");
+    }
+
+    #[test]
+    fn only_lvalues_are_assigned() {
+        assert!(parse("void main(){ 1 = 2; }").is_err());
+        assert!(parse("void main(){ f() = 2; }").is_err());
+    }
+}
@@ -0,0 +1,400 @@
+//! Writes the syntax tree back out as KXI source.
+//!
+//! ```text
+//! class MyClass {
+//!     static public int x = 4;
+//!
+//!     public int get(int a) {
+//!         return x + a;
+//!     }
+//! }
+//!
+//! void main() {
+//!     MyClass c = new MyClass();
+//!     c.get(1);
+//! }
+//! ```
+//!
+//! Statements are indented by four spaces and expressions get only the
+//! parentheses their precedence needs, so parsing the output gives back
+//! the same tree apart from line numbers. The exceptions are negative `int`
+//! literals, which come back as a negation of a positive one, and an `if`
+//! without `else` in the unbraced branch of one with, which is put in a
+//! block so the `else` stays where it was.
+
+use std::fmt::Write as _;
+
+use crate::ast::*;
+
+const INDENT: &str = "    ";
+
+pub fn program(p: &Program) -> String {
+    let mut out = String::new();
+    for class in &p.classes {
+        writeln!(out, "class {} {{", class.name).unwrap();
+        for (i, m) in class.members.iter().enumerate() {
+            if i > 0 && !matches!(m, Member::Field(_)) {
+                out.push('\n');
+            }
+            member(&mut out, m);
+        }
+        out.push_str("}\n\n");
+    }
+    write!(out, "void {}() ", p.main.name).unwrap();
+    block(&mut out, &p.main.body, 0);
+    out.push('\n');
+    out
+}
+
+/// Writes a single statement, with its first line unindented.
+pub fn statement(s: &Stmt) -> String {
+    let mut out = String::new();
+    stmt(&mut out, s, 0);
+    out
+}
+
+fn member(out: &mut String, m: &Member) {
+    out.push_str(INDENT);
+    match m {
+        Member::Field(f) => {
+            write!(out, "{}{} {}", modifiers(f.modifiers), f.ty, f.name).unwrap();
+            if let Some(init) = &f.init {
+                write!(out, " = {}", expr(init)).unwrap();
+            }
+            out.push_str(";\n");
+        }
+        Member::Method(m) => {
+            let head = format!("{}{} {}", modifiers(m.modifiers), m.ret, m.name);
+            write!(out, "{}({}) ", head, params(&m.params)).unwrap();
+            block(out, &m.body, 1);
+            out.push('\n');
+        }
+        Member::Constructor(c) => {
+            write!(out, "{}({}) ", c.name, params(&c.params)).unwrap();
+            block(out, &c.body, 1);
+            out.push('\n');
+        }
+    }
+}
+
+fn modifiers(m: Modifiers) -> String {
+    let visibility = match m.visibility {
+        Visibility::Public => "public",
+        Visibility::Private => "private",
+    };
+    if m.is_static {
+        format!("static {} ", visibility)
+    } else {
+        format!("{} ", visibility)
+    }
+}
+
+fn params(ps: &[Param]) -> String {
+    let ps: Vec<String> = ps.iter().map(|p| format!("{} {}", p.ty, p.name)).collect();
+    ps.join(", ")
+}
+
+/// Writes `{`, the statements and the closing `}` at `depth`, without a
+/// newline after it.
+fn block(out: &mut String, body: &[Stmt], depth: usize) {
+    out.push_str("{\n");
+    for s in body {
+        stmt(out, s, depth + 1);
+    }
+    out.push_str(&INDENT.repeat(depth));
+    out.push('}');
+}
+
+/// Writes a statement on lines of its own, indented to `depth`.
+fn stmt(out: &mut String, s: &Stmt, depth: usize) {
+    out.push_str(&INDENT.repeat(depth));
+    match &s.kind {
+        StmtKind::Block(body) => block(out, body, depth),
+        StmtKind::Var { ty, name, init } => {
+            write!(out, "{} {}", ty, name).unwrap();
+            if let Some(init) = init {
+                write!(out, " = {}", expr(init)).unwrap();
+            }
+            out.push(';');
+        }
+        StmtKind::Expr(e) => write!(out, "{};", expr(e)).unwrap(),
+        StmtKind::If {
+            cond,
+            then,
+            otherwise,
+        } => {
+            write!(out, "if ({})", expr(cond)).unwrap();
+            match otherwise {
+                Some(otherwise) if !matches!(then.kind, StmtKind::Block(_)) && dangles(then) => {
+                    out.push(' ');
+                    block(out, std::slice::from_ref(then), depth);
+                }
+                _ => body(out, then, depth),
+            }
+            if let Some(otherwise) = otherwise {
+                if matches!(then.kind, StmtKind::Block(_)) || dangles(then) {
+                    out.push_str(" else");
+                } else {
+                    out.push('\n');
+                    out.push_str(&INDENT.repeat(depth));
+                    out.push_str("else");
+                }
+                if let StmtKind::If { .. } = otherwise.kind {
+                    out.push(' ');
+                    let mut nested = String::new();
+                    stmt(&mut nested, otherwise, depth);
+                    out.push_str(nested.trim_start());
+                    return;
+                }
+                body(out, otherwise, depth);
+            }
+        }
+        StmtKind::While { cond, body: b } => {
+            write!(out, "while ({})", expr(cond)).unwrap();
+            body(out, b, depth);
+        }
+        StmtKind::For {
+            init,
+            cond,
+            step,
+            body: b,
+        } => {
+            out.push_str("for (");
+            if let Some(init) = init {
+                out.push_str(&expr(init));
+            }
+            out.push(';');
+            if let Some(cond) = cond {
+                write!(out, " {}", expr(cond)).unwrap();
+            }
+            out.push(';');
+            if let Some(step) = step {
+                write!(out, " {}", expr(step)).unwrap();
+            }
+            out.push(')');
+            body(out, b, depth);
+        }
+        StmtKind::Return(None) => out.push_str("return;"),
+        StmtKind::Return(Some(e)) => write!(out, "return {};", expr(e)).unwrap(),
+        StmtKind::Cin(e) => write!(out, "cin >> {};", expr(e)).unwrap(),
+        StmtKind::Cout(e) => write!(out, "cout << {};", expr(e)).unwrap(),
+        StmtKind::Switch {
+            scrutinee,
+            cases,
+            default,
+        } => {
+            writeln!(out, "switch ({}) {{", expr(scrutinee)).unwrap();
+            let labels = cases
+                .iter()
+                .map(|c| (format!("case {}:", c.label), &c.body))
+                .chain(default.iter().map(|d| ("default:".to_string(), d)));
+            for (label, body) in labels {
+                writeln!(out, "{}{}", INDENT.repeat(depth + 1), label).unwrap();
+                for s in body {
+                    stmt(out, s, depth + 2);
+                }
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push('}');
+        }
+        StmtKind::Break => out.push_str("break;"),
+    }
+    // Statements that end in a nested `if` have already written their
+    // newline.
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Writes the body of an `if`, `else`, `while` or `for`: a block on the
+/// same line, anything else on the next line one level deeper.
+fn body(out: &mut String, s: &Stmt, depth: usize) {
+    if let StmtKind::Block(body) = &s.kind {
+        out.push(' ');
+        block(out, body, depth);
+    } else {
+        out.push('\n');
+        stmt(out, s, depth + 1);
+        // `stmt` ends with a newline the caller adds itself.
+        out.pop();
+    }
+}
+
+/// Whether an `else` written after `s` would be taken for the `else` of an
+/// `if` inside it.
+fn dangles(s: &Stmt) -> bool {
+    match &s.kind {
+        StmtKind::If {
+            otherwise: None, ..
+        } => true,
+        StmtKind::If {
+            otherwise: Some(s), ..
+        }
+        | StmtKind::While { body: s, .. }
+        | StmtKind::For { body: s, .. } => dangles(s),
+        _ => false,
+    }
+}
+
+/// How tightly an expression binds. An operand is put in parentheses if it
+/// binds less tightly than its position needs.
+fn precedence(e: &Expr) -> u8 {
+    match &e.kind {
+        ExprKind::Assign(..) => 0,
+        ExprKind::Binary(op, ..) => match op {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq | BinaryOp::NotEq => 3,
+            BinaryOp::Less | BinaryOp::LessEq | BinaryOp::Greater | BinaryOp::GreaterEq => 4,
+            BinaryOp::Add | BinaryOp::Sub => 5,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 6,
+        },
+        // `-1` is read back as a negation, and `new int[3][0]` would index
+        // the new array.
+        ExprKind::Unary(..) | ExprKind::NewArray(..) => 7,
+        ExprKind::Literal(Literal::Int(n)) if *n < 0 => 7,
+        ExprKind::Member(..) | ExprKind::Call(..) | ExprKind::Index(..) | ExprKind::New(..) => 8,
+        ExprKind::Literal(_) | ExprKind::Ident(_) | ExprKind::This => 9,
+    }
+}
+
+/// Writes an expression on one line.
+pub fn expr(e: &Expr) -> String {
+    let mut out = String::new();
+    write_expr(&mut out, e, 0);
+    out
+}
+
+fn write_expr(out: &mut String, e: &Expr, min: u8) {
+    let own = precedence(e);
+    if own < min {
+        out.push('(');
+    }
+    match &e.kind {
+        ExprKind::Literal(l) => write!(out, "{}", l).unwrap(),
+        ExprKind::Ident(name) => out.push_str(name),
+        ExprKind::This => out.push_str("this"),
+        ExprKind::Unary(op, operand) => {
+            out.push_str(op.as_str());
+            write_expr(out, operand, 7);
+        }
+        ExprKind::Binary(op, lhs, rhs) => {
+            write_expr(out, lhs, own);
+            write!(out, " {} ", op.as_str()).unwrap();
+            write_expr(out, rhs, own + 1);
+        }
+        ExprKind::Assign(target, value) => {
+            write_expr(out, target, 1);
+            out.push_str(" = ");
+            write_expr(out, value, 0);
+        }
+        ExprKind::Member(object, name) => {
+            write_expr(out, object, 8);
+            write!(out, ".{}", name).unwrap();
+        }
+        ExprKind::Call(callee, args) => {
+            write_expr(out, callee, 8);
+            write_args(out, args);
+        }
+        ExprKind::Index(array, index) => {
+            write_expr(out, array, 8);
+            out.push('[');
+            write_expr(out, index, 0);
+            out.push(']');
+        }
+        ExprKind::New(class, args) => {
+            write!(out, "new {}", class).unwrap();
+            write_args(out, args);
+        }
+        ExprKind::NewArray(elem, size) => {
+            write!(out, "new {}[", elem).unwrap();
+            write_expr(out, size, 0);
+            out.push(']');
+        }
+    }
+    if own < min {
+        out.push(')');
+    }
+}
+
+fn write_args(out: &mut String, args: &[Expr]) {
+    out.push('(');
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_expr(out, arg, 0);
+    }
+    out.push(')');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    /// Prints the program in `source` and checks that the printed program
+    /// parses to the same tree.
+    fn round_trip(source: &str) -> String {
+        let program = parse(source).unwrap();
+        let printed = super::program(&program);
+        let reparsed = parse(&printed).unwrap_or_else(|e| panic!("{}\n{}", e, printed));
+        assert_eq!(
+            crate::sexp::program(&reparsed),
+            crate::sexp::program(&program),
+            "{}",
+            printed
+        );
+        printed
+    }
+
+    #[test]
+    fn writes_only_the_parentheses_needed() {
+        let printed = round_trip(
+            "void main(){ x = (a + b) * c - (d - e) + f.g(1, (2)).h[0]; \
+             y = !(a && b) || (c == d) < e; z = a = b; }",
+        );
+        assert!(printed.contains("x = (a + b) * c - (d - e) + f.g(1, 2).h[0];"));
+        assert!(printed.contains("y = !(a && b) || (c == d) < e;"));
+        assert!(printed.contains("z = a = b;"));
+    }
+
+    #[test]
+    fn writes_declarations_and_statements() {
+        let printed = round_trip(
+            "class A { static public int x = 4; private A[] as; A(int a) { x = a; } \
+             public char f(char c, int[][] n) { switch (c) { case 'a': return 'b'; \
+             case -1: break; default: } return c; } } \
+             void main() { int[][] a = new int[][3]; for (;;) break; \
+             for (i = 0; i < 3; i = i + 1) { cout << \"a\\n\"; } \
+             if (a == null) if (b) cin >> x; else x = 1; \
+             if (a) { } else if (b) x = 2; else { x = 3; } \
+             while (true) { { x = new A(1).x; } } }",
+        );
+        assert!(
+            printed.contains("    static public int x = 4;\n    private A[] as;\n\n    A(int a) {")
+        );
+        assert!(printed.contains("int[][] a = new int[][3];"));
+        assert!(printed.contains("    if (a == null)\n        if (b)"));
+        assert!(printed.contains("} else if (b)\n        x = 2;\n    else {"));
+    }
+
+    #[test]
+    fn keeps_a_dangling_else_with_its_if() {
+        let braced = parse("void main() { if (a) { if (b) x = 1; } else x = 2; }").unwrap();
+        let mut program = braced.clone();
+        let StmtKind::If { then, .. } = &mut program.main.body[0].kind else {
+            unreachable!()
+        };
+        let StmtKind::Block(inner) = &mut then.kind else {
+            unreachable!()
+        };
+        **then = inner.remove(0);
+        let printed = super::program(&program);
+        assert!(printed.contains("if (a) {\n        if (b)\n            x = 1;\n    } else\n"));
+        assert_eq!(
+            crate::sexp::program(&parse(&printed).unwrap()),
+            crate::sexp::program(&braced)
+        );
+    }
+}
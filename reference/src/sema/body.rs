@@ -0,0 +1,718 @@
+//! Walks method bodies and initializers for the resolve and type-check
+//! passes.
+//!
+//! Both passes need the type of every expression: resolving `c.x` means
+//! knowing the class of `c`. So one walker computes types and names alike,
+//! and each pass keeps only its own errors.
+
+use std::collections::HashMap;
+
+use crate::ast::*;
+
+use super::table::{ClassInfo, Table};
+use super::types::Ty;
+use super::{ErrorKind, SemanticError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Mode {
+    Resolve,
+    TypeCheck,
+}
+
+pub(super) fn check(program: &Program, table: &Table, mode: Mode) -> Vec<SemanticError> {
+    let mut walker = Walker {
+        table,
+        mode,
+        class: None,
+        is_static: true,
+        ret: Ty::Void,
+        scopes: Vec::new(),
+        breakable: 0,
+        errors: Vec::new(),
+    };
+    for class in &program.classes {
+        walker.class(class);
+    }
+    walker.enter(None, true, Ty::Void);
+    walker.body(&[], &program.main.body);
+    walker.errors
+}
+
+struct Walker<'a> {
+    table: &'a Table,
+    mode: Mode,
+    /// The class whose members are being checked, `None` in `main`.
+    class: Option<&'a ClassInfo>,
+    /// Whether `this` and instance members are out of reach.
+    is_static: bool,
+    /// What `return` must return.
+    ret: Ty,
+    scopes: Vec<HashMap<String, Ty>>,
+    /// Number of enclosing loops and switches.
+    breakable: usize,
+    errors: Vec<SemanticError>,
+}
+
+impl<'a> Walker<'a> {
+    fn resolve_error(&mut self, line: usize, kind: ErrorKind, message: String) {
+        if self.mode == Mode::Resolve {
+            self.errors.push(SemanticError::new(line, kind, message));
+        }
+    }
+
+    fn type_error(&mut self, line: usize, kind: ErrorKind, message: String) {
+        if self.mode == Mode::TypeCheck {
+            self.errors.push(SemanticError::new(line, kind, message));
+        }
+    }
+
+    /// Reports a type mismatch unless `expected` accepts `actual`.
+    fn expect(&mut self, line: usize, expected: &Ty, actual: &Ty, what: &str) {
+        if !expected.accepts(actual) {
+            self.type_error(
+                line,
+                ErrorKind::TypeMismatch,
+                format!("{} must be {}, found {}", what, expected, actual),
+            );
+        }
+    }
+
+    fn enter(&mut self, class: Option<&'a ClassInfo>, is_static: bool, ret: Ty) {
+        self.class = class;
+        self.is_static = is_static;
+        self.ret = ret;
+        self.scopes.clear();
+        self.breakable = 0;
+    }
+
+    fn class(&mut self, class: &ClassDef) {
+        let Some(info) = self.table.class(&class.name) else {
+            return;
+        };
+        // A second class of the same name was reported by the declare pass.
+        if info.line != class.line {
+            return;
+        }
+        for member in &class.members {
+            match member {
+                Member::Field(f) => {
+                    self.enter(Some(info), f.modifiers.is_static, Ty::Void);
+                    self.check_type(f.line, &f.ty);
+                    if let Some(init) = &f.init {
+                        let ty = self.expr(init);
+                        self.expect(f.line, &Ty::from(&f.ty), &ty, "the initializer");
+                    }
+                }
+                Member::Method(m) => {
+                    self.enter(Some(info), m.modifiers.is_static, Ty::from(&m.ret));
+                    self.check_type(m.line, &m.ret);
+                    self.body(&m.params, &m.body);
+                    if m.ret != Type::Void && !returns(&m.body) {
+                        self.type_error(
+                            m.line,
+                            ErrorKind::MissingReturn,
+                            format!("{} may end without returning {}", m.name, m.ret),
+                        );
+                    }
+                }
+                Member::Constructor(c) => {
+                    self.enter(Some(info), false, Ty::Void);
+                    self.body(&c.params, &c.body);
+                }
+            }
+        }
+    }
+
+    /// Checks that every class named by `ty` exists, and returns whether
+    /// they do.
+    fn check_type(&mut self, line: usize, ty: &Type) -> bool {
+        match ty {
+            Type::Class(name) if self.table.class(name).is_none() => {
+                self.resolve_error(
+                    line,
+                    ErrorKind::UnknownType,
+                    format!("there is no class {}", name),
+                );
+                false
+            }
+            Type::Array(elem) => self.check_type(line, elem),
+            _ => true,
+        }
+    }
+
+    /// A method or constructor body. Parameters share the body's scope, so a
+    /// local may not reuse a parameter's name.
+    fn body(&mut self, params: &[Param], body: &[Stmt]) {
+        self.scopes.push(HashMap::new());
+        for p in params {
+            self.check_type(p.line, &p.ty);
+            self.declare(p.line, &p.name, Ty::from(&p.ty));
+        }
+        self.stmts(body);
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, line: usize, name: &str, ty: Ty) {
+        let scope = self.scopes.last_mut().expect("no scope");
+        if scope.contains_key(name) {
+            self.resolve_error(
+                line,
+                ErrorKind::DuplicateDeclaration,
+                format!("{} is already declared in this scope", name),
+            );
+        } else {
+            scope.insert(name.to_string(), ty);
+        }
+    }
+
+    fn lookup_local(&self, name: &str) -> Option<&Ty> {
+        self.scopes.iter().rev().find_map(|s| s.get(name))
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt]) {
+        for s in stmts {
+            self.stmt(s);
+        }
+    }
+
+    /// A statement that is the body of an `if` or a loop gets its own scope
+    /// even without braces.
+    fn scoped(&mut self, s: &Stmt) {
+        self.scopes.push(HashMap::new());
+        self.stmt(s);
+        self.scopes.pop();
+    }
+
+    fn condition(&mut self, cond: &Expr) {
+        let ty = self.expr(cond);
+        self.expect(cond.line, &Ty::Bool, &ty, "the condition");
+    }
+
+    fn stmt(&mut self, s: &Stmt) {
+        match &s.kind {
+            StmtKind::Block(body) => {
+                self.scopes.push(HashMap::new());
+                self.stmts(body);
+                self.scopes.pop();
+            }
+            StmtKind::Var { ty, name, init } => {
+                self.check_type(s.line, ty);
+                let declared = Ty::from(ty);
+                if declared == Ty::Void {
+                    self.type_error(
+                        s.line,
+                        ErrorKind::TypeMismatch,
+                        format!("variable {} cannot be void", name),
+                    );
+                }
+                if let Some(init) = init {
+                    let value = self.expr(init);
+                    self.expect(s.line, &declared, &value, "the initializer");
+                }
+                self.declare(s.line, name, declared);
+            }
+            StmtKind::Expr(e) => {
+                self.expr(e);
+            }
+            StmtKind::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                self.condition(cond);
+                self.scoped(then);
+                if let Some(otherwise) = otherwise {
+                    self.scoped(otherwise);
+                }
+            }
+            StmtKind::While { cond, body } => {
+                self.condition(cond);
+                self.breakable += 1;
+                self.scoped(body);
+                self.breakable -= 1;
+            }
+            StmtKind::For {
+                init,
+                cond,
+                step,
+                body,
+            } => {
+                if let Some(init) = init {
+                    self.expr(init);
+                }
+                if let Some(cond) = cond {
+                    self.condition(cond);
+                }
+                if let Some(step) = step {
+                    self.expr(step);
+                }
+                self.breakable += 1;
+                self.scoped(body);
+                self.breakable -= 1;
+            }
+            StmtKind::Return(value) => {
+                let ret = self.ret.clone();
+                match value {
+                    Some(value) => {
+                        let ty = self.expr(value);
+                        if ret == Ty::Void {
+                            self.type_error(
+                                s.line,
+                                ErrorKind::TypeMismatch,
+                                "a void method cannot return a value".into(),
+                            );
+                        } else {
+                            self.expect(s.line, &ret, &ty, "the returned value");
+                        }
+                    }
+                    None if ret != Ty::Void => self.type_error(
+                        s.line,
+                        ErrorKind::TypeMismatch,
+                        format!("the method must return {}", ret),
+                    ),
+                    None => {}
+                }
+            }
+            StmtKind::Cin(target) => {
+                let ty = self.expr(target);
+                if !is_place(target) {
+                    self.type_error(
+                        s.line,
+                        ErrorKind::TypeMismatch,
+                        "cin can only read into a variable, member or element".into(),
+                    );
+                } else if !matches!(ty, Ty::Int | Ty::Char | Ty::Unknown) {
+                    self.type_error(
+                        s.line,
+                        ErrorKind::TypeMismatch,
+                        format!("cin reads int or char, not {}", ty),
+                    );
+                }
+            }
+            StmtKind::Cout(value) => {
+                let ty = self.expr(value);
+                if !matches!(ty, Ty::Int | Ty::Char | Ty::String | Ty::Unknown) {
+                    self.type_error(
+                        s.line,
+                        ErrorKind::TypeMismatch,
+                        format!("cout writes int, char or string, not {}", ty),
+                    );
+                }
+            }
+            StmtKind::Switch {
+                scrutinee,
+                cases,
+                default,
+            } => self.switch(s.line, scrutinee, cases, default.as_deref()),
+            StmtKind::Break => {
+                if self.breakable == 0 {
+                    self.resolve_error(
+                        s.line,
+                        ErrorKind::MisplacedBreak,
+                        "break outside of a loop or switch".into(),
+                    );
+                }
+            }
+        }
+    }
+
+    /// The switch must be on an int or a char, every label must have that
+    /// type, and the labels must all have the same type. The last rule is
+    /// reported on the `switch` line, the others on the offending line.
+    fn switch(&mut self, line: usize, scrutinee: &Expr, cases: &[Case], default: Option<&[Stmt]>) {
+        let ty = self.expr(scrutinee);
+        let valid = matches!(ty, Ty::Int | Ty::Char);
+        if !valid && !ty.is_unknown() {
+            self.type_error(
+                line,
+                ErrorKind::TypeMismatch,
+                format!("switch needs an int or a char, found {}", ty),
+            );
+        }
+        let labels: Vec<Ty> = cases.iter().map(|c| Ty::of_literal(&c.label)).collect();
+        if valid {
+            for (case, label) in cases.iter().zip(&labels) {
+                self.expect(case.line, &ty, label, "the case label");
+            }
+        }
+        if labels.windows(2).any(|w| w[0] != w[1]) {
+            self.type_error(
+                line,
+                ErrorKind::TypeMismatch,
+                "the case labels have different types".into(),
+            );
+        }
+        self.scopes.push(HashMap::new());
+        self.breakable += 1;
+        for case in cases {
+            self.stmts(&case.body);
+        }
+        if let Some(default) = default {
+            self.stmts(default);
+        }
+        self.breakable -= 1;
+        self.scopes.pop();
+    }
+
+    fn expr(&mut self, e: &Expr) -> Ty {
+        match &e.kind {
+            ExprKind::Literal(l) => Ty::of_literal(l),
+            ExprKind::Ident(name) => match self.ident(e.line, name) {
+                Ty::Static(class) => {
+                    self.resolve_error(
+                        e.line,
+                        ErrorKind::UndeclaredIdentifier,
+                        format!("{} is a class, not a variable", class),
+                    );
+                    Ty::Unknown
+                }
+                ty => ty,
+            },
+            ExprKind::This => match self.class {
+                Some(class) if !self.is_static => Ty::Class(class.name.clone()),
+                _ => {
+                    self.resolve_error(
+                        e.line,
+                        ErrorKind::InvalidThis,
+                        "this can only be used in instance members".into(),
+                    );
+                    Ty::Unknown
+                }
+            },
+            ExprKind::Unary(op, operand) => {
+                let ty = self.expr(operand);
+                let expected = match op {
+                    UnaryOp::Not => Ty::Bool,
+                    UnaryOp::Neg | UnaryOp::Plus => Ty::Int,
+                };
+                self.operand(operand.line, op.as_str(), &expected, ty)
+                    .unwrap_or(expected)
+            }
+            ExprKind::Binary(op, lhs, rhs) => self.binary(e.line, *op, lhs, rhs),
+            ExprKind::Assign(target, value) => {
+                let target_ty = self.expr(target);
+                let value_ty = self.expr(value);
+                self.expect(e.line, &target_ty, &value_ty, "the assigned value");
+                target_ty
+            }
+            ExprKind::Member(object, name) => {
+                let object = self.object(object);
+                self.field(e.line, &object, name)
+            }
+            ExprKind::Call(callee, args) => self.call(e.line, callee, args),
+            ExprKind::Index(array, index) => {
+                let array_ty = self.expr(array);
+                let index_ty = self.expr(index);
+                self.expect(index.line, &Ty::Int, &index_ty, "an index");
+                match array_ty {
+                    Ty::Array(elem) => *elem,
+                    Ty::Unknown => Ty::Unknown,
+                    other => {
+                        self.type_error(
+                            e.line,
+                            ErrorKind::TypeMismatch,
+                            format!("only arrays can be indexed, not {}", other),
+                        );
+                        Ty::Unknown
+                    }
+                }
+            }
+            ExprKind::New(class, args) => {
+                let params = match self.table.class(class) {
+                    Some(info) => Some(info.constructor.clone().unwrap_or_default()),
+                    None => {
+                        self.resolve_error(
+                            e.line,
+                            ErrorKind::UnknownType,
+                            format!("there is no class {}", class),
+                        );
+                        None
+                    }
+                };
+                self.args(e.line, params.as_deref(), args);
+                match params {
+                    Some(_) => Ty::Class(class.clone()),
+                    None => Ty::Unknown,
+                }
+            }
+            ExprKind::NewArray(elem, size) => {
+                let known = self.check_type(e.line, elem);
+                let size_ty = self.expr(size);
+                self.expect(size.line, &Ty::Int, &size_ty, "an array size");
+                if known {
+                    Ty::Array(Box::new(Ty::from(elem)))
+                } else {
+                    Ty::Unknown
+                }
+            }
+        }
+    }
+
+    /// Checks one operand against the type it must have. Returns the
+    /// offending type so the result of the operation carries the mistake
+    /// along: `x + 2` with a char `x` is itself a char.
+    fn operand(&mut self, line: usize, op: &str, expected: &Ty, actual: Ty) -> Option<Ty> {
+        if expected.accepts(&actual) {
+            return None;
+        }
+        self.type_error(
+            line,
+            ErrorKind::TypeMismatch,
+            format!("{} needs {}, found {}", op, expected, actual),
+        );
+        Some(actual)
+    }
+
+    fn binary(&mut self, line: usize, op: BinaryOp, lhs: &Expr, rhs: &Expr) -> Ty {
+        let l = self.expr(lhs);
+        let r = self.expr(rhs);
+        let sym = op.as_str();
+        match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                let bad_l = self.operand(lhs.line, sym, &Ty::Int, l);
+                let bad_r = self.operand(rhs.line, sym, &Ty::Int, r);
+                bad_l.or(bad_r).unwrap_or(Ty::Int)
+            }
+            BinaryOp::And | BinaryOp::Or => {
+                self.operand(lhs.line, sym, &Ty::Bool, l);
+                self.operand(rhs.line, sym, &Ty::Bool, r);
+                Ty::Bool
+            }
+            BinaryOp::Less | BinaryOp::LessEq | BinaryOp::Greater | BinaryOp::GreaterEq => {
+                let ordered = |t: &Ty| matches!(t, Ty::Int | Ty::Char | Ty::Unknown);
+                let mut ok = true;
+                for (operand, ty) in [(lhs, &l), (rhs, &r)] {
+                    if !ordered(ty) {
+                        ok = false;
+                        self.type_error(
+                            operand.line,
+                            ErrorKind::TypeMismatch,
+                            format!("{} compares ints or chars, found {}", sym, ty),
+                        );
+                    }
+                }
+                if ok && !l.comparable(&r) {
+                    self.type_error(
+                        line,
+                        ErrorKind::TypeMismatch,
+                        format!("cannot compare {} with {}", l, r),
+                    );
+                }
+                Ty::Bool
+            }
+            BinaryOp::Eq | BinaryOp::NotEq => {
+                if !l.comparable(&r) {
+                    self.type_error(
+                        line,
+                        ErrorKind::TypeMismatch,
+                        format!("cannot compare {} with {}", l, r),
+                    );
+                }
+                Ty::Bool
+            }
+        }
+    }
+
+    /// A bare name: a local, a member of the current class or a class.
+    fn ident(&mut self, line: usize, name: &str) -> Ty {
+        if let Some(ty) = self.lookup_local(name) {
+            return ty.clone();
+        }
+        if let Some(field) = self.class.and_then(|c| c.fields.get(name)) {
+            let ty = Ty::from(&field.ty);
+            if self.is_static && !field.is_static {
+                self.resolve_error(
+                    line,
+                    ErrorKind::StaticContext,
+                    format!("instance field {} used in a static context", name),
+                );
+            }
+            return ty;
+        }
+        if self.table.class(name).is_some() {
+            return Ty::Static(name.to_string());
+        }
+        self.resolve_error(
+            line,
+            ErrorKind::UndeclaredIdentifier,
+            format!("{} is not declared", name),
+        );
+        Ty::Unknown
+    }
+
+    /// The type of the object before a `.`, the one place a class name
+    /// may stand on its own.
+    fn object(&mut self, e: &Expr) -> Ty {
+        match &e.kind {
+            ExprKind::Ident(name) => self.ident(e.line, name),
+            _ => self.expr(e),
+        }
+    }
+
+    /// The class whose members `object` gives access to, and whether only
+    /// its static members are reachable. Reports `what` being looked up on
+    /// anything else.
+    fn receiver(&mut self, line: usize, object: &Ty, what: &str) -> Option<(&'a ClassInfo, bool)> {
+        let (name, is_static) = match object {
+            Ty::Unknown => return None,
+            Ty::Class(name) => (name, false),
+            Ty::Static(name) => (name, true),
+            other => {
+                self.resolve_error(
+                    line,
+                    ErrorKind::UnknownMember,
+                    format!("{} has no member {}", other, what),
+                );
+                return None;
+            }
+        };
+        // Types of declared variables were checked where they were declared.
+        self.table.class(name).map(|class| (class, is_static))
+    }
+
+    /// Reports private members used outside their class and instance
+    /// members used through the class name.
+    fn check_access(
+        &mut self,
+        line: usize,
+        class: &ClassInfo,
+        name: &str,
+        through_class: bool,
+        member: (bool, Visibility),
+    ) {
+        let (is_static, visibility) = member;
+        if visibility == Visibility::Private && self.class.map(|c| &c.name) != Some(&class.name) {
+            self.resolve_error(
+                line,
+                ErrorKind::PrivateAccess,
+                format!("{}.{} is private", class.name, name),
+            );
+        }
+        if through_class && !is_static {
+            self.resolve_error(
+                line,
+                ErrorKind::StaticContext,
+                format!("{}.{} is not static", class.name, name),
+            );
+        }
+    }
+
+    fn field(&mut self, line: usize, object: &Ty, name: &str) -> Ty {
+        let Some((class, through_class)) = self.receiver(line, object, name) else {
+            return Ty::Unknown;
+        };
+        let Some(field) = class.fields.get(name) else {
+            self.resolve_error(
+                line,
+                ErrorKind::UnknownMember,
+                format!("{} has no field {}", class.name, name),
+            );
+            return Ty::Unknown;
+        };
+        let access = (field.is_static, field.visibility);
+        self.check_access(line, class, name, through_class, access);
+        Ty::from(&field.ty)
+    }
+
+    fn call(&mut self, line: usize, callee: &Expr, args: &[Expr]) -> Ty {
+        let method = match &callee.kind {
+            ExprKind::Ident(name) => self.bare_method(line, name),
+            ExprKind::Member(object, name) => {
+                let object = self.object(object);
+                self.member_method(line, &object, name)
+            }
+            _ => None,
+        };
+        match method {
+            Some((params, ret)) => {
+                self.args(line, Some(&params), args);
+                Ty::from(&ret)
+            }
+            None => {
+                self.args(line, None, args);
+                Ty::Unknown
+            }
+        }
+    }
+
+    /// `name(...)`: a method of the current class, or the main function.
+    fn bare_method(&mut self, line: usize, name: &str) -> Option<(Vec<Type>, Type)> {
+        if let Some(method) = self.class.and_then(|c| c.methods.get(name)) {
+            if self.is_static && !method.is_static {
+                self.resolve_error(
+                    line,
+                    ErrorKind::StaticContext,
+                    format!("instance method {} called in a static context", name),
+                );
+            }
+            return Some((method.params.clone(), method.ret.clone()));
+        }
+        if name == self.table.main {
+            return Some((Vec::new(), Type::Void));
+        }
+        self.resolve_error(
+            line,
+            ErrorKind::UndeclaredIdentifier,
+            format!("there is no method {}", name),
+        );
+        None
+    }
+
+    /// `object.name(...)`.
+    fn member_method(&mut self, line: usize, object: &Ty, name: &str) -> Option<(Vec<Type>, Type)> {
+        let (class, through_class) = self.receiver(line, object, name)?;
+        let Some(method) = class.methods.get(name) else {
+            self.resolve_error(
+                line,
+                ErrorKind::UnknownMember,
+                format!("{} has no method {}", class.name, name),
+            );
+            return None;
+        };
+        let access = (method.is_static, method.visibility);
+        self.check_access(line, class, name, through_class, access);
+        Some((method.params.clone(), method.ret.clone()))
+    }
+
+    /// Checks arguments against parameters: one error for a wrong count,
+    /// otherwise one per argument of the wrong type.
+    fn args(&mut self, line: usize, params: Option<&[Type]>, args: &[Expr]) {
+        let tys: Vec<Ty> = args.iter().map(|a| self.expr(a)).collect();
+        let Some(params) = params else {
+            return;
+        };
+        if params.len() != args.len() {
+            self.type_error(
+                line,
+                ErrorKind::BadArgCount,
+                format!("expected {} arguments, found {}", params.len(), args.len()),
+            );
+            return;
+        }
+        for ((param, arg), ty) in params.iter().zip(args).zip(&tys) {
+            self.expect(arg.line, &Ty::from(param), ty, "the argument");
+        }
+    }
+}
+
+/// Whether `e` names a place a value can be stored in.
+fn is_place(e: &Expr) -> bool {
+    matches!(
+        e.kind,
+        ExprKind::Ident(_) | ExprKind::Member(..) | ExprKind::Index(..)
+    )
+}
+
+/// Whether every way through `stmts` ends in a `return`.
+fn returns(stmts: &[Stmt]) -> bool {
+    stmts.iter().any(|s| match &s.kind {
+        StmtKind::Return(_) => true,
+        StmtKind::Block(body) => returns(body),
+        StmtKind::If {
+            then,
+            otherwise: Some(otherwise),
+            ..
+        } => returns(std::slice::from_ref(then)) && returns(std::slice::from_ref(otherwise)),
+        _ => false,
+    })
+}
@@ -0,0 +1,184 @@
+//! The three semantic passes exercised by `sem_tests.rs`.
+//!
+//! * [`declare`] (`SymbolTable`) records every class with its fields,
+//!   methods and constructor, and checks the names of constructors and of
+//!   the closing `main` function.
+//! * [`resolve`] (`SymbolInner`) walks method bodies and initializers with
+//!   block scopes and checks that every name refers to something that may be
+//!   used there: no undeclared or redeclared variables, no `this` or
+//!   instance members in a static context, no unknown members after `.`.
+//! * [`typecheck`] (`TypeCheck`) checks the type of every expression and
+//!   statement.
+//!
+//! Each pass assumes the ones before it found nothing wrong. Anything that
+//! cannot be resolved gets an unknown type that is compatible with every
+//! other, so a single mistake is reported once.
+
+mod body;
+mod table;
+mod types;
+
+use std::fmt;
+
+use crate::ast::Program;
+
+pub use table::{declare, ClassInfo, FieldInfo, MethodInfo, Table};
+pub use types::Ty;
+
+/// What is wrong, using the kinds of the corpus annotations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorKind {
+    DuplicateDeclaration,
+    InvalidConstructor,
+    MissingMain,
+    UndeclaredIdentifier,
+    UnknownType,
+    UnknownMember,
+    StaticContext,
+    InvalidThis,
+    PrivateAccess,
+    MisplacedBreak,
+    TypeMismatch,
+    BadArgCount,
+    MissingReturn,
+}
+
+impl ErrorKind {
+    /// The annotation spelling, e.g. `type-mismatch`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::DuplicateDeclaration => "duplicate-declaration",
+            ErrorKind::InvalidConstructor => "invalid-constructor",
+            ErrorKind::MissingMain => "missing-main",
+            ErrorKind::UndeclaredIdentifier => "undeclared-identifier",
+            ErrorKind::UnknownType => "unknown-type",
+            ErrorKind::UnknownMember => "unknown-member",
+            ErrorKind::StaticContext => "static-context",
+            ErrorKind::InvalidThis => "invalid-this",
+            ErrorKind::PrivateAccess => "private-access",
+            ErrorKind::MisplacedBreak => "misplaced-break",
+            ErrorKind::TypeMismatch => "type-mismatch",
+            ErrorKind::BadArgCount => "bad-arg-count",
+            ErrorKind::MissingReturn => "missing-return",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error found by one of the semantic passes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticError {
+    pub line: usize,
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl SemanticError {
+    fn new(line: usize, kind: ErrorKind, message: impl Into<String>) -> SemanticError {
+        SemanticError {
+            line,
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} [{}]", self.line, self.message, self.kind)
+    }
+}
+
+/// Checks names inside method bodies and initializers.
+pub fn resolve(program: &Program, table: &Table) -> Vec<SemanticError> {
+    body::check(program, table, body::Mode::Resolve)
+}
+
+/// Checks types inside method bodies and initializers.
+pub fn typecheck(program: &Program, table: &Table) -> Vec<SemanticError> {
+    body::check(program, table, body::Mode::TypeCheck)
+}
+
+/// Runs the passes in order and returns the errors of the first one that
+/// finds any.
+pub fn analyze(program: &Program) -> Result<Table, Vec<SemanticError>> {
+    let (table, errors) = declare(program);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    for pass in [resolve, typecheck] {
+        let errors = pass(program, &table);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(usize, ErrorKind)> {
+        let program = crate::parse(source).unwrap();
+        match analyze(&program) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|e| (e.line, e.kind)).collect(),
+        }
+    }
+
+    #[test]
+    fn private_members_stay_in_their_class() {
+        let source = "class A {\n    private int x;\n    public int y() { return x; }\n}\n\
+                      void main(){\n    A a = new A();\n    int z = a.x;\n}";
+        assert_eq!(kinds(source), vec![(7, ErrorKind::PrivateAccess)]);
+    }
+
+    #[test]
+    fn break_needs_a_loop_or_switch() {
+        let source = "void main(){\n    while (true) { break; }\n    break;\n}";
+        assert_eq!(kinds(source), vec![(3, ErrorKind::MisplacedBreak)]);
+    }
+
+    #[test]
+    fn unknown_types_do_not_cascade() {
+        let source = "void main(){\n    Nope n = new Nope();\n    n.x = 1;\n}";
+        assert_eq!(
+            kinds(source),
+            vec![(2, ErrorKind::UnknownType), (2, ErrorKind::UnknownType)]
+        );
+    }
+
+    #[test]
+    fn both_branches_of_an_if_may_return() {
+        let source = "class A {\n    public int f(bool b) {\n        if (b) { return 1; } else { return 2; }\n    }\n\
+                      public int g(bool b) {\n        if (b) { return 1; }\n    }\n}\nvoid main(){}";
+        assert_eq!(kinds(source), vec![(5, ErrorKind::MissingReturn)]);
+    }
+
+    #[test]
+    fn class_names_only_come_before_a_dot() {
+        let source =
+            "class A {\n    static public int x;\n    static public int f() { return 1; }\n}\n\
+                      void main(){\n    A.x = A.f();\n    A;\n    int y = A + 1;\n}";
+        assert_eq!(
+            kinds(source),
+            vec![
+                (7, ErrorKind::UndeclaredIdentifier),
+                (8, ErrorKind::UndeclaredIdentifier)
+            ]
+        );
+    }
+
+    #[test]
+    fn null_fits_classes_and_arrays_only() {
+        let source =
+            "class A {}\nvoid main(){\n    A a = null;\n    int[] b = null;\n    int c = null;\n}";
+        assert_eq!(kinds(source), vec![(5, ErrorKind::TypeMismatch)]);
+    }
+}
@@ -0,0 +1,146 @@
+//! The declaration pass and the symbol table it builds.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::ast::{Member, Program, Type, Visibility};
+
+use super::{ErrorKind, SemanticError};
+
+/// Every class in the program and what it declares.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Table {
+    pub classes: BTreeMap<String, ClassInfo>,
+    /// Name of the closing function, `main` in a correct program.
+    pub main: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassInfo {
+    pub name: String,
+    pub line: usize,
+    pub fields: BTreeMap<String, FieldInfo>,
+    pub methods: BTreeMap<String, MethodInfo>,
+    /// Parameter types of the constructor. A class without one is built
+    /// with `new C()`.
+    pub constructor: Option<Vec<Type>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub ty: Type,
+    pub is_static: bool,
+    pub visibility: Visibility,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodInfo {
+    pub ret: Type,
+    pub params: Vec<Type>,
+    pub is_static: bool,
+    pub visibility: Visibility,
+}
+
+impl Table {
+    pub fn class(&self, name: &str) -> Option<&ClassInfo> {
+        self.classes.get(name)
+    }
+}
+
+/// Records every class and member. The first of two declarations with the
+/// same name is kept, the second is reported.
+///
+/// Fields and methods share one namespace, constructors are separate: a
+/// class may have a method named after itself but only one constructor.
+pub fn declare(program: &Program) -> (Table, Vec<SemanticError>) {
+    let mut table = Table {
+        classes: BTreeMap::new(),
+        main: program.main.name.clone(),
+    };
+    let mut errors = Vec::new();
+
+    for class in &program.classes {
+        if table.classes.contains_key(&class.name) {
+            errors.push(SemanticError::new(
+                class.line,
+                ErrorKind::DuplicateDeclaration,
+                format!("class {} is already declared", class.name),
+            ));
+            continue;
+        }
+        let mut info = ClassInfo {
+            name: class.name.clone(),
+            line: class.line,
+            ..ClassInfo::default()
+        };
+        let mut names = HashSet::new();
+        for member in &class.members {
+            if let Member::Constructor(c) = member {
+                if c.name != class.name {
+                    errors.push(SemanticError::new(
+                        c.line,
+                        ErrorKind::InvalidConstructor,
+                        format!("constructor {} does not match class {}", c.name, class.name),
+                    ));
+                } else if info.constructor.is_some() {
+                    errors.push(SemanticError::new(
+                        c.line,
+                        ErrorKind::DuplicateDeclaration,
+                        format!("{} already has a constructor", class.name),
+                    ));
+                } else {
+                    info.constructor = Some(c.params.iter().map(|p| p.ty.clone()).collect());
+                }
+                continue;
+            }
+            if !names.insert(member.name()) {
+                errors.push(SemanticError::new(
+                    member.line(),
+                    ErrorKind::DuplicateDeclaration,
+                    format!("{}.{} is already declared", class.name, member.name()),
+                ));
+                continue;
+            }
+            match member {
+                Member::Field(f) => {
+                    info.fields.insert(
+                        f.name.clone(),
+                        FieldInfo {
+                            ty: f.ty.clone(),
+                            is_static: f.modifiers.is_static,
+                            visibility: f.modifiers.visibility,
+                        },
+                    );
+                }
+                Member::Method(m) => {
+                    info.methods.insert(
+                        m.name.clone(),
+                        MethodInfo {
+                            ret: m.ret.clone(),
+                            params: m.params.iter().map(|p| p.ty.clone()).collect(),
+                            is_static: m.modifiers.is_static,
+                            visibility: m.modifiers.visibility,
+                        },
+                    );
+                }
+                Member::Constructor(_) => unreachable!(),
+            }
+        }
+        table.classes.insert(class.name.clone(), info);
+    }
+
+    let main = &program.main;
+    if main.name != "main" {
+        errors.push(SemanticError::new(
+            main.line,
+            ErrorKind::MissingMain,
+            format!("the program must end with void main(), not {}()", main.name),
+        ));
+    } else if table.classes.contains_key("main") {
+        errors.push(SemanticError::new(
+            main.line,
+            ErrorKind::DuplicateDeclaration,
+            "main is already declared as a class",
+        ));
+    }
+    (table, errors)
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+
+use crate::ast::{Literal, Type};
+
+/// The type of an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ty {
+    Void,
+    Int,
+    Char,
+    Bool,
+    String,
+    /// An instance of a class.
+    Class(String),
+    Array(Box<Ty>),
+    /// The type of `null`.
+    Null,
+    /// A class name used as the receiver of a static member, as in
+    /// `MyClass.x`.
+    Static(String),
+    /// Something that could not be resolved. Compatible with everything so
+    /// the original error is the only one reported.
+    Unknown,
+}
+
+impl Ty {
+    pub fn of_literal(literal: &Literal) -> Ty {
+        match literal {
+            Literal::Int(_) => Ty::Int,
+            Literal::Char(_) => Ty::Char,
+            Literal::Str(_) => Ty::String,
+            Literal::Bool(_) => Ty::Bool,
+            Literal::Null => Ty::Null,
+        }
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        *self == Ty::Unknown
+    }
+
+    /// Whether a value of type `value` may be stored where `self` is
+    /// expected.
+    pub fn accepts(&self, value: &Ty) -> bool {
+        match (self, value) {
+            (Ty::Unknown, _) | (_, Ty::Unknown) => true,
+            (Ty::Class(_) | Ty::Array(_), Ty::Null) => true,
+            (Ty::Static(_), _) | (_, Ty::Static(_)) => false,
+            (t, v) => t == v,
+        }
+    }
+
+    /// Whether `==` and `!=` may compare the two.
+    pub fn comparable(&self, other: &Ty) -> bool {
+        self.accepts(other) || other.accepts(self)
+    }
+}
+
+impl From<&Type> for Ty {
+    fn from(ty: &Type) -> Ty {
+        match ty {
+            Type::Void => Ty::Void,
+            Type::Int => Ty::Int,
+            Type::Char => Ty::Char,
+            Type::Bool => Ty::Bool,
+            Type::String => Ty::String,
+            Type::Class(name) => Ty::Class(name.clone()),
+            Type::Array(elem) => Ty::Array(Box::new(Ty::from(&**elem))),
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Void => f.write_str("void"),
+            Ty::Int => f.write_str("int"),
+            Ty::Char => f.write_str("char"),
+            Ty::Bool => f.write_str("bool"),
+            Ty::String => f.write_str("string"),
+            Ty::Class(name) => f.write_str(name),
+            Ty::Array(elem) => write!(f, "{}[]", elem),
+            Ty::Null => f.write_str("null"),
+            Ty::Static(name) => write!(f, "class {}", name),
+            Ty::Unknown => f.write_str("?"),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Canonical S-expression form of the syntax tree, used by the golden
+//! `.ast` files under `valid_parse/`.
+//!
+//! ```text
+//! (program
+//!   (class MyClass
+//!     (field public static int x 4)
+//!     (method public int get (params (int a))
+//!       (return (+ x a))))
+//!   (main main
+//!     (var MyClass c (new MyClass))
+//!     (expr (call (. c get) 1))))
+//! ```
+//!
+//! Expressions are always written on one line. Declarations and statements
+//! that contain statements put each of them on a line of its own, indented
+//! by two spaces. Missing `for` clauses are written `_`.
+
+use crate::ast::*;
+
+/// A node of the printed tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sexp {
+    Atom(String),
+    /// `(a b c)` on one line.
+    List(Vec<Sexp>),
+    /// `(head...` followed by one indented line per item of the body.
+    Nested(Vec<Sexp>, Vec<Sexp>),
+}
+
+fn atom(s: impl ToString) -> Sexp {
+    Sexp::Atom(s.to_string())
+}
+
+impl Sexp {
+    /// Renders the tree, ending with a newline.
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        out.push('\n');
+        out
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        match self {
+            Sexp::Atom(a) => out.push_str(a),
+            Sexp::List(items) => {
+                out.push('(');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(' ');
+                    }
+                    item.write(out, depth);
+                }
+                out.push(')');
+            }
+            Sexp::Nested(head, body) => {
+                Sexp::List(head.clone()).write(out, depth);
+                out.pop();
+                for item in body {
+                    out.push('\n');
+                    out.push_str(&"  ".repeat(depth + 1));
+                    item.write(out, depth + 1);
+                }
+                out.push(')');
+            }
+        }
+    }
+}
+
+pub fn program(p: &Program) -> Sexp {
+    let mut body: Vec<Sexp> = p.classes.iter().map(class).collect();
+    body.push(Sexp::Nested(
+        vec![atom("main"), atom(&p.main.name)],
+        stmts(&p.main.body),
+    ));
+    Sexp::Nested(vec![atom("program")], body)
+}
+
+fn class(c: &ClassDef) -> Sexp {
+    Sexp::Nested(
+        vec![atom("class"), atom(&c.name)],
+        c.members.iter().map(member).collect(),
+    )
+}
+
+fn modifiers(m: Modifiers) -> Vec<Sexp> {
+    let mut out = vec![atom(match m.visibility {
+        Visibility::Public => "public",
+        Visibility::Private => "private",
+    })];
+    if m.is_static {
+        out.push(atom("static"));
+    }
+    out
+}
+
+fn params(ps: &[Param]) -> Sexp {
+    let mut items = vec![atom("params")];
+    items.extend(
+        ps.iter()
+            .map(|p| Sexp::List(vec![atom(&p.ty), atom(&p.name)])),
+    );
+    Sexp::List(items)
+}
+
+fn member(m: &Member) -> Sexp {
+    match m {
+        Member::Field(f) => {
+            let mut items = vec![atom("field")];
+            items.extend(modifiers(f.modifiers));
+            items.push(atom(&f.ty));
+            items.push(atom(&f.name));
+            items.extend(f.init.as_ref().map(expr));
+            Sexp::List(items)
+        }
+        Member::Method(m) => {
+            let mut head = vec![atom("method")];
+            head.extend(modifiers(m.modifiers));
+            head.push(atom(&m.ret));
+            head.push(atom(&m.name));
+            head.push(params(&m.params));
+            Sexp::Nested(head, stmts(&m.body))
+        }
+        Member::Constructor(c) => Sexp::Nested(
+            vec![atom("constructor"), atom(&c.name), params(&c.params)],
+            stmts(&c.body),
+        ),
+    }
+}
+
+fn stmts(ss: &[Stmt]) -> Vec<Sexp> {
+    ss.iter().map(stmt).collect()
+}
+
+fn optional(e: &Option<Expr>) -> Sexp {
+    e.as_ref().map_or_else(|| atom("_"), expr)
+}
+
+fn stmt(s: &Stmt) -> Sexp {
+    match &s.kind {
+        StmtKind::Block(body) => Sexp::Nested(vec![atom("block")], stmts(body)),
+        StmtKind::Var { ty, name, init } => {
+            let mut items = vec![atom("var"), atom(ty), atom(name)];
+            items.extend(init.as_ref().map(expr));
+            Sexp::List(items)
+        }
+        StmtKind::Expr(e) => Sexp::List(vec![atom("expr"), expr(e)]),
+        StmtKind::If {
+            cond,
+            then,
+            otherwise,
+        } => {
+            let mut body = vec![stmt(then)];
+            body.extend(otherwise.as_deref().map(stmt));
+            Sexp::Nested(vec![atom("if"), expr(cond)], body)
+        }
+        StmtKind::While { cond, body } => {
+            Sexp::Nested(vec![atom("while"), expr(cond)], vec![stmt(body)])
+        }
+        StmtKind::For {
+            init,
+            cond,
+            step,
+            body,
+        } => Sexp::Nested(
+            vec![atom("for"), optional(init), optional(cond), optional(step)],
+            vec![stmt(body)],
+        ),
+        StmtKind::Return(value) => {
+            let mut items = vec![atom("return")];
+            items.extend(value.as_ref().map(expr));
+            Sexp::List(items)
+        }
+        StmtKind::Cin(e) => Sexp::List(vec![atom("cin"), expr(e)]),
+        StmtKind::Cout(e) => Sexp::List(vec![atom("cout"), expr(e)]),
+        StmtKind::Switch {
+            scrutinee,
+            cases,
+            default,
+        } => {
+            let mut body: Vec<Sexp> = cases
+                .iter()
+                .map(|c| Sexp::Nested(vec![atom("case"), atom(&c.label)], stmts(&c.body)))
+                .collect();
+            body.extend(
+                default
+                    .as_ref()
+                    .map(|d| Sexp::Nested(vec![atom("default")], stmts(d))),
+            );
+            Sexp::Nested(vec![atom("switch"), expr(scrutinee)], body)
+        }
+        StmtKind::Break => Sexp::List(vec![atom("break")]),
+    }
+}
+
+pub fn expr(e: &Expr) -> Sexp {
+    match &e.kind {
+        ExprKind::Literal(l) => atom(l),
+        ExprKind::Ident(name) => atom(name),
+        ExprKind::This => atom("this"),
+        ExprKind::Unary(op, operand) => Sexp::List(vec![atom(op.as_str()), expr(operand)]),
+        ExprKind::Binary(op, lhs, rhs) => Sexp::List(vec![atom(op.as_str()), expr(lhs), expr(rhs)]),
+        ExprKind::Assign(target, value) => Sexp::List(vec![atom("="), expr(target), expr(value)]),
+        ExprKind::Member(object, name) => Sexp::List(vec![atom("."), expr(object), atom(name)]),
+        ExprKind::Call(callee, args) => {
+            let mut items = vec![atom("call"), expr(callee)];
+            items.extend(args.iter().map(expr));
+            Sexp::List(items)
+        }
+        ExprKind::Index(array, index) => Sexp::List(vec![atom("index"), expr(array), expr(index)]),
+        ExprKind::New(class, args) => {
+            let mut items = vec![atom("new"), atom(class)];
+            items.extend(args.iter().map(expr));
+            Sexp::List(items)
+        }
+        ExprKind::NewArray(elem, size) => {
+            Sexp::List(vec![atom("new-array"), atom(elem), expr(size)])
+        }
+    }
+}
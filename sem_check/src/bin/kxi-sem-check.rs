@@ -0,0 +1,51 @@
+//! Reports where `sem_tests.rs` and the `.kxi` corpus disagree.
+//!
+//! Usage: `kxi-sem-check [SEM_TESTS_RS] [CORPUS_DIR]`, defaulting to
+//! `dons_tests/sem_tests.rs` and `dons_tests`. Exits with status 1 if
+//! anything disagrees.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use kxi_harness::corpus::dons_tests_dir;
+use kxi_harness::Corpus;
+
+fn main() -> ExitCode {
+    let mut args = env::args_os().skip(1);
+    let sem_tests = args
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| dons_tests_dir().join("sem_tests.rs"));
+    let corpus_dir = args
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(dons_tests_dir);
+
+    let source = match fs::read_to_string(&sem_tests) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("kxi-sem-check: {}: {}", sem_tests.display(), e);
+            return ExitCode::from(2);
+        }
+    };
+    let corpus = match Corpus::discover(&corpus_dir) {
+        Ok(corpus) => corpus,
+        Err(e) => {
+            eprintln!("kxi-sem-check: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let issues = kxi_sem_check::check(&source, &corpus);
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    if issues.is_empty() {
+        println!("sem_tests.rs and {} agree", corpus_dir.display());
+        ExitCode::SUCCESS
+    } else {
+        println!("{} issues", issues.len());
+        ExitCode::FAILURE
+    }
+}
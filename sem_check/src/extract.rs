@@ -0,0 +1,157 @@
+//! Pulling the KXI programs and expectations out of `sem_tests.rs`.
+
+use kxi_harness::Phase;
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, ExprMethodCall, Lit, Macro, Token};
+
+/// What a test asserts about the errors of the last pass it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected {
+    /// `assert_eq!(st.errs.len(), n)`
+    Count(usize),
+    /// `assert!(!st.errs.is_empty())` or `assert!(res.is_err())`
+    AtLeastOne,
+}
+
+/// One `#[test]` function from `sem_tests.rs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Test {
+    pub name: String,
+    /// 1-based line of the `#[test]` attribute.
+    pub line: usize,
+    /// The program handed to `tok_gen`.
+    pub source: Option<String>,
+    /// The last pass the test runs.
+    pub phase: Phase,
+    pub expected: Option<Expected>,
+}
+
+/// A `#[test]` function that is not valid Rust.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unparseable {
+    /// The function name if it could be found, else `?`.
+    pub name: String,
+    pub line: usize,
+    pub error: String,
+}
+
+/// Splits `file` at every `#[test]` attribute and parses each function on
+/// its own, so one broken test does not hide all the others.
+pub fn extract(file: &str) -> (Vec<Test>, Vec<Unparseable>) {
+    let starts: Vec<usize> = file.match_indices("#[test]").map(|(i, _)| i).collect();
+    let mut tests = Vec::new();
+    let mut broken = Vec::new();
+    for (idx, &start) in starts.iter().enumerate() {
+        let end = starts.get(idx + 1).copied().unwrap_or(file.len());
+        let chunk = &file[start..end];
+        let line = file[..start].matches('\n').count() + 1;
+        match syn::parse_str::<syn::ItemFn>(chunk) {
+            Ok(item) => {
+                let mut finder = Finder::default();
+                finder.visit_item_fn(&item);
+                tests.push(Test {
+                    name: item.sig.ident.to_string(),
+                    line,
+                    source: finder.source,
+                    phase: finder.phase,
+                    expected: finder.expected,
+                });
+            }
+            Err(e) => broken.push(Unparseable {
+                name: fn_name(chunk).unwrap_or("?").to_string(),
+                line: line + e.span().start().line - 1,
+                error: e.to_string(),
+            }),
+        }
+    }
+    (tests, broken)
+}
+
+/// Finds the name of a function by text alone, for chunks syn rejects.
+fn fn_name(chunk: &str) -> Option<&str> {
+    let rest = &chunk[chunk.find("fn ")? + 3..];
+    let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    Some(&rest[..end])
+}
+
+struct Finder {
+    source: Option<String>,
+    /// Tests that never build a symbol table only exercise the parser.
+    phase: Phase,
+    expected: Option<Expected>,
+}
+
+impl Default for Finder {
+    fn default() -> Finder {
+        Finder {
+            source: None,
+            phase: Phase::Parse,
+            expected: None,
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Finder {
+    fn visit_expr_call(&mut self, call: &'ast ExprCall) {
+        if path_ends_with(&call.func, "tok_gen") {
+            if let Some(Expr::Lit(lit)) = call.args.first() {
+                if let Lit::Str(s) = &lit.lit {
+                    self.source = Some(s.value());
+                }
+            }
+        }
+        visit::visit_expr_call(self, call);
+    }
+
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        let phase = path
+            .segments
+            .iter()
+            .find_map(|seg| match seg.ident.to_string().as_str() {
+                "SymbolTable" => Some(Phase::Declare),
+                "SymbolInner" => Some(Phase::Resolve),
+                "TypeCheck" => Some(Phase::TypeCheck),
+                _ => None,
+            });
+        if let Some(phase) = phase {
+            self.phase = self.phase.max(phase);
+        }
+        visit::visit_path(self, path);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        let args = mac
+            .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+            .unwrap_or_default();
+        let args: Vec<&Expr> = args.iter().collect();
+        if mac.path.is_ident("assert_eq") {
+            if let [left, Expr::Lit(lit), ..] = args[..] {
+                if let (true, Lit::Int(n)) = (is_method(left, "len"), &lit.lit) {
+                    self.expected = n.base10_parse().ok().map(Expected::Count);
+                }
+            }
+        } else if mac.path.is_ident("assert") {
+            match args.first() {
+                Some(Expr::Unary(not)) if is_method(&not.expr, "is_empty") => {
+                    self.expected = Some(Expected::AtLeastOne)
+                }
+                Some(e) if is_method(e, "is_err") => self.expected = Some(Expected::AtLeastOne),
+                Some(e) if is_method(e, "is_ok") => self.expected = Some(Expected::Count(0)),
+                _ => {}
+            }
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+fn path_ends_with(expr: &Expr, name: &str) -> bool {
+    match expr {
+        Expr::Path(p) => p.path.segments.last().is_some_and(|s| s.ident == name),
+        _ => false,
+    }
+}
+
+fn is_method(expr: &Expr, name: &str) -> bool {
+    matches!(expr, Expr::MethodCall(ExprMethodCall { method, .. }) if method == name)
+}
@@ -0,0 +1,264 @@
+//! Consistency check between `dons_tests/sem_tests.rs` and the `.kxi`
+//! corpus.
+//!
+//! The corpus was made by copying the string literals out of `sem_tests.rs`
+//! by hand, and the two have drifted apart since. [`check`] pairs every
+//! test with the corpus file holding the same program and reports whatever
+//! does not line up: tests without a file, files without a test, programs
+//! that differ, and expectations that disagree with the file's directory or
+//! header.
+
+use std::fmt;
+
+use kxi_harness::{strip_expectations, Corpus, Expect, Phase, TestCase};
+
+pub mod extract;
+
+pub use extract::{extract, Expected, Test, Unparseable};
+
+/// One disagreement between `sem_tests.rs` and the corpus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// A test that syn could not parse.
+    Unparseable(Unparseable),
+    /// A test whose program is in no corpus file.
+    MissingFile { test: String, line: usize },
+    /// A corpus file no test corresponds to.
+    MissingTest { file: String },
+    /// A test and the file of the same name hold different programs.
+    SourceMismatch {
+        test: String,
+        file: String,
+        /// First differing line, as `(test, file)`.
+        first_difference: (String, String),
+    },
+    /// The test expects errors from a program in `valid_kxi`, or none from a
+    /// program in `invalid_kxi`.
+    Misclassified {
+        test: String,
+        file: String,
+        expected: Expected,
+    },
+    /// The test runs a different set of passes than the file's header asks
+    /// for.
+    PhaseMismatch {
+        test: String,
+        file: String,
+        test_phase: Phase,
+        file_phase: Option<Phase>,
+    },
+    /// The test and the file's header expect a different number of errors.
+    CountMismatch {
+        test: String,
+        file: String,
+        test_count: usize,
+        file_count: usize,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Unparseable(u) => write!(
+                f,
+                "sem_tests.rs:{}: {} is not valid Rust: {}",
+                u.line, u.name, u.error
+            ),
+            Issue::MissingFile { test, line } => {
+                write!(f, "sem_tests.rs:{}: {} has no .kxi file", line, test)
+            }
+            Issue::MissingTest { file } => write!(f, "{}: no test in sem_tests.rs", file),
+            Issue::SourceMismatch {
+                test,
+                file,
+                first_difference: (t, k),
+            } => write!(
+                f,
+                "{}: program differs from {}: test has `{}`, file has `{}`",
+                file, test, t, k
+            ),
+            Issue::Misclassified {
+                test,
+                file,
+                expected,
+            } => {
+                let expects = match expected {
+                    Expected::Count(0) => "no errors".to_string(),
+                    Expected::Count(n) => format!("{} errors", n),
+                    Expected::AtLeastOne => "errors".to_string(),
+                };
+                write!(f, "{}: {} expects {} from it", file, test, expects)
+            }
+            Issue::PhaseMismatch {
+                test,
+                file,
+                test_phase,
+                file_phase,
+            } => write!(
+                f,
+                "{}: {} runs through {}, the header says {}",
+                file,
+                test,
+                test_phase,
+                file_phase.map_or("nothing".to_string(), |p| p.to_string())
+            ),
+            Issue::CountMismatch {
+                test,
+                file,
+                test_count,
+                file_count,
+            } => write!(
+                f,
+                "{}: {} expects {} errors, the header says {}",
+                file, test, test_count, file_count
+            ),
+        }
+    }
+}
+
+/// Compares the tests in `sem_tests` (the contents of `sem_tests.rs`) with
+/// the files in `corpus`.
+///
+/// A test is paired with the file holding the same program, ignoring
+/// whitespace and the harness's comments. A test whose program matches no
+/// file is paired by name instead (`test_scope_out` with `scope_out.kxi`),
+/// and any difference between the two programs is reported. Tests that are
+/// not valid Rust are reported, and paired by name so that their files are
+/// not reported as well.
+pub fn check(sem_tests: &str, corpus: &Corpus) -> Vec<Issue> {
+    let (tests, broken) = extract(sem_tests);
+    let programs: Vec<String> = corpus
+        .cases
+        .iter()
+        .map(|c| normalize(&strip_expectations(&c.source)))
+        .collect();
+    let mut paired = vec![false; corpus.len()];
+    let by_name = |name: &str, paired: &[bool]| {
+        let name = name.strip_prefix("test_").unwrap_or(name);
+        corpus
+            .cases
+            .iter()
+            .enumerate()
+            .position(|(i, c)| !paired[i] && c.name == name)
+    };
+
+    // The file of a broken test is not missing a test, it is only waiting
+    // for the test to be fixed.
+    let mut issues = Vec::new();
+    for test in broken {
+        if let Some(idx) = by_name(&test.name, &paired) {
+            paired[idx] = true;
+        }
+        issues.push(Issue::Unparseable(test));
+    }
+
+    for test in &tests {
+        let source = test.source.as_deref().map(normalize);
+        let by_source = corpus
+            .cases
+            .iter()
+            .enumerate()
+            .position(|(i, _)| !paired[i] && source.as_deref() == Some(programs[i].as_str()));
+        let Some(idx) = by_source.or_else(|| by_name(&test.name, &paired)) else {
+            issues.push(Issue::MissingFile {
+                test: test.name.clone(),
+                line: test.line,
+            });
+            continue;
+        };
+        paired[idx] = true;
+        let case = &corpus.cases[idx];
+        if by_source.is_none() {
+            issues.push(Issue::SourceMismatch {
+                test: test.name.clone(),
+                file: case.id(),
+                first_difference: first_difference(
+                    test.source.as_deref().unwrap_or_default(),
+                    &strip_expectations(&case.source),
+                ),
+            });
+        }
+        issues.extend(compare(test, case));
+    }
+
+    for (case, paired) in corpus.cases.iter().zip(paired) {
+        if !paired {
+            issues.push(Issue::MissingTest { file: case.id() });
+        }
+    }
+    issues
+}
+
+/// Checks a test's expectations against its file's directory and header.
+///
+/// A test may run fewer passes than the header names: a valid program that
+/// gets through every pass also gets through the first two, and an invalid
+/// program rejected by type checking rightly has no errors after resolve.
+fn compare(test: &Test, case: &TestCase) -> Option<Issue> {
+    let expected = test.expected?;
+    let file_phase = case.header.phase;
+    let last = file_phase.unwrap_or(Phase::TypeCheck);
+    let misclassified = match (case.expect, expected) {
+        (Expect::Accept, Expected::Count(n)) => n > 0,
+        (Expect::Accept, Expected::AtLeastOne) => true,
+        (Expect::Reject, Expected::Count(0)) => test.phase >= last,
+        (Expect::Reject, _) => false,
+    };
+    if misclassified {
+        return Some(Issue::Misclassified {
+            test: test.name.clone(),
+            file: case.id(),
+            expected,
+        });
+    }
+    let phase_ok = match (case.expect, expected) {
+        (Expect::Reject, Expected::Count(0)) | (Expect::Accept, _) => test.phase <= last,
+        (Expect::Reject, _) => test.phase == last,
+    };
+    if !phase_ok {
+        return Some(Issue::PhaseMismatch {
+            test: test.name.clone(),
+            file: case.id(),
+            test_phase: test.phase,
+            file_phase,
+        });
+    }
+    match expected {
+        Expected::Count(test_count) if case.expect == Expect::Reject && test_count > 0 => {
+            let file_count = case.header.errors.unwrap_or(case.annotations.len());
+            (test_count != file_count).then(|| Issue::CountMismatch {
+                test: test.name.clone(),
+                file: case.id(),
+                test_count,
+                file_count,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Collapses all whitespace, which KXI does not care about.
+fn normalize(source: &str) -> String {
+    source.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The first pair of non-blank lines that differ, ignoring indentation.
+fn first_difference(test: &str, file: &str) -> (String, String) {
+    let lines = |s: &str| -> Vec<String> {
+        s.lines()
+            .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|l| !l.is_empty())
+            .collect()
+    };
+    let (test, file) = (lines(test), lines(file));
+    let len = test.len().max(file.len());
+    (0..len)
+        .map(|i| {
+            (
+                test.get(i).cloned().unwrap_or_default(),
+                file.get(i).cloned().unwrap_or_default(),
+            )
+        })
+        .find(|(t, f)| t != f)
+        .unwrap_or_default()
+}
@@ -0,0 +1,213 @@
+#[path = "../../harness/tests/common/mod.rs"]
+mod common;
+
+use std::fs;
+
+use common::Scratch;
+use kxi_harness::corpus::dons_tests_dir;
+use kxi_harness::{Corpus, Phase};
+use kxi_sem_check::{check, extract, Expected, Issue};
+
+fn corpus(scratch: &Scratch) -> Corpus {
+    Corpus::discover(scratch.root()).unwrap()
+}
+
+fn typecheck_test(name: &str, source: &str, errors: usize) -> String {
+    format!(
+        "#[test]\nfn {}() {{\n    let parser = kxi::CompUnitParser::new();\n    \
+         let mut res = parser.parse(tok_gen(r\"{}\")).unwrap();\n    \
+         let mut st = SymbolTable::default();\n    res.accept(&mut st);\n    \
+         let mut st = SymbolInner::new(st.table, st.c_scopes);\n    res.accept(&mut st);\n    \
+         let mut st = TypeCheck::new(st.table);\n    res.accept(&mut st);\n    \
+         assert_eq!(st.errs.len(), {});\n}}\n",
+        name, source, errors
+    )
+}
+
+#[test]
+fn finds_the_broken_tests_in_sem_tests_rs() {
+    let dir = dons_tests_dir();
+    let sem_tests = fs::read_to_string(dir.join("sem_tests.rs")).unwrap();
+    let issues = check(&sem_tests, &Corpus::dons_tests().unwrap());
+    let report: Vec<String> = issues.iter().map(ToString::to_string).collect();
+    let unparseable: Vec<&str> = issues
+        .iter()
+        .filter_map(|i| match i {
+            Issue::Unparseable(u) => Some(u.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    // Their programs hold string literals in plain `"..."` strings.
+    for name in [
+        "test_double_dec_parm",
+        "bad_dec_type_check",
+        "io_type_check",
+    ] {
+        assert!(unparseable.contains(&name), "{}", report.join("\n"));
+    }
+    // Everything else has been migrated, apart from tests still missing a
+    // file and programs added to the corpus after sem_tests.rs.
+    let added = |file: &str| file == "invalid_kxi/class_as_value.kxi";
+    assert!(
+        issues
+            .iter()
+            .any(|i| matches!(i, Issue::MissingTest { file } if added(file))),
+        "{}",
+        report.join("\n")
+    );
+    assert!(
+        issues.iter().all(|i| match i {
+            Issue::Unparseable(_) | Issue::MissingFile { .. } => true,
+            Issue::MissingTest { file } => added(file),
+            _ => false,
+        }),
+        "{}",
+        report.join("\n")
+    );
+}
+
+#[test]
+fn extracts_program_phase_and_expectation() {
+    let (tests, broken) = extract(&typecheck_test("test_x", "void main(){}", 2));
+    assert!(broken.is_empty());
+    assert_eq!(tests.len(), 1);
+    assert_eq!(tests[0].name, "test_x");
+    assert_eq!(tests[0].line, 1);
+    assert_eq!(tests[0].source.as_deref(), Some("void main(){}"));
+    assert_eq!(tests[0].phase, Phase::TypeCheck);
+    assert_eq!(tests[0].expected, Some(Expected::Count(2)));
+}
+
+#[test]
+fn parser_tests_stop_at_parse() {
+    let file =
+        "#[test]\nfn test_p() {\n    let res = parser.parse(tok_gen(\"void main(){\"));\n    \
+                assert!(res.is_err());\n}\n";
+    let (tests, _) = extract(file);
+    assert_eq!(tests[0].phase, Phase::Parse);
+    assert_eq!(tests[0].expected, Some(Expected::AtLeastOne));
+}
+
+#[test]
+fn one_broken_test_does_not_hide_the_rest() {
+    let file = format!(
+        "#[test]\nfn test_bad() {{\n    tok_gen(\"string s = \"x\";\");\n}}\n{}",
+        typecheck_test("test_good", "void main(){}", 0)
+    );
+    let (tests, broken) = extract(&file);
+    assert_eq!(tests.len(), 1);
+    assert_eq!(broken.len(), 1);
+    assert_eq!(broken[0].name, "test_bad");
+    assert_eq!(broken[0].line, 3);
+}
+
+#[test]
+fn broken_tests_keep_their_files() {
+    let scratch = Scratch::new("broken");
+    scratch.file("valid_kxi/bad.kxi", "void main(){ string s = \"x\"; }\n");
+    let file = "#[test]\nfn test_bad() {\n    tok_gen(\"void main(){ string s = \"x\"; }\");\n}\n";
+    let issues = check(file, &corpus(&scratch));
+    assert_eq!(issues.len(), 1);
+    assert!(
+        matches!(&issues[0], Issue::Unparseable(u) if u.name == "test_bad"),
+        "{:?}",
+        issues
+    );
+}
+
+#[test]
+fn reports_tests_and_files_without_a_partner() {
+    let scratch = Scratch::new("unpaired");
+    scratch.file("valid_kxi/lonely.kxi", "void main(){ int x; }\n");
+    let issues = check(
+        &typecheck_test("test_orphan", "void main(){}", 0),
+        &corpus(&scratch),
+    );
+    assert_eq!(
+        issues,
+        vec![
+            Issue::MissingFile {
+                test: "test_orphan".into(),
+                line: 1
+            },
+            Issue::MissingTest {
+                file: "valid_kxi/lonely.kxi".into()
+            },
+        ]
+    );
+}
+
+#[test]
+fn pairs_by_name_when_the_programs_differ() {
+    let scratch = Scratch::new("by_name");
+    scratch.file("valid_kxi/scope.kxi", "void main(){\n    int y;\n}\n");
+    let issues = check(
+        &typecheck_test("test_scope", "void main(){\n    int x;\n}", 0),
+        &corpus(&scratch),
+    );
+    assert_eq!(
+        issues,
+        vec![Issue::SourceMismatch {
+            test: "test_scope".into(),
+            file: "valid_kxi/scope.kxi".into(),
+            first_difference: ("int x;".into(), "int y;".into()),
+        }]
+    );
+}
+
+#[test]
+fn reports_disagreeing_expectations() {
+    let scratch = Scratch::new("expectations");
+    scratch.file("valid_kxi/ok.kxi", "void main(){ int a; }\n");
+    scratch.file(
+        "invalid_kxi/early.kxi",
+        "// expect: phase=declare errors=1\nvoid main(){ int b; }\n",
+    );
+    scratch.file(
+        "invalid_kxi/count.kxi",
+        "// expect: phase=typecheck errors=3\nvoid main(){ int c; }\n",
+    );
+    let sem_tests = [
+        typecheck_test("test_ok", "void main(){ int a; }", 1),
+        typecheck_test("test_early", "void main(){ int b; }", 1),
+        typecheck_test("test_count", "void main(){ int c; }", 2),
+    ]
+    .concat();
+    let issues = check(&sem_tests, &corpus(&scratch));
+    assert_eq!(
+        issues,
+        vec![
+            Issue::Misclassified {
+                test: "test_ok".into(),
+                file: "valid_kxi/ok.kxi".into(),
+                expected: Expected::Count(1),
+            },
+            Issue::PhaseMismatch {
+                test: "test_early".into(),
+                file: "invalid_kxi/early.kxi".into(),
+                test_phase: Phase::TypeCheck,
+                file_phase: Some(Phase::Declare),
+            },
+            Issue::CountMismatch {
+                test: "test_count".into(),
+                file: "invalid_kxi/count.kxi".into(),
+                test_count: 2,
+                file_count: 3,
+            },
+        ]
+    );
+}
+
+#[test]
+fn stopping_before_the_rejecting_pass_is_consistent() {
+    let scratch = Scratch::new("stops_early");
+    scratch.file(
+        "invalid_kxi/late.kxi",
+        "// expect: phase=typecheck errors=1\nvoid main(){ int d = true; } // error: type-mismatch\n",
+    );
+    let sem_tests = typecheck_test("test_late", "void main(){ int d = true; }", 0).replace(
+        "    let mut st = TypeCheck::new(st.table);\n    res.accept(&mut st);\n",
+        "",
+    );
+    assert_eq!(check(&sem_tests, &corpus(&scratch)), vec![]);
+}
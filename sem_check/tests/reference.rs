@@ -0,0 +1,36 @@
+//! Runs every test in `sem_tests.rs` against the reference front end.
+
+use std::fs;
+
+use kxi_harness::corpus::dons_tests_dir;
+use kxi_harness::reference;
+use kxi_sem_check::{extract, Expected};
+
+#[test]
+fn reference_passes_every_test_in_sem_tests_rs() {
+    let file = fs::read_to_string(dons_tests_dir().join("sem_tests.rs")).unwrap();
+    // Tests that are not valid Rust are kxi-sem-check's to report.
+    let (tests, _) = extract(&file);
+    assert!(!tests.is_empty());
+    let mut failures = Vec::new();
+    for test in &tests {
+        let (Some(source), Some(expected)) = (&test.source, test.expected) else {
+            continue;
+        };
+        let errors = reference()
+            .run_through(source, test.phase)
+            .diagnostics
+            .len();
+        let ok = match expected {
+            Expected::Count(n) => errors == n,
+            Expected::AtLeastOne => errors > 0,
+        };
+        if !ok {
+            failures.push(format!(
+                "{}: expected {:?} after {}, the reference reports {}",
+                test.name, expected, test.phase, errors
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}